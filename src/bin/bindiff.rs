@@ -0,0 +1,94 @@
+/// バイナリ差分 - 2つのビルド間で移動・変更された関数を表示
+///
+/// Usage:
+///   bindiff old.exe new.exe
+///   bindiff old.exe new.exe --status changed --page 1
+///   bindiff old.exe new.exe --json
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use ghidra_mcp::decompiler_prototype::{diff_binaries, DiffStatus};
+use ghidra_mcp::hierarchical_analyzer::HierarchicalAnalyzer;
+
+#[derive(Parser)]
+#[command(name = "bindiff")]
+#[command(about = "Match functions between two builds of an executable", long_about = None)]
+struct Cli {
+    /// Old build
+    old: String,
+
+    /// New build
+    new: String,
+
+    /// Only show entries with this status (unchanged, moved, changed, added, removed)
+    #[arg(short, long)]
+    status: Option<String>,
+
+    /// Page number (0-based)
+    #[arg(short, long, default_value = "0")]
+    page: usize,
+
+    /// Entries per page
+    #[arg(long, default_value = "50")]
+    page_size: usize,
+
+    /// Maximum instructions per function
+    #[arg(long, default_value = "1000")]
+    max_instructions: usize,
+
+    /// Print the page as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let status = match &cli.status {
+        Some(name) => Some(DiffStatus::parse(name).ok_or_else(|| anyhow!("Unknown status: {}", name))?),
+        None => None,
+    };
+
+    let mut analyzer = HierarchicalAnalyzer::new();
+    let old_functions = analyzer.function_entry_points(&cli.old)?;
+    let new_functions = analyzer.function_entry_points(&cli.new)?;
+    let old_data = std::fs::read(&cli.old)?;
+    let new_data = std::fs::read(&cli.new)?;
+
+    let diff = diff_binaries(&old_data, &old_functions, &new_data, &new_functions, cli.max_instructions)?;
+    let page = diff.page(cli.page, cli.page_size, status);
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&page)?);
+        return Ok(());
+    }
+
+    let summary = &page.summary;
+    println!(
+        "unchanged: {}  moved: {}  changed: {}  added: {}  removed: {}",
+        summary.unchanged, summary.moved, summary.changed, summary.added, summary.removed
+    );
+    println!("{}", "=".repeat(80));
+
+    let format_address = |address: Option<u64>| address.map(|a| format!("0x{:016X}", a)).unwrap_or_else(|| "-".repeat(18));
+    for entry in &page.entries {
+        let kind = entry.match_kind.map(|kind| format!("{:?}", kind)).unwrap_or_default();
+        println!(
+            "{:10} {} -> {}  {:10} {:.2}",
+            format!("{:?}", entry.status).to_lowercase(),
+            format_address(entry.old_address),
+            format_address(entry.new_address),
+            kind,
+            entry.similarity
+        );
+    }
+
+    println!("{}", "=".repeat(80));
+    println!(
+        "page {} ({} of {} entries)",
+        page.page,
+        page.entries.len(),
+        page.total_count
+    );
+
+    Ok(())
+}
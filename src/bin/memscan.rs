@@ -4,10 +4,21 @@
 ///   memscan --process <name or PID>
 ///   memscan -p aces.exe --string "Player"
 ///   memscan -p 1234 --int 100 --float 1.5
+///   memscan -p aces.exe new --type i32 exact 100
+///   memscan -p aces.exe next decreased
+///   memscan -p aces.exe results
+///   memscan -p aces.exe pointer-scan 0x1F2E3D4C5B0 --out hp.json
+///   memscan -p aces.exe pointer-rescan hp.json 0x2A0B1C2D3E0
+///   memscan -p aces.exe freeze 0x1F2E3D4C5B0 100 --type f32
+///   memscan -p aces.exe watch 0x1F2E3D4C5B0:f32 0x1F2E3D4C5C0
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use ghidra_mcp::memory_scanner::MemoryScanner;
+use ghidra_mcp::memory_scanner::{MemoryScanner, ScanFilter, ScanSession, ScanValue, ScanValueType};
+use ghidra_mcp::memory_watch::{ValueFreezer, WatchList};
+use ghidra_mcp::pointer_scanner::{rescan_live, PointerChain, PointerMap, PointerScanOptions, PointerScanResults};
+use ghidra_mcp::signature::Signature;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Parser)]
 #[command(name = "memscan")]
@@ -59,6 +70,151 @@ enum Commands {
         pattern: String,
     },
 
+    /// Scan for many signatures in one pass
+    /// File format: one pattern per line, optionally "name = pattern"; '#' starts a comment
+    Signatures {
+        /// Signature list file
+        file: String,
+    },
+
+    /// Start a scan session (filters: exact <v>, range <min> <max>, unknown)
+    New {
+        /// Value type (i8, i16, i32, i64, f32, f64)
+        #[arg(short = 't', long = "type", default_value = "i32")]
+        value_type: String,
+
+        /// Session name (sessions are saved and can be resumed)
+        #[arg(long, default_value = "default")]
+        session: String,
+
+        /// Scan filter
+        #[arg(required = true, num_args = 1.., allow_hyphen_values = true)]
+        filter: Vec<String>,
+    },
+
+    /// Refine a scan session (filters: exact <v>, range <min> <max>, changed, unchanged, increased, decreased)
+    Next {
+        /// Session name
+        #[arg(long, default_value = "default")]
+        session: String,
+
+        /// Scan filter
+        #[arg(required = true, num_args = 1.., allow_hyphen_values = true)]
+        filter: Vec<String>,
+    },
+
+    /// Show the candidates of a scan session
+    Results {
+        /// Session name
+        #[arg(long, default_value = "default")]
+        session: String,
+
+        /// Maximum number of candidates to show
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
+    },
+
+    /// Save a pointer map of the process (for rescanning chains offline)
+    PointerMap {
+        /// Output file
+        #[arg(short, long, default_value = "pointers.ptr")]
+        out: String,
+    },
+
+    /// Find static pointer chains (module + offsets) leading to an address
+    PointerScan {
+        /// Target address (hex)
+        address: String,
+
+        /// Maximum number of pointer levels
+        #[arg(long, default_value = "5")]
+        max_level: usize,
+
+        /// Maximum offset added at each level (hex)
+        #[arg(long, default_value = "0x1000")]
+        max_offset: String,
+
+        /// Maximum number of chains
+        #[arg(long, default_value = "10000")]
+        max_results: usize,
+
+        /// Use a saved pointer map instead of the live process
+        #[arg(long)]
+        map: Option<String>,
+
+        /// Output file for the found chains
+        #[arg(short, long, default_value = "pointers.json")]
+        out: String,
+    },
+
+    /// Keep only chains that still lead to the (new) address of the value in this run
+    PointerRescan {
+        /// Chains saved by pointer-scan (updated in place)
+        results: String,
+
+        /// Address of the value in this run (hex)
+        address: String,
+
+        /// Use a saved pointer map instead of the live process
+        #[arg(long)]
+        map: Option<String>,
+    },
+
+    /// Write a value to an address
+    Write {
+        /// Address (hex)
+        address: String,
+
+        /// Value to write
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+
+        /// Value type (i8, i16, i32, i64, f32, f64)
+        #[arg(short = 't', long = "type", default_value = "i32")]
+        value_type: String,
+    },
+
+    /// Keep rewriting a value to an address
+    Freeze {
+        /// Address (hex)
+        address: String,
+
+        /// Value to keep
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+
+        /// Value type (i8, i16, i32, i64, f32, f64)
+        #[arg(short = 't', long = "type", default_value = "i32")]
+        value_type: String,
+
+        /// Rewrite interval in milliseconds
+        #[arg(long, default_value = "100")]
+        interval: u64,
+
+        /// Stop after this many seconds (default: until interrupted)
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+
+    /// Poll addresses and log value changes with timestamps
+    Watch {
+        /// Addresses (hex), optionally with a type: 0x1234:f32
+        #[arg(required = true)]
+        addresses: Vec<String>,
+
+        /// Default value type (i8, i16, i32, i64, f32, f64)
+        #[arg(short = 't', long = "type", default_value = "i32")]
+        value_type: String,
+
+        /// Poll interval in milliseconds
+        #[arg(long, default_value = "250")]
+        interval: u64,
+
+        /// Stop after this many seconds (default: until interrupted)
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+
     /// Interactive mode (multiple scans)
     Interactive,
 }
@@ -97,6 +253,42 @@ fn main() -> Result<()> {
         Some(Commands::Pattern { pattern }) => {
             cmd_pattern(&scanner, &pattern)?;
         },
+        Some(Commands::Signatures { file }) => {
+            cmd_signatures(&scanner, &file)?;
+        },
+        Some(Commands::New { value_type, session, filter }) => {
+            cmd_new_scan(&scanner, &session, &value_type, &filter)?;
+        },
+        Some(Commands::Next { session, filter }) => {
+            cmd_next_scan(&scanner, &session, &filter)?;
+        },
+        Some(Commands::Results { session, limit }) => {
+            let session = load_session(&scanner, &session)?;
+            print_session(&session, limit);
+        },
+        Some(Commands::PointerMap { out }) => {
+            cmd_pointer_map(&scanner, &out)?;
+        },
+        Some(Commands::PointerScan { address, max_level, max_offset, max_results, map, out }) => {
+            let options = PointerScanOptions {
+                max_level,
+                max_offset: parse_hex(&max_offset)?,
+                max_results,
+            };
+            cmd_pointer_scan(&scanner, parse_hex(&address)?, &options, map.as_deref(), &out)?;
+        },
+        Some(Commands::PointerRescan { results, address, map }) => {
+            cmd_pointer_rescan(&scanner, &results, parse_hex(&address)?, map.as_deref())?;
+        },
+        Some(Commands::Write { address, value, value_type }) => {
+            cmd_write(&scanner, parse_hex(&address)?, &value, &value_type)?;
+        },
+        Some(Commands::Freeze { address, value, value_type, interval, duration }) => {
+            cmd_freeze(&scanner, parse_hex(&address)?, &value, &value_type, interval, duration)?;
+        },
+        Some(Commands::Watch { addresses, value_type, interval, duration }) => {
+            cmd_watch(&scanner, &addresses, &value_type, interval, duration)?;
+        },
         Some(Commands::Interactive) => {
             cmd_interactive(&scanner)?;
         },
@@ -165,6 +357,43 @@ fn cmd_int(scanner: &MemoryScanner, value: i64, size: u8) -> Result<()> {
     Ok(())
 }
 
+fn cmd_signatures(scanner: &MemoryScanner, file: &str) -> Result<()> {
+    println!("\n🔍 Scanning for signatures from: {}", file);
+
+    let mut names = Vec::new();
+    let mut signatures = Vec::new();
+    for (line_number, line) in std::fs::read_to_string(file)?.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, pattern) = match line.split_once('=') {
+            Some((name, pattern)) => (name.trim().to_string(), pattern.trim()),
+            None => (format!("line {}", line_number + 1), line),
+        };
+        match Signature::parse(pattern) {
+            Ok(signature) => {
+                names.push(name);
+                signatures.push(signature);
+            },
+            Err(e) => eprintln!("   ❌ {}: {}", name, e),
+        }
+    }
+
+    println!("   Signatures: {}", signatures.len());
+
+    let results = scanner.scan_signatures(&signatures)?;
+    for (name, addresses) in names.iter().zip(&results) {
+        match addresses.as_slice() {
+            [] => println!("   ❌ {:30} not found", name),
+            [address] => println!("   ✅ {:30} 0x{:016X}", name, address),
+            [first, ..] => println!("   ⚠️  {:30} 0x{:016X} (+{} more)", name, first, addresses.len() - 1),
+        }
+    }
+
+    Ok(())
+}
+
 fn cmd_float(scanner: &MemoryScanner, value: f32) -> Result<()> {
     println!("\n🔢 Scanning for float: {}", value);
 
@@ -210,32 +439,18 @@ fn cmd_pattern(scanner: &MemoryScanner, pattern_str: &str) -> Result<()> {
     println!("\n🔍 Scanning for pattern: {}", pattern_str);
 
     // パターンをパース（例: "48 8B 5C 24 ?? 48 83 C4"）
-    let parts: Vec<&str> = pattern_str.split_whitespace().collect();
-    let mut pattern = Vec::new();
-    let mut mask = Vec::new();
-
-    for part in parts {
-        if part == "??" {
-            pattern.push(0);
-            mask.push(false);
-        } else {
-            match u8::from_str_radix(part, 16) {
-                Ok(byte) => {
-                    pattern.push(byte);
-                    mask.push(true);
-                },
-                Err(_) => {
-                    eprintln!("   ❌ Invalid hex byte: {}", part);
-                    return Ok(());
-                }
-            }
+    let signature = match Signature::parse(pattern_str) {
+        Ok(signature) => signature,
+        Err(e) => {
+            eprintln!("   ❌ {}", e);
+            return Ok(());
         }
-    }
+    };
 
-    println!("   Pattern bytes: {}", pattern.len());
-    println!("   Wildcards: {}", mask.iter().filter(|&&m| !m).count());
+    println!("   Pattern bytes: {}", signature.len());
+    println!("   Wildcards: {}", signature.wildcard_count());
 
-    let results = scanner.scan_pattern(&pattern, Some(&mask))?;
+    let results = scanner.scan_signature(&signature)?;
     println!("   ✅ Found {} matches", results.len());
 
     for (i, addr) in results.iter().take(50).enumerate() {
@@ -249,6 +464,248 @@ fn cmd_pattern(scanner: &MemoryScanner, pattern_str: &str) -> Result<()> {
     Ok(())
 }
 
+/// セッションファイルのパス
+fn session_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join("memscan_sessions").join(format!("{}.scan", name))
+}
+
+/// セッションを読み込む（別のプロセスのセッションはエラー）
+fn load_session(scanner: &MemoryScanner, name: &str) -> Result<ScanSession> {
+    let session = ScanSession::load(session_path(name))?;
+    if session.pid != scanner.process_info.pid {
+        anyhow::bail!(
+            "Session '{}' belongs to {} (PID {}); start a new scan",
+            name,
+            session.process_name,
+            session.pid
+        );
+    }
+    Ok(session)
+}
+
+fn cmd_new_scan(scanner: &MemoryScanner, name: &str, value_type: &str, filter: &[String]) -> Result<()> {
+    let value_type = ScanValueType::parse(value_type)?;
+    let args: Vec<&str> = filter.iter().map(String::as_str).collect();
+    let filter = ScanFilter::parse(&args, value_type)?;
+
+    println!("\n🔎 New scan ({:?}): {}", value_type, args.join(" "));
+    let session = ScanSession::first_scan(scanner, &scanner.process_info, value_type, filter)?;
+    session.save(session_path(name))?;
+    print_session(&session, 50);
+
+    Ok(())
+}
+
+fn cmd_next_scan(scanner: &MemoryScanner, name: &str, filter: &[String]) -> Result<()> {
+    let mut session = load_session(scanner, name)?;
+    let args: Vec<&str> = filter.iter().map(String::as_str).collect();
+    let filter = ScanFilter::parse(&args, session.value_type)?;
+
+    println!("\n🔎 Next scan #{}: {}", session.scan_count + 1, args.join(" "));
+    session.next_scan(scanner, filter)?;
+    session.save(session_path(name))?;
+    print_session(&session, 50);
+
+    Ok(())
+}
+
+fn print_session(session: &ScanSession, limit: usize) {
+    let count = session.candidate_count();
+    println!("   ✅ {} candidates after {} scan(s)", count, session.scan_count);
+
+    for (i, (address, value)) in session.results(limit).iter().enumerate() {
+        println!("   [{:4}] 0x{:016X}  {}", i, address, value);
+    }
+
+    if count > limit {
+        println!("   ... and {} more candidates", count - limit);
+    }
+}
+
+/// 16進数のアドレス・オフセットをパース（0xは省略可）
+fn parse_hex(text: &str) -> Result<usize> {
+    let digits = if text.starts_with("0x") || text.starts_with("0X") {
+        &text[2..]
+    } else {
+        text
+    };
+    Ok(usize::from_str_radix(digits, 16)?)
+}
+
+/// 保存したポインタマップ、なければ実プロセスからポインタマップを作る
+fn pointer_map(scanner: &MemoryScanner, map: Option<&str>) -> Result<PointerMap> {
+    match map {
+        Some(path) => {
+            println!("   Loading pointer map: {}", path);
+            PointerMap::load(path)
+        },
+        None => {
+            println!("   Building pointer map...");
+            PointerMap::build(scanner, &scanner.process_info)
+        },
+    }
+}
+
+fn cmd_pointer_map(scanner: &MemoryScanner, out: &str) -> Result<()> {
+    println!("\n🗺️  Building pointer map");
+
+    let map = PointerMap::build(scanner, &scanner.process_info)?;
+    map.save(out)?;
+    println!("   ✅ {} pointers, {} modules → {}", map.len(), map.modules.len(), out);
+
+    Ok(())
+}
+
+fn cmd_pointer_scan(
+    scanner: &MemoryScanner,
+    target: usize,
+    options: &PointerScanOptions,
+    map: Option<&str>,
+    out: &str,
+) -> Result<()> {
+    println!("\n🧭 Pointer scan for 0x{:016X}", target);
+    println!("   Max level: {}, max offset: 0x{:X}", options.max_level, options.max_offset);
+
+    let map = pointer_map(scanner, map)?;
+    println!("   Pointers: {}", map.len());
+
+    let chains = map.scan(target, options);
+    println!("   ✅ Found {} chains", chains.len());
+    print_chains(&chains);
+
+    PointerScanResults {
+        process_name: map.process_name.clone(),
+        target,
+        options: *options,
+        chains,
+    }
+    .save(out)?;
+    println!("   Saved to {} (rescan after restarting the process to find stable chains)", out);
+
+    Ok(())
+}
+
+fn cmd_pointer_rescan(scanner: &MemoryScanner, results: &str, target: usize, map: Option<&str>) -> Result<()> {
+    println!("\n🧭 Pointer rescan for 0x{:016X}", target);
+
+    let mut saved = PointerScanResults::load(results)?;
+    let before = saved.chains.len();
+    saved.chains = match map {
+        Some(_) => pointer_map(scanner, map)?.rescan(&saved.chains, target),
+        None => rescan_live(scanner, &saved.chains, target)?,
+    };
+    saved.target = target;
+
+    println!("   ✅ {} of {} chains still valid", saved.chains.len(), before);
+    print_chains(&saved.chains);
+    saved.save(results)?;
+
+    Ok(())
+}
+
+fn print_chains(chains: &[PointerChain]) {
+    for (i, chain) in chains.iter().take(50).enumerate() {
+        println!("   [{:4}] {}", i, chain);
+    }
+
+    if chains.len() > 50 {
+        println!("   ... and {} more chains", chains.len() - 50);
+    }
+}
+
+fn cmd_write(scanner: &MemoryScanner, address: usize, value: &str, value_type: &str) -> Result<()> {
+    let value_type = ScanValueType::parse(value_type)?;
+    let value = value_type.parse_value(value)?;
+
+    println!("\n✏️  Writing {} ({:?}) to 0x{:016X}", value, value_type, address);
+    let before = scanner.read_memory(address, value_type.size())?;
+    scanner.write_memory(address, &value_type.encode(value))?;
+    println!("   ✅ {} → {}", value_type.decode(&before), value);
+
+    Ok(())
+}
+
+fn cmd_freeze(
+    scanner: &MemoryScanner,
+    address: usize,
+    value: &str,
+    value_type: &str,
+    interval: u64,
+    duration: Option<u64>,
+) -> Result<()> {
+    let value_type = ScanValueType::parse(value_type)?;
+    let value = value_type.parse_value(value)?;
+
+    let mut freezer = ValueFreezer::new();
+    freezer.freeze(address, value_type, value);
+
+    println!("\n🧊 Freezing 0x{:016X} at {} ({:?}) every {} ms", address, value, value_type, interval);
+    println!("   Press Ctrl+C to stop");
+    let rewritten = freezer.run(
+        scanner,
+        Duration::from_millis(interval),
+        duration.map(Duration::from_secs),
+    )?;
+    println!("   ✅ Rewrote the value {} times", rewritten);
+
+    Ok(())
+}
+
+fn cmd_watch(
+    scanner: &MemoryScanner,
+    addresses: &[String],
+    value_type: &str,
+    interval: u64,
+    duration: Option<u64>,
+) -> Result<()> {
+    let default_type = ScanValueType::parse(value_type)?;
+
+    let mut watch = WatchList::new();
+    for entry in addresses {
+        let (address, value_type) = match entry.split_once(':') {
+            Some((address, value_type)) => (address, ScanValueType::parse(value_type)?),
+            None => (entry.as_str(), default_type),
+        };
+        watch.add(parse_hex(address)?, value_type);
+    }
+
+    println!("\n👀 Watching {} addresses every {} ms", watch.len(), interval);
+    println!("   Press Ctrl+C to stop");
+    watch.run(
+        scanner,
+        Duration::from_millis(interval),
+        duration.map(Duration::from_secs),
+        |event| {
+            let show = |value: Option<ScanValue>| value.map_or("??".to_string(), |value| value.to_string());
+            match event.previous {
+                None => println!("   [{}] 0x{:016X}  {}", format_timestamp(event.timestamp), event.address, show(event.current)),
+                Some(_) => println!(
+                    "   [{}] 0x{:016X}  {} → {}",
+                    format_timestamp(event.timestamp),
+                    event.address,
+                    show(event.previous),
+                    show(event.current)
+                ),
+            }
+        },
+    );
+
+    Ok(())
+}
+
+/// 時刻をHH:MM:SS.mmm（UTC）で表示
+fn format_timestamp(timestamp: SystemTime) -> String {
+    let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % 86400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
 fn cmd_interactive(scanner: &MemoryScanner) -> Result<()> {
     println!("\n🎮 Interactive Mode");
     println!("   Commands:");
@@ -256,11 +713,16 @@ fn cmd_interactive(scanner: &MemoryScanner) -> Result<()> {
     println!("     float <value>      - Scan for float");
     println!("     string <text>      - Scan for string");
     println!("     pattern <hex>      - Scan for byte pattern");
+    println!("     new <type> <filter> - Start a scan session (e.g. new i32 exact 100, new f32 unknown)");
+    println!("     next <filter>      - Refine (exact/range/changed/unchanged/increased/decreased)");
+    println!("     results [n]        - Show session candidates");
     println!("     regions            - List memory regions");
     println!("     quit               - Exit");
 
     use std::io::{self, Write};
 
+    let mut session: Option<ScanSession> = None;
+
     loop {
         print!("\nmemscan> ");
         io::stdout().flush()?;
@@ -295,6 +757,39 @@ fn cmd_interactive(scanner: &MemoryScanner) -> Result<()> {
                 let pattern = parts[1..].join(" ");
                 cmd_pattern(scanner, &pattern)?;
             },
+            Some(&"new") if parts.len() >= 3 => {
+                let started = ScanValueType::parse(parts[1]).and_then(|value_type| {
+                    let filter = ScanFilter::parse(&parts[2..], value_type)?;
+                    ScanSession::first_scan(scanner, &scanner.process_info, value_type, filter)
+                });
+                match started {
+                    Ok(started) => {
+                        print_session(&started, 20);
+                        session = Some(started);
+                    },
+                    Err(e) => println!("   ❌ {}", e),
+                }
+            },
+            Some(&"next") if parts.len() >= 2 => {
+                match session.as_mut() {
+                    Some(current) => {
+                        let refined = ScanFilter::parse(&parts[1..], current.value_type)
+                            .and_then(|filter| current.next_scan(scanner, filter));
+                        match refined {
+                            Ok(_) => print_session(current, 20),
+                            Err(e) => println!("   ❌ {}", e),
+                        }
+                    },
+                    None => println!("   ❌ No scan session (use: new <type> <filter>)"),
+                }
+            },
+            Some(&"results") => {
+                let limit = parts.get(1).and_then(|n| n.parse().ok()).unwrap_or(50);
+                match &session {
+                    Some(current) => print_session(current, limit),
+                    None => println!("   ❌ No scan session (use: new <type> <filter>)"),
+                }
+            },
             _ => {
                 println!("   ❌ Unknown command");
             }
@@ -323,6 +818,10 @@ fn cmd_info(scanner: &MemoryScanner) -> Result<()> {
     println!("   memscan -p {} float 1.5", scanner.process_info.pid);
     println!("   memscan -p {} string Player", scanner.process_info.pid);
     println!("   memscan -p {} pattern \"48 8B ?? 24\"", scanner.process_info.pid);
+    println!("   memscan -p {} new --type f32 unknown", scanner.process_info.pid);
+    println!("   memscan -p {} next decreased", scanner.process_info.pid);
+    println!("   memscan -p {} pointer-scan 0x7FF6A1B2C3D0", scanner.process_info.pid);
+    println!("   memscan -p {} watch 0x7FF6A1B2C3D0:f32", scanner.process_info.pid);
     println!("   memscan -p {} interactive", scanner.process_info.pid);
 
     Ok(())
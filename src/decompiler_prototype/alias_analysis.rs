@@ -0,0 +1,397 @@
+/// エイリアス解析
+///
+/// Load/Storeのアドレスを「スタックスロット」「グローバル変数」「ヒープ（その他のポインタ）」に
+/// 分類し、2つのメモリアクセスが同じ領域を指しうるかを判定する
+/// スタック上のアドレスが外部に渡されていない（エスケープしていない）限り、
+/// スタックスロットは他の領域と重ならないものとして扱う
+
+use crate::decompiler_prototype::dataflow::OpId;
+use crate::decompiler_prototype::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
+use crate::decompiler_prototype::x86_64::X86Register;
+use std::collections::{HashMap, HashSet};
+
+/// メモリ領域の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryRegion {
+    /// 関数入口のRSPからの相対オフセットで表すスタックスロット
+    Stack(i64),
+    /// 絶対アドレスで表すグローバル変数
+    Global(u64),
+    /// スタック以外を指すポインタ（引数・ロード結果・呼び出し結果など）からの相対オフセット
+    Heap { base: usize, offset: i64 },
+    /// 分類できないアドレス
+    Unknown,
+}
+
+/// メモリアクセスの位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemoryLocation {
+    pub region: MemoryRegion,
+    pub size: usize,
+}
+
+/// エイリアス判定の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasResult {
+    /// 決して重ならない
+    No,
+    /// 重なる可能性がある
+    May,
+    /// 完全に同じ位置・サイズ
+    Must,
+}
+
+/// 解析中に追跡するポインタ値
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PointerValue {
+    Stack(i64),
+    Const(u64),
+    Heap { base: usize, offset: i64 },
+    Unknown,
+}
+
+/// エイリアス解析器
+///
+/// P-code操作列を先頭から順に辿り、各Varnodeが指すアドレスを記号的に追跡する
+/// 分岐先（ラベル）では複数回定義されるVarnodeの値を不明として扱う
+pub struct AliasAnalysis {
+    /// Load/Store操作 → アクセス位置
+    locations: HashMap<OpId, MemoryLocation>,
+    /// 直線コード領域の先頭になる操作
+    region_starts: HashSet<OpId>,
+    /// スタック上のアドレスが外部に渡されたか
+    stack_escaped: bool,
+    /// 次に割り当てるヒープ基底ID
+    next_heap_base: usize,
+}
+
+impl AliasAnalysis {
+    /// P-code操作列を解析
+    pub fn analyze(ops: &[PcodeOp]) -> Self {
+        let mut analysis = Self {
+            locations: HashMap::new(),
+            region_starts: Self::find_region_starts(ops),
+            stack_escaped: false,
+            next_heap_base: 0,
+        };
+
+        let mut def_counts: HashMap<&Varnode, usize> = HashMap::new();
+        for op in ops {
+            if let Some(ref output) = op.output {
+                *def_counts.entry(output).or_insert(0) += 1;
+            }
+        }
+
+        let mut values: HashMap<Varnode, PointerValue> = HashMap::new();
+        values.insert(stack_pointer(), PointerValue::Stack(0));
+        // 命令開始時点のスタックポインタ（呼び出し後の復元用）
+        let mut instruction_sp = PointerValue::Stack(0);
+
+        for (op_id, op) in ops.iter().enumerate() {
+            if op_id == 0 || ops[op_id - 1].address != op.address {
+                instruction_sp = values.get(&stack_pointer()).copied().unwrap_or(PointerValue::Unknown);
+            }
+
+            if op_id > 0 && analysis.region_starts.contains(&op_id) {
+                // 合流点では複数の定義が届きうるため、値を確定できない
+                for (vn, value) in values.iter_mut() {
+                    if !is_frame_register(vn) && def_counts.get(vn).copied().unwrap_or(0) > 1 {
+                        *value = PointerValue::Unknown;
+                    }
+                }
+            }
+
+            match op.opcode {
+                OpCode::Load => {
+                    if let (Some(addr), Some(output)) = (op.inputs.last(), op.output.as_ref()) {
+                        let pointer = analysis.value_of(&mut values, addr);
+                        analysis.locations.insert(op_id, MemoryLocation {
+                            region: Self::region_of(pointer),
+                            size: output.size,
+                        });
+                    }
+                }
+                OpCode::Store if op.inputs.len() >= 2 => {
+                    let addr = &op.inputs[op.inputs.len() - 2];
+                    let value = &op.inputs[op.inputs.len() - 1];
+                    let pointer = analysis.value_of(&mut values, addr);
+                    analysis.locations.insert(op_id, MemoryLocation {
+                        region: Self::region_of(pointer),
+                        size: value.size,
+                    });
+
+                    // スタックのアドレスをメモリに書き込むとエスケープ
+                    if let PointerValue::Stack(_) = analysis.value_of(&mut values, value) {
+                        analysis.stack_escaped = true;
+                    }
+                }
+                OpCode::Call | OpCode::CallInd => {
+                    // レジスタ経由でスタックのアドレスを渡すとエスケープ
+                    if values.iter().any(|(vn, value)| {
+                        vn.space == AddressSpace::Register
+                            && !is_frame_register(vn)
+                            && matches!(value, PointerValue::Stack(_))
+                    }) {
+                        analysis.stack_escaped = true;
+                    }
+
+                    // 呼び出し先のretで戻りアドレスが取り除かれ、命令前の値に戻る
+                    values.insert(stack_pointer(), instruction_sp);
+
+                    // フレームレジスタ以外は呼び出し先で書き換えられうる
+                    values.retain(|vn, _| vn.space != AddressSpace::Register || is_frame_register(vn));
+                }
+                _ => {}
+            }
+
+            if let Some(ref output) = op.output {
+                if op.opcode != OpCode::Load {
+                    let value = analysis.evaluate(&mut values, op);
+                    values.insert(output.clone(), value);
+                } else {
+                    let base = analysis.fresh_heap_base();
+                    values.insert(output.clone(), PointerValue::Heap { base, offset: 0 });
+                }
+            }
+        }
+
+        analysis
+    }
+
+    /// Load/Store操作のアクセス位置を取得
+    pub fn location(&self, op_id: OpId) -> Option<&MemoryLocation> {
+        self.locations.get(&op_id)
+    }
+
+    /// 操作が直線コード領域の先頭かどうか
+    pub fn is_region_start(&self, op_id: OpId) -> bool {
+        self.region_starts.contains(&op_id)
+    }
+
+    /// スタック上のアドレスが外部に渡されたかどうか
+    pub fn stack_escaped(&self) -> bool {
+        self.stack_escaped
+    }
+
+    /// 2つのアクセス位置のエイリアス関係を判定
+    pub fn alias(&self, a: &MemoryLocation, b: &MemoryLocation) -> AliasResult {
+        use MemoryRegion::*;
+
+        match (a.region, b.region) {
+            (Stack(x), Stack(y)) => overlap(x, a.size, y, b.size),
+            (Global(x), Global(y)) => overlap(x as i64, a.size, y as i64, b.size),
+            (Heap { base: p, offset: x }, Heap { base: q, offset: y }) if p == q => {
+                overlap(x, a.size, y, b.size)
+            }
+            (Stack(_), Global(_)) | (Global(_), Stack(_)) => AliasResult::No,
+            (Stack(_), Heap { .. }) | (Heap { .. }, Stack(_)) if !self.stack_escaped => {
+                AliasResult::No
+            }
+            _ => AliasResult::May,
+        }
+    }
+
+    /// 呼び出し操作がこの位置を読み書きしうるか
+    pub fn clobbered_by_call(&self, location: &MemoryLocation) -> bool {
+        !matches!(location.region, MemoryRegion::Stack(_)) || self.stack_escaped
+    }
+
+    /// Varnodeのポインタ値を取得（未知のVarnodeはヒープ基底として登録）
+    fn value_of(&mut self, values: &mut HashMap<Varnode, PointerValue>, vn: &Varnode) -> PointerValue {
+        if vn.space == AddressSpace::Const {
+            return PointerValue::Const(vn.offset);
+        }
+        if let Some(value) = values.get(vn) {
+            return *value;
+        }
+        let base = self.fresh_heap_base();
+        let value = PointerValue::Heap { base, offset: 0 };
+        values.insert(vn.clone(), value);
+        value
+    }
+
+    /// 操作の出力のポインタ値を計算
+    fn evaluate(&mut self, values: &mut HashMap<Varnode, PointerValue>, op: &PcodeOp) -> PointerValue {
+        let inputs: Vec<PointerValue> = op.inputs.iter().map(|vn| self.value_of(values, vn)).collect();
+
+        match (op.opcode, inputs.as_slice()) {
+            (OpCode::Copy, [value]) => *value,
+            (OpCode::IntAdd, [a, b]) => self.add(*a, *b, false),
+            (OpCode::IntSub, [a, b]) => self.add(*a, *b, true),
+            _ => {
+                if inputs.iter().any(|v| matches!(v, PointerValue::Stack(_) | PointerValue::Unknown)) {
+                    PointerValue::Unknown
+                } else {
+                    let base = self.fresh_heap_base();
+                    PointerValue::Heap { base, offset: 0 }
+                }
+            }
+        }
+    }
+
+    /// ポインタ演算（加算・減算）
+    fn add(&mut self, a: PointerValue, b: PointerValue, subtract: bool) -> PointerValue {
+        use PointerValue::*;
+
+        let delta = |c: u64| if subtract { (c as i64).wrapping_neg() } else { c as i64 };
+
+        match (a, b) {
+            (Const(x), Const(y)) => Const(if subtract { x.wrapping_sub(y) } else { x.wrapping_add(y) }),
+            (Stack(offset), Const(c)) => Stack(offset.wrapping_add(delta(c))),
+            (Heap { base, offset }, Const(c)) => Heap { base, offset: offset.wrapping_add(delta(c)) },
+            (Const(c), Stack(offset)) if !subtract => Stack(offset.wrapping_add(c as i64)),
+            (Const(c), Heap { base, offset }) if !subtract => Heap { base, offset: offset.wrapping_add(c as i64) },
+            (Stack(_), _) | (_, Stack(_)) | (Unknown, _) | (_, Unknown) => Unknown,
+            _ => {
+                // スタック以外同士の演算（配列の添字計算など）は新しいヒープ基底とする
+                let base = self.fresh_heap_base();
+                Heap { base, offset: 0 }
+            }
+        }
+    }
+
+    fn fresh_heap_base(&mut self) -> usize {
+        let base = self.next_heap_base;
+        self.next_heap_base += 1;
+        base
+    }
+
+    fn region_of(pointer: PointerValue) -> MemoryRegion {
+        match pointer {
+            PointerValue::Stack(offset) => MemoryRegion::Stack(offset),
+            PointerValue::Const(address) => MemoryRegion::Global(address),
+            PointerValue::Heap { base, offset } => MemoryRegion::Heap { base, offset },
+            PointerValue::Unknown => MemoryRegion::Unknown,
+        }
+    }
+
+    /// 直線コード領域の先頭を求める
+    ///
+    /// 分岐先の操作と分岐直後の操作が領域の先頭になる
+    /// 間接分岐を含む場合は全ての命令境界を領域の先頭とみなす
+    fn find_region_starts(ops: &[PcodeOp]) -> HashSet<OpId> {
+        let mut targets = HashSet::new();
+        let mut every_instruction = false;
+
+        for op in ops {
+            match op.opcode {
+                OpCode::Branch | OpCode::CBranch => match op.inputs.first() {
+                    Some(target) => {
+                        targets.insert(target.offset);
+                    }
+                    None => every_instruction = true,
+                },
+                OpCode::BranchInd => every_instruction = true,
+                _ => {}
+            }
+        }
+
+        let mut starts = HashSet::new();
+        starts.insert(0);
+        for op_id in 1..ops.len() {
+            let prev = &ops[op_id - 1];
+            let op = &ops[op_id];
+            let new_instruction = op.address != prev.address;
+
+            let after_branch = matches!(
+                prev.opcode,
+                OpCode::Branch | OpCode::CBranch | OpCode::BranchInd | OpCode::Return
+            );
+            let is_target = new_instruction && targets.contains(&op.address);
+
+            if after_branch || is_target || (every_instruction && new_instruction) {
+                starts.insert(op_id);
+            }
+        }
+
+        starts
+    }
+}
+
+fn stack_pointer() -> Varnode {
+    X86Register::RSP.to_varnode_64()
+}
+
+/// スタックポインタ・フレームポインタかどうか
+fn is_frame_register(vn: &Varnode) -> bool {
+    vn.space == AddressSpace::Register
+        && (vn.offset == X86Register::RSP as u64 || vn.offset == X86Register::RBP as u64)
+}
+
+/// 2つの区間 [x, x+a) と [y, y+b) の重なりを判定
+fn overlap(x: i64, a: usize, y: i64, b: usize) -> AliasResult {
+    if x == y && a == b {
+        AliasResult::Must
+    } else if x.wrapping_add(a as i64) <= y || y.wrapping_add(b as i64) <= x {
+        AliasResult::No
+    } else {
+        AliasResult::May
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_global_heap_classification() {
+        let rsp = X86Register::RSP.to_varnode_64();
+        let rcx = X86Register::RCX.to_varnode_64();
+        let rdx = X86Register::RDX.to_varnode_64();
+        let slot = Varnode::unique(0x100, 8);
+
+        let ops = vec![
+            // [rsp + 8] = rcx
+            PcodeOp::binary(OpCode::IntAdd, slot.clone(), rsp.clone(), Varnode::constant(8, 8), 0x1000),
+            PcodeOp::no_output(OpCode::Store, vec![slot.clone(), rcx.clone()], 0x1000),
+            // [0x140003000] = rdx
+            PcodeOp::no_output(OpCode::Store, vec![Varnode::constant(0x140003000, 8), rdx.clone()], 0x1004),
+            // [rcx] = rdx
+            PcodeOp::no_output(OpCode::Store, vec![rcx.clone(), rdx.clone()], 0x1008),
+            // rdx = [rsp + 8]
+            PcodeOp::unary(OpCode::Load, rdx.clone(), slot.clone(), 0x100c),
+        ];
+
+        let analysis = AliasAnalysis::analyze(&ops);
+        let stack = *analysis.location(1).unwrap();
+        let global = *analysis.location(2).unwrap();
+        let heap = *analysis.location(3).unwrap();
+        let reload = *analysis.location(4).unwrap();
+
+        assert_eq!(stack.region, MemoryRegion::Stack(8));
+        assert_eq!(global.region, MemoryRegion::Global(0x140003000));
+        assert!(matches!(heap.region, MemoryRegion::Heap { offset: 0, .. }));
+
+        assert!(!analysis.stack_escaped());
+        assert_eq!(analysis.alias(&stack, &reload), AliasResult::Must);
+        assert_eq!(analysis.alias(&stack, &global), AliasResult::No);
+        assert_eq!(analysis.alias(&stack, &heap), AliasResult::No);
+        assert_eq!(analysis.alias(&global, &heap), AliasResult::May);
+    }
+
+    #[test]
+    fn test_stack_escape_through_call() {
+        let rsp = X86Register::RSP.to_varnode_64();
+        let rcx = X86Register::RCX.to_varnode_64();
+        let rax = X86Register::RAX.to_varnode_64();
+
+        let ops = vec![
+            // lea rcx, [rsp + 0x20]; call f
+            PcodeOp::binary(OpCode::IntAdd, rcx.clone(), rsp.clone(), Varnode::constant(0x20, 8), 0x1000),
+            PcodeOp::no_output(OpCode::Call, vec![Varnode::constant(0x2000, 8)], 0x1004),
+            // [rax] = 0
+            PcodeOp::no_output(OpCode::Store, vec![rax.clone(), Varnode::constant(0, 4)], 0x1009),
+            // [rsp + 0x20] = 1
+            PcodeOp::binary(OpCode::IntAdd, rcx.clone(), rsp.clone(), Varnode::constant(0x20, 8), 0x100f),
+            PcodeOp::no_output(OpCode::Store, vec![rcx.clone(), Varnode::constant(1, 4)], 0x100f),
+        ];
+
+        let analysis = AliasAnalysis::analyze(&ops);
+        assert!(analysis.stack_escaped());
+
+        let heap = *analysis.location(2).unwrap();
+        let stack = *analysis.location(4).unwrap();
+        assert_eq!(stack.region, MemoryRegion::Stack(0x20));
+        assert_eq!(analysis.alias(&heap, &stack), AliasResult::May);
+    }
+}
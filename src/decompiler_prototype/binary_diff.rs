@@ -0,0 +1,579 @@
+/// バイナリ差分
+///
+/// 同じプログラムの2つのビルド間で関数を対応付け、追加・削除・変更された関数と
+/// アドレスの対応を求める
+/// 対応付けは確度の高い順に、関数ハッシュの一致 → CFG形状の一致 →
+/// 対応済み関数の呼び出し先 → 参照する文字列の一致で行う
+
+use super::capstone_translator::CapstoneTranslator;
+use super::cfg::ControlFlowGraph;
+use super::function_analyzer::FunctionDetector;
+use super::function_hash::{file_offset_for_address, fingerprint_function};
+use anyhow::Result;
+use capstone::arch::x86::{ArchMode, X86OperandType, X86Reg};
+use capstone::prelude::*;
+use goblin::Object;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use xxhash_rust::xxh3::Xxh3;
+
+/// 呼び出し先をたどって検出する関数数の上限
+const MAX_FUNCTIONS: usize = 200_000;
+
+/// 文字列参照とみなす最小の長さ
+const MIN_STRING_LENGTH: usize = 4;
+
+/// 文字列参照として読む最大の長さ
+const MAX_STRING_LENGTH: usize = 256;
+
+/// 関数の対応付けの根拠
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// 関数ハッシュが一致
+    Exact,
+    /// CFGの形状が一致
+    CfgShape,
+    /// 対応済み関数の呼び出し先
+    CallGraph,
+    /// 参照する文字列が一致
+    Strings,
+}
+
+/// 関数の差分の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStatus {
+    /// 同じアドレスで本体も同じ
+    Unchanged,
+    /// 本体は同じだがアドレスが変わった
+    Moved,
+    /// 本体が変わった
+    Changed,
+    /// 新しいビルドにのみ存在
+    Added,
+    /// 古いビルドにのみ存在
+    Removed,
+}
+
+impl DiffStatus {
+    /// 文字列から変換（MCPツールの絞り込み用）
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "unchanged" => Some(Self::Unchanged),
+            "moved" => Some(Self::Moved),
+            "changed" => Some(Self::Changed),
+            "added" => Some(Self::Added),
+            "removed" => Some(Self::Removed),
+            _ => None,
+        }
+    }
+}
+
+/// 1関数の差分
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffEntry {
+    pub status: DiffStatus,
+    /// 古いビルドでのアドレス
+    pub old_address: Option<u64>,
+    /// 新しいビルドでのアドレス
+    pub new_address: Option<u64>,
+    /// 対応付けの根拠（追加・削除された関数はNone）
+    pub match_kind: Option<MatchKind>,
+    /// 類似度（0.0〜1.0）
+    pub similarity: f64,
+    /// 古いビルドでのサイズ（バイト）
+    pub old_size: Option<usize>,
+    /// 新しいビルドでのサイズ（バイト）
+    pub new_size: Option<usize>,
+}
+
+/// 種類ごとの関数数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffSummary {
+    pub unchanged: usize,
+    pub moved: usize,
+    pub changed: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// バイナリ差分の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryDiff {
+    pub summary: DiffSummary,
+    /// 古いビルドのアドレス順（追加された関数は末尾に新しいビルドのアドレス順）
+    pub entries: Vec<DiffEntry>,
+}
+
+/// バイナリ差分のページ（ページネーション + 種類での絞り込み）
+#[derive(Debug, Serialize)]
+pub struct BinaryDiffPage {
+    pub summary: DiffSummary,
+    pub total_count: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub entries: Vec<DiffEntry>,
+}
+
+impl BinaryDiff {
+    /// 結果の1ページを取得
+    pub fn page(&self, page: usize, page_size: usize, status: Option<DiffStatus>) -> BinaryDiffPage {
+        let filtered: Vec<&DiffEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| status.is_none_or(|status| entry.status == status))
+            .collect();
+
+        let total_count = filtered.len();
+        let start = std::cmp::min(page * page_size, total_count);
+        let end = std::cmp::min(start + page_size, total_count);
+
+        BinaryDiffPage {
+            summary: self.summary.clone(),
+            total_count,
+            page,
+            page_size,
+            entries: filtered[start..end].iter().map(|entry| (*entry).clone()).collect(),
+        }
+    }
+}
+
+/// 対応付けに使う関数の特徴
+#[derive(Debug, Clone)]
+struct FunctionFeatures {
+    address: u64,
+    /// 位置に依存しない関数ハッシュ
+    hash: u64,
+    size: usize,
+    instruction_count: usize,
+    /// CFG形状（ブロックごとの入次数・出次数）のハッシュ
+    shape: u64,
+    /// 直接呼び出す関数（呼び出し位置の順）
+    callees: Vec<u64>,
+    /// 参照する文字列（ソート済み・重複なし）
+    strings: Vec<String>,
+}
+
+/// 2つのバイナリの関数を対応付ける
+///
+/// `old_functions`・`new_functions`は（仮想アドレス, ファイルオフセット）の一覧で、
+/// 各関数の呼び出し先とエントリポイントから検出した関数も比較対象に加える
+pub fn diff_binaries(
+    old_data: &[u8],
+    old_functions: &[(u64, usize)],
+    new_data: &[u8],
+    new_functions: &[(u64, usize)],
+    max_instructions: usize,
+) -> Result<BinaryDiff> {
+    let old = discover_functions(old_data, old_functions, max_instructions);
+    let new = discover_functions(new_data, new_functions, max_instructions);
+    Ok(match_functions(&old, &new))
+}
+
+/// 起点の関数から呼び出し先をたどって関数を検出し、特徴を求める
+///
+/// 呼び出し先の検出には`FunctionDetector`を使う
+fn discover_functions(binary_data: &[u8], seeds: &[(u64, usize)], max_instructions: usize) -> BTreeMap<u64, FunctionFeatures> {
+    let mut seen: HashSet<u64> = seeds.iter().map(|&(address, _)| address).collect();
+    let mut frontier: Vec<(u64, usize)> = seeds.to_vec();
+    if let Some(entry) = entry_point(binary_data) {
+        if seen.insert(entry.0) {
+            frontier.push(entry);
+        }
+    }
+
+    let mut functions = BTreeMap::new();
+    while !frontier.is_empty() && functions.len() < MAX_FUNCTIONS {
+        let extract = |&(address, offset): &(u64, usize)| extract_features(binary_data, address, offset, max_instructions);
+
+        #[cfg(feature = "parallel")]
+        let extracted: Vec<FunctionFeatures> = {
+            use rayon::prelude::*;
+            frontier.par_iter().filter_map(extract).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let extracted: Vec<FunctionFeatures> = frontier.iter().filter_map(extract).collect();
+
+        frontier = Vec::new();
+        for features in extracted {
+            for &callee in &features.callees {
+                if seen.insert(callee) {
+                    if let Some(offset) = file_offset_for_address(binary_data, callee) {
+                        frontier.push((callee, offset));
+                    }
+                }
+            }
+            functions.insert(features.address, features);
+        }
+    }
+
+    functions
+}
+
+/// バイナリのエントリポイント（仮想アドレス, ファイルオフセット）
+fn entry_point(binary_data: &[u8]) -> Option<(u64, usize)> {
+    let address = match Object::parse(binary_data).ok()? {
+        Object::PE(pe) => pe.image_base as u64 + pe.entry as u64,
+        Object::Elf(elf) => elf.entry,
+        _ => return None,
+    };
+    Some((address, file_offset_for_address(binary_data, address)?))
+}
+
+/// 関数の特徴を求める（逆アセンブルできない場合はNone）
+fn extract_features(binary_data: &[u8], address: u64, offset: usize, max_instructions: usize) -> Option<FunctionFeatures> {
+    let end = std::cmp::min(offset.checked_add(max_instructions * 15)?, binary_data.len());
+    let code = binary_data.get(offset..end)?;
+    let fingerprint = fingerprint_function(code, address, max_instructions).ok()?;
+    let body = &code[..fingerprint.size];
+
+    let mut translator = CapstoneTranslator::new().ok()?;
+    let pcodes = translator.translate(body, address, max_instructions).ok()?;
+
+    // 呼び出し先
+    let mut detector = FunctionDetector::new();
+    detector.detect_function_prologues(&pcodes);
+    let mut call_sites: Vec<(u64, u64)> = detector
+        .get_call_graph()
+        .iter()
+        .flat_map(|(&site, targets)| targets.iter().map(move |&target| (site, target)))
+        .collect();
+    call_sites.sort_unstable();
+    let mut callees = Vec::new();
+    for (_, target) in call_sites {
+        if target != address && !callees.contains(&target) {
+            callees.push(target);
+        }
+    }
+
+    // CFG形状
+    let cfg = ControlFlowGraph::from_pcodes(pcodes);
+    let mut degrees: Vec<(usize, usize)> = cfg
+        .blocks
+        .values()
+        .map(|block| (block.predecessors.len(), block.successors.len()))
+        .collect();
+    degrees.sort_unstable();
+    let mut hasher = Xxh3::new();
+    for (preds, succs) in degrees {
+        hasher.update(&(preds as u32).to_le_bytes());
+        hasher.update(&(succs as u32).to_le_bytes());
+    }
+
+    Some(FunctionFeatures {
+        address,
+        hash: fingerprint.hash,
+        size: fingerprint.size,
+        instruction_count: fingerprint.instruction_count,
+        shape: hasher.digest(),
+        callees,
+        strings: referenced_strings(binary_data, body, address),
+    })
+}
+
+/// 関数本体が参照する文字列（RIP相対の変位と即値のアドレス）
+fn referenced_strings(binary_data: &[u8], body: &[u8], address: u64) -> Vec<String> {
+    let Ok(cs) = Capstone::new().x86().mode(ArchMode::Mode64).detail(true).build() else {
+        return Vec::new();
+    };
+    let Ok(insns) = cs.disasm_all(body, address) else {
+        return Vec::new();
+    };
+
+    let mut strings = Vec::new();
+    for insn in insns.iter() {
+        let Ok(detail) = cs.insn_detail(insn) else {
+            continue;
+        };
+        let arch_detail = detail.arch_detail();
+        let Some(x86) = arch_detail.x86() else {
+            continue;
+        };
+        let next = insn.address() + insn.len() as u64;
+
+        for operand in x86.operands() {
+            let target = match operand.op_type {
+                X86OperandType::Mem(mem) if mem.base().0 as u32 == X86Reg::X86_REG_RIP => next.wrapping_add(mem.disp() as u64),
+                X86OperandType::Imm(value) => value as u64,
+                _ => continue,
+            };
+            if let Some(string) = read_string(binary_data, target) {
+                strings.push(string);
+            }
+        }
+    }
+
+    strings.sort();
+    strings.dedup();
+    strings
+}
+
+/// アドレスにあるNUL終端の印字可能な文字列を読む
+fn read_string(binary_data: &[u8], address: u64) -> Option<String> {
+    let offset = file_offset_for_address(binary_data, address)?;
+    let end = std::cmp::min(offset + MAX_STRING_LENGTH, binary_data.len());
+    let bytes = &binary_data[offset..end];
+    let length = bytes.iter().position(|&b| b == 0)?;
+    let text = &bytes[..length];
+
+    let printable = text.iter().all(|&b| (0x20..0x7f).contains(&b) || matches!(b, b'\t' | b'\n' | b'\r'));
+    (length >= MIN_STRING_LENGTH && printable).then(|| String::from_utf8_lossy(text).to_string())
+}
+
+/// 対応付けの状態
+struct Matcher<'a> {
+    old: &'a BTreeMap<u64, FunctionFeatures>,
+    new: &'a BTreeMap<u64, FunctionFeatures>,
+    old_to_new: HashMap<u64, (u64, MatchKind)>,
+    new_to_old: HashMap<u64, u64>,
+}
+
+impl<'a> Matcher<'a> {
+    fn add(&mut self, old: u64, new: u64, kind: MatchKind) -> bool {
+        if self.old_to_new.contains_key(&old) || self.new_to_old.contains_key(&new) {
+            return false;
+        }
+        self.old_to_new.insert(old, (new, kind));
+        self.new_to_old.insert(new, old);
+        true
+    }
+
+    /// 未対応の関数をキーで分類し、両側で同じキーを持つ組を対応付ける
+    ///
+    /// `pair_all`がtrueなら同じキーの関数が同数のときアドレス順に組にし、
+    /// falseなら両側で1つずつの場合のみ組にする
+    fn match_by_key<K, F>(&mut self, kind: MatchKind, pair_all: bool, key: F) -> usize
+    where
+        K: std::hash::Hash + Eq,
+        F: Fn(&FunctionFeatures) -> Option<K>,
+    {
+        let mut groups: HashMap<K, (Vec<u64>, Vec<u64>)> = HashMap::new();
+        for features in self.old.values().filter(|f| !self.old_to_new.contains_key(&f.address)) {
+            if let Some(k) = key(features) {
+                groups.entry(k).or_default().0.push(features.address);
+            }
+        }
+        for features in self.new.values().filter(|f| !self.new_to_old.contains_key(&f.address)) {
+            if let Some(k) = key(features) {
+                groups.entry(k).or_default().1.push(features.address);
+            }
+        }
+
+        let mut pairs: Vec<(u64, u64)> = Vec::new();
+        for (old, new) in groups.into_values() {
+            let pairable = if pair_all { old.len() == new.len() } else { old.len() == 1 && new.len() == 1 };
+            if pairable {
+                pairs.extend(old.into_iter().zip(new));
+            }
+        }
+        pairs.sort_unstable();
+
+        pairs.into_iter().filter(|&(old, new)| self.add(old, new, kind)).count()
+    }
+
+    /// 対応済みの関数の呼び出し先を対応付ける
+    ///
+    /// 未対応の呼び出し先が両側で1つずつ、またはCFG形状で一意に決まる場合に組にする
+    fn match_callees(&mut self) -> usize {
+        let mut pairs = Vec::new();
+        for (&old, &(new, _)) in &self.old_to_new {
+            let (Some(old_features), Some(new_features)) = (self.old.get(&old), self.new.get(&new)) else {
+                continue;
+            };
+            let old_callees: Vec<&FunctionFeatures> = old_features
+                .callees
+                .iter()
+                .filter(|callee| !self.old_to_new.contains_key(callee))
+                .filter_map(|callee| self.old.get(callee))
+                .collect();
+            let new_callees: Vec<&FunctionFeatures> = new_features
+                .callees
+                .iter()
+                .filter(|callee| !self.new_to_old.contains_key(callee))
+                .filter_map(|callee| self.new.get(callee))
+                .collect();
+
+            if old_callees.len() == 1 && new_callees.len() == 1 {
+                pairs.push((old_callees[0].address, new_callees[0].address));
+                continue;
+            }
+            for old_callee in &old_callees {
+                let mut same_shape = new_callees.iter().filter(|f| f.shape == old_callee.shape);
+                if let (Some(new_callee), None) = (same_shape.next(), same_shape.next()) {
+                    if old_callees.iter().filter(|f| f.shape == old_callee.shape).count() == 1 {
+                        pairs.push((old_callee.address, new_callee.address));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+
+        pairs.into_iter().filter(|&(old, new)| self.add(old, new, MatchKind::CallGraph)).count()
+    }
+}
+
+/// 関数の特徴から2つのビルドの関数を対応付ける
+fn match_functions(old: &BTreeMap<u64, FunctionFeatures>, new: &BTreeMap<u64, FunctionFeatures>) -> BinaryDiff {
+    let mut matcher = Matcher {
+        old,
+        new,
+        old_to_new: HashMap::new(),
+        new_to_old: HashMap::new(),
+    };
+
+    matcher.match_by_key(MatchKind::Exact, true, |f| Some(f.hash));
+    loop {
+        // 命令数が大きく違う関数はCFG形状が同じでも対応付けない
+        let shape_matches = matcher.match_by_key(MatchKind::CfgShape, false, |f| {
+            (f.instruction_count >= 8).then_some((f.shape, f.instruction_count / 8))
+        });
+        let callee_matches = matcher.match_callees();
+        let string_matches = matcher.match_by_key(MatchKind::Strings, false, |f| {
+            (!f.strings.is_empty()).then(|| f.strings.clone())
+        });
+        if shape_matches + callee_matches + string_matches == 0 {
+            break;
+        }
+    }
+
+    let mut summary = DiffSummary::default();
+    let mut entries = Vec::new();
+
+    for (&old_address, old_features) in old {
+        let entry = match matcher.old_to_new.get(&old_address) {
+            Some(&(new_address, kind)) => {
+                let new_features = &new[&new_address];
+                let status = if old_features.hash != new_features.hash {
+                    summary.changed += 1;
+                    DiffStatus::Changed
+                } else if old_address != new_address {
+                    summary.moved += 1;
+                    DiffStatus::Moved
+                } else {
+                    summary.unchanged += 1;
+                    DiffStatus::Unchanged
+                };
+                DiffEntry {
+                    status,
+                    old_address: Some(old_address),
+                    new_address: Some(new_address),
+                    match_kind: Some(kind),
+                    similarity: similarity(old_features, new_features),
+                    old_size: Some(old_features.size),
+                    new_size: Some(new_features.size),
+                }
+            }
+            None => {
+                summary.removed += 1;
+                DiffEntry {
+                    status: DiffStatus::Removed,
+                    old_address: Some(old_address),
+                    new_address: None,
+                    match_kind: None,
+                    similarity: 0.0,
+                    old_size: Some(old_features.size),
+                    new_size: None,
+                }
+            }
+        };
+        entries.push(entry);
+    }
+
+    for (&new_address, new_features) in new {
+        if !matcher.new_to_old.contains_key(&new_address) {
+            summary.added += 1;
+            entries.push(DiffEntry {
+                status: DiffStatus::Added,
+                old_address: None,
+                new_address: Some(new_address),
+                match_kind: None,
+                similarity: 0.0,
+                old_size: None,
+                new_size: Some(new_features.size),
+            });
+        }
+    }
+
+    BinaryDiff { summary, entries }
+}
+
+/// 2つの関数の類似度（命令数・CFG形状・参照文字列の一致度の平均）
+fn similarity(a: &FunctionFeatures, b: &FunctionFeatures) -> f64 {
+    if a.hash == b.hash {
+        return 1.0;
+    }
+
+    let size = a.instruction_count.min(b.instruction_count) as f64 / a.instruction_count.max(b.instruction_count).max(1) as f64;
+    let shape = if a.shape == b.shape { 1.0 } else { 0.0 };
+    let strings = if a.strings.is_empty() && b.strings.is_empty() {
+        1.0
+    } else {
+        let common = a.strings.iter().filter(|s| b.strings.contains(s)).count();
+        common as f64 / (a.strings.len() + b.strings.len() - common) as f64
+    };
+
+    (size + shape + strings) / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 関数を並べた生バイナリを作る（アドレス = ファイルオフセット）
+    fn layout(functions: &[(usize, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0xccu8; 0x400];
+        for (offset, code) in functions {
+            data[*offset..*offset + code.len()].copy_from_slice(code);
+        }
+        data
+    }
+
+    /// call rel32 を作る
+    fn call(from: usize, to: usize) -> Vec<u8> {
+        let mut code = vec![0xe8];
+        code.extend_from_slice(&((to as i64 - (from as i64 + 5)) as i32).to_le_bytes());
+        code
+    }
+
+    /// mov eax, imm32; ret
+    fn leaf(value: u32) -> Vec<u8> {
+        let mut code = vec![0xb8];
+        code.extend_from_slice(&value.to_le_bytes());
+        code.push(0xc3);
+        code
+    }
+
+    #[test]
+    fn test_diff_moved_changed_added_removed() -> Result<()> {
+        // 旧: 0x100 main → 0x200 leaf(1), 0x300 leaf(7)
+        let mut old_main = call(0x100, 0x200);
+        old_main.extend(call(0x105, 0x300));
+        old_main.push(0xc3);
+        let old = layout(&[(0x100, old_main), (0x200, leaf(1)), (0x300, leaf(7))]);
+
+        // 新: mainは同じ位置、leaf(1)は0x280へ移動、leaf(7)はleaf(9)に変更されて0x380へ、0x180を追加
+        let mut new_main = call(0x100, 0x280);
+        new_main.extend(call(0x105, 0x380));
+        new_main.push(0xc3);
+        let extra = vec![0x31, 0xc0, 0xff, 0xc0, 0xc3]; // xor eax, eax; inc eax; ret
+        let new = layout(&[(0x100, new_main), (0x180, extra), (0x280, leaf(1)), (0x380, leaf(9))]);
+
+        // 旧ビルドでは存在しない関数0x180を起点に含めても追加として扱われる
+        let diff = diff_binaries(&old, &[(0x100, 0x100)], &new, &[(0x100, 0x100), (0x180, 0x180)], 100)?;
+
+        let find = |old_address: u64| diff.entries.iter().find(|e| e.old_address == Some(old_address)).unwrap();
+        assert_eq!(find(0x100).status, DiffStatus::Unchanged);
+        assert_eq!((find(0x200).status, find(0x200).new_address), (DiffStatus::Moved, Some(0x280)));
+        assert_eq!(find(0x300).status, DiffStatus::Changed);
+        assert_eq!(find(0x300).new_address, Some(0x380));
+        assert_eq!(find(0x300).match_kind, Some(MatchKind::CallGraph));
+        assert_eq!(diff.summary.added, 1);
+        assert_eq!(diff.summary.removed, 0);
+
+        let page = diff.page(0, 10, Some(DiffStatus::Added));
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.entries[0].new_address, Some(0x180));
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 /// Ghidraのprintc.ccに基づくP-code→C言語変換
 /// 式の優先順位、括弧の最小化、型キャストなどを処理
 
+use crate::decompiler_prototype::control_flow::format_for_header;
 use crate::decompiler_prototype::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
 use crate::decompiler_prototype::type_inference::{Type, TypeInference};
 use std::collections::HashMap;
@@ -27,6 +28,7 @@ struct VarnodeKey {
     space: AddressSpace,
     offset: u64,
     size: usize,
+    version: u32,
 }
 
 impl From<&Varnode> for VarnodeKey {
@@ -35,6 +37,7 @@ impl From<&Varnode> for VarnodeKey {
             space: vn.space,
             offset: vn.offset,
             size: vn.size,
+            version: vn.version,
         }
     }
 }
@@ -85,6 +88,13 @@ impl CPrinter {
             }
         };
 
+        // SSA解除後も別変数として残ったものはバージョンで区別
+        let name = if vn.version > 0 && vn.space != AddressSpace::Unique {
+            format!("{}_{}", name, vn.version)
+        } else {
+            name
+        };
+
         self.var_names.insert(key, name.clone());
         name
     }
@@ -393,6 +403,11 @@ impl CPrinter {
         self.output.join("\n")
     }
 
+    /// forループのヘッダーを出力（ControlStructure::For の init/cond/step から）
+    pub fn print_for_header(&mut self, init: &PcodeOp, cond: &PcodeOp, step: &PcodeOp) -> String {
+        format_for_header(init, cond, step, &mut |vn| self.get_var_name(vn))
+    }
+
     /// 生成されたコードを取得
     pub fn get_output(&self) -> String {
         self.output.join("\n")
@@ -954,7 +954,7 @@ impl CapstoneTranslator {
             X86OperandType::Mem(mem) => {
                 // jmp [memory] - メモリから間接ジャンプ
                 let (addr_ops, mem_addr) = self.compute_mem_address(mem, address)?;
-                let target_temp = Varnode { space: AddressSpace::Unique, offset: 0x2000, size: 8 };
+                let target_temp = Varnode::new(AddressSpace::Unique, 0x2000, 8);
                 let mut ops = addr_ops;
                 // target_temp = *mem_addr (Load jump target)
                 ops.push(PcodeOp::unary(OpCode::Load, target_temp.clone(), mem_addr, address));
@@ -985,7 +985,7 @@ impl CapstoneTranslator {
             X86OperandType::Mem(mem) => {
                 // call [memory] - メモリから間接コール
                 let (addr_ops, mem_addr) = self.compute_mem_address(mem, address)?;
-                let target_temp = Varnode { space: AddressSpace::Unique, offset: 0x2100, size: 8 };
+                let target_temp = Varnode::new(AddressSpace::Unique, 0x2100, 8);
                 let mut ops = addr_ops;
                 // target_temp = *mem_addr (Load call target)
                 ops.push(PcodeOp::unary(OpCode::Load, target_temp.clone(), mem_addr, address));
@@ -1,9 +1,10 @@
 /// 制御フロー解析
 /// 基本ブロックの構築と制御フローグラフ
 
-use super::pcode::{OpCode, PcodeOp};
+use super::jumptable::JumpTable;
+use super::pcode::{AddressSpace, OpCode, PcodeOp};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// 基本ブロックID
 pub type BlockId = usize;
@@ -78,18 +79,57 @@ impl ControlFlowGraph {
 
     /// P-code列から制御フローグラフを構築
     pub fn from_pcodes(pcodes: Vec<PcodeOp>) -> Self {
+        Self::from_pcodes_with_jump_tables(pcodes, &[])
+    }
+
+    /// P-code列から制御フローグラフを構築
+    ///
+    /// 分岐命令と分岐先でブロックを分割し、直接分岐・フォールスルーの後続を結ぶ
+    /// 解決済みのジャンプテーブルがあれば、その分岐先を間接分岐ブロックの後続とする
+    pub fn from_pcodes_with_jump_tables(pcodes: Vec<PcodeOp>, tables: &[JumpTable]) -> Self {
         let mut cfg = ControlFlowGraph::new();
 
         if pcodes.is_empty() {
             return cfg;
         }
 
+        // 間接分岐命令のアドレス → 分岐先
+        let indirect_targets: HashMap<u64, Vec<u64>> = tables
+            .iter()
+            .map(|table| (table.branch_address, table.targets()))
+            .collect();
+
+        // ブロックの先頭となる命令アドレス
+        let mut leaders: HashSet<u64> = HashSet::new();
+        for op in &pcodes {
+            match op.opcode {
+                OpCode::Branch | OpCode::CBranch => leaders.extend(branch_target(op)),
+                OpCode::BranchInd => leaders.extend(indirect_targets.get(&op.address).into_iter().flatten()),
+                _ => {}
+            }
+        }
+
         // 1つ目のブロックを作成
         let mut current_block = BasicBlock::new(0, pcodes[0].address);
         cfg.entry_block = 0;
         cfg.next_block_id = 1;
+        let mut prev_address = None;
 
         for op in pcodes {
+            // 分岐先となる命令の先頭で分割
+            let starts_instruction = prev_address != Some(op.address);
+            if starts_instruction && leaders.contains(&op.address) && !current_block.ops.is_empty() {
+                let block_id = current_block.id;
+                cfg.blocks.insert(block_id, current_block);
+
+                current_block = BasicBlock::new(cfg.next_block_id, op.address);
+                cfg.next_block_id += 1;
+            }
+            if current_block.ops.is_empty() {
+                current_block.start_address = op.address;
+            }
+            prev_address = Some(op.address);
+
             let should_split = matches!(
                 op.opcode,
                 OpCode::Branch | OpCode::CBranch | OpCode::BranchInd | OpCode::Return
@@ -112,9 +152,59 @@ impl ControlFlowGraph {
             cfg.blocks.insert(current_block.id, current_block);
         }
 
+        cfg.link_blocks(&indirect_targets);
         cfg
     }
 
+    /// ブロック末尾の命令から後続・先行ブロックを設定
+    fn link_blocks(&mut self, indirect_targets: &HashMap<u64, Vec<u64>>) {
+        let mut block_at: HashMap<u64, BlockId> = HashMap::new();
+        for block in self.blocks_in_order() {
+            block_at.entry(block.start_address).or_insert(block.id);
+        }
+
+        let mut edges: Vec<(BlockId, Vec<BlockId>)> = Vec::new();
+        for block in self.blocks_in_order() {
+            let fallthrough = self.blocks.contains_key(&(block.id + 1)).then_some(block.id + 1);
+            let target_block = |address: &u64| block_at.get(address).copied();
+
+            let successors: Vec<BlockId> = match block.ops.last() {
+                Some(op) if op.opcode == OpCode::Branch => branch_target(op).and_then(|t| target_block(&t)).into_iter().collect(),
+                Some(op) if op.opcode == OpCode::CBranch => {
+                    // 条件成立側を先頭にする
+                    branch_target(op).and_then(|t| target_block(&t)).into_iter().chain(fallthrough).collect()
+                }
+                Some(op) if op.opcode == OpCode::BranchInd => indirect_targets
+                    .get(&op.address)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(target_block)
+                    .collect(),
+                Some(op) if op.opcode == OpCode::Return => Vec::new(),
+                _ => fallthrough.into_iter().collect(),
+            };
+
+            let mut unique = Vec::new();
+            for succ in successors {
+                if !unique.contains(&succ) {
+                    unique.push(succ);
+                }
+            }
+            edges.push((block.id, unique));
+        }
+
+        for (block_id, successors) in edges {
+            for &succ in &successors {
+                if let Some(succ_block) = self.blocks.get_mut(&succ) {
+                    succ_block.predecessors.push(block_id);
+                }
+            }
+            if let Some(block) = self.blocks.get_mut(&block_id) {
+                block.successors = successors;
+            }
+        }
+    }
+
     /// エントリブロックを取得
     pub fn entry(&self) -> Option<&BasicBlock> {
         self.blocks.get(&self.entry_block)
@@ -133,6 +223,14 @@ impl ControlFlowGraph {
     }
 }
 
+/// 直接分岐の分岐先アドレス
+pub(crate) fn branch_target(op: &PcodeOp) -> Option<u64> {
+    op.inputs
+        .first()
+        .filter(|target| matches!(target.space, AddressSpace::Const | AddressSpace::Ram))
+        .map(|target| target.offset)
+}
+
 impl std::fmt::Display for ControlFlowGraph {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Control Flow Graph:")?;
@@ -158,7 +256,7 @@ impl std::fmt::Display for ControlFlowGraph {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::decompiler_prototype::x86_64::example_translation;
+    use crate::decompiler_prototype::x86_64::{example_translation, X86Decoder, X86Register};
 
     #[test]
     fn test_cfg_construction() {
@@ -178,4 +276,25 @@ mod tests {
         let entry = cfg.entry().unwrap();
         assert!(!entry.ops.is_empty());
     }
+
+    #[test]
+    fn test_branch_successors() {
+        let mut decoder = X86Decoder::new();
+        let mut pcodes = Vec::new();
+        // 0x1000: cmp eax, 0; je 0x1010; inc eax; jmp 0x1000; 0x1010: ret
+        pcodes.extend(decoder.decode_cmp_imm(X86Register::RAX, 0, 4, 0x1000));
+        pcodes.extend(decoder.decode_je(0x1010, 0x1003));
+        pcodes.extend(decoder.decode_inc(X86Register::RAX, 4, 0x1005));
+        pcodes.extend(decoder.decode_jmp(0x1000, 0x1007));
+        pcodes.extend(decoder.decode_ret(0x1010));
+
+        let cfg = ControlFlowGraph::from_pcodes(pcodes);
+
+        assert_eq!(cfg.block_count(), 3);
+        assert_eq!(cfg.blocks[&0].successors, vec![2, 1]);
+        assert_eq!(cfg.blocks[&1].successors, vec![0]);
+        assert_eq!(cfg.blocks[&2].start_address, 0x1010);
+        assert!(cfg.blocks[&2].successors.is_empty());
+        assert_eq!(cfg.blocks[&0].predecessors, vec![1]);
+    }
 }
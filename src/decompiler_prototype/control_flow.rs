@@ -1,35 +1,117 @@
 /// 制御構造検出
 /// CFGから高レベルの制御構造（if/while/for/switch）を検出する
+///
+/// 支配木・後支配木を用いて領域を順に構造化し、短絡評価（&&/||）の条件、
+/// 複数の出口を持つループのbreak/continueを復元する
+/// 構造化できない辺（既約でない領域や共有された末尾ブロック）はラベル付きgotoで表す
 
 use super::cfg::*;
-use super::pcode::*;
+use super::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
 use std::collections::{HashMap, HashSet, VecDeque};
 
+/// 後支配木の計算で使う仮想的な出口ブロック
+const VIRTUAL_EXIT: BlockId = BlockId::MAX;
+
+/// 条件式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// ブロック末尾の条件分岐が成立する（successors[0]へ分岐する）
+    Block(BlockId),
+    /// 否定
+    Not(Box<Condition>),
+    /// 短絡評価のAND
+    And(Box<Condition>, Box<Condition>),
+    /// 短絡評価のOR
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    /// 条件を否定（二重否定は打ち消す）
+    pub fn negate(self) -> Condition {
+        match self {
+            Condition::Not(inner) => *inner,
+            other => Condition::Not(Box::new(other)),
+        }
+    }
+
+    /// 条件式を構成するブロックを評価順に取得
+    pub fn blocks(&self) -> Vec<BlockId> {
+        match self {
+            Condition::Block(id) => vec![*id],
+            Condition::Not(inner) => inner.blocks(),
+            Condition::And(lhs, rhs) | Condition::Or(lhs, rhs) => {
+                let mut blocks = lhs.blocks();
+                blocks.extend(rhs.blocks());
+                blocks
+            }
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::And(..) | Condition::Or(..) => write!(f, "({})", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Block(id) => write!(f, "block_{}", id),
+            Condition::Not(inner) => {
+                write!(f, "!")?;
+                inner.fmt_operand(f)
+            }
+            Condition::And(lhs, rhs) => {
+                lhs.fmt_operand(f)?;
+                write!(f, " && ")?;
+                rhs.fmt_operand(f)
+            }
+            Condition::Or(lhs, rhs) => {
+                lhs.fmt_operand(f)?;
+                write!(f, " || ")?;
+                rhs.fmt_operand(f)
+            }
+        }
+    }
+}
+
 /// 制御構造の種類
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ControlStructure {
     /// 順次実行
     Sequence(Vec<ControlStructure>),
-    /// if文: (条件ブロック, then部, else部)
+    /// if文: (条件, then部, else部)
     IfThenElse {
-        condition_block: BlockId,
+        condition: Condition,
         then_branch: Box<ControlStructure>,
         else_branch: Option<Box<ControlStructure>>,
     },
     /// if文（else無し）
     IfThen {
-        condition_block: BlockId,
+        condition: Condition,
         then_branch: Box<ControlStructure>,
     },
-    /// whileループ: (条件ブロック, ループ本体)
+    /// whileループ: (条件, ループ本体)
     While {
-        condition_block: BlockId,
+        condition: Condition,
         body: Box<ControlStructure>,
     },
-    /// do-whileループ: (ループ本体, 条件ブロック)
+    /// forループ: for (init; cond; step) { body }
+    For {
+        /// 初期化: 帰納変数 = 初期値
+        init: PcodeOp,
+        /// 継続条件: 帰納変数と上限の比較
+        cond: PcodeOp,
+        /// 更新: 帰納変数 = 帰納変数 ± 増分
+        step: PcodeOp,
+        body: Box<ControlStructure>,
+    },
+    /// do-whileループ: (ループ本体, 条件)
     DoWhile {
         body: Box<ControlStructure>,
-        condition_block: BlockId,
+        condition: Condition,
     },
     /// 無限ループ
     InfiniteLoop {
@@ -46,6 +128,10 @@ pub enum ControlStructure {
     Break,
     /// continue文
     Continue,
+    /// goto文（構造化できない辺）
+    Goto(BlockId),
+    /// gotoの飛び先ラベル
+    Label(BlockId),
 }
 
 /// ループ情報
@@ -59,6 +145,26 @@ pub struct LoopInfo {
     pub back_edges: Vec<(BlockId, BlockId)>,
     /// ループの種類
     pub loop_type: LoopType,
+    /// ループ脱出後に実行されるブロック（breakの飛び先）
+    pub follow: Option<BlockId>,
+    /// forループの帰納変数
+    pub induction: Option<InductionVariable>,
+}
+
+/// 帰納変数
+///
+/// ループ前で初期化され、ヘッダーで比較され、バックエッジ上で一定量ずつ更新される変数
+/// 各操作はSSAバージョンを除いた元の変数で表す
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InductionVariable {
+    /// 帰納変数
+    pub variable: Varnode,
+    /// 初期化: variable = 初期値
+    pub init: PcodeOp,
+    /// 継続条件（成立している間ループを続ける）
+    pub cond: PcodeOp,
+    /// 更新: variable = variable ± 増分
+    pub step: PcodeOp,
 }
 
 /// ループの種類
@@ -66,20 +172,48 @@ pub struct LoopInfo {
 pub enum LoopType {
     /// whileループ（前判定）
     While,
+    /// forループ（帰納変数を持つ前判定ループ）
+    For,
     /// do-whileループ（後判定）
     DoWhile,
     /// 無限ループ
     Infinite,
 }
 
+/// 構造化中のループ文脈（break/continueの飛び先）
+#[derive(Debug, Clone)]
+struct LoopContext {
+    header: BlockId,
+    /// continueの飛び先（do-whileでは条件判定ブロック）
+    continue_target: BlockId,
+    follow: Option<BlockId>,
+    body: HashSet<BlockId>,
+    /// switch文の内側ではbreakがswitchを抜けてしまうため使えない
+    in_switch: bool,
+}
+
 /// 制御構造解析器
 pub struct ControlFlowAnalyzer {
-    /// 支配木情報
+    /// 支配木情報（ブロック → 即時支配者）
     dominators: HashMap<BlockId, BlockId>,
+    /// 後支配木情報（ブロック → 即時後支配者）
+    post_dominators: HashMap<BlockId, BlockId>,
     /// ループ情報
     loops: Vec<LoopInfo>,
-    /// 訪問済みブロック
-    visited: HashSet<BlockId>,
+    /// 後続ブロック
+    successors: HashMap<BlockId, Vec<BlockId>>,
+    /// 先行ブロック
+    predecessors: HashMap<BlockId, Vec<BlockId>>,
+    /// 逆ポストオーダーでの順位
+    rpo_index: HashMap<BlockId, usize>,
+    /// 出力済みブロック
+    emitted: HashSet<BlockId>,
+    /// gotoの飛び先
+    goto_targets: HashSet<BlockId>,
+    /// ラベルを出力するブロック
+    labels: HashSet<BlockId>,
+    /// 構造化中のループ
+    loop_stack: Vec<LoopContext>,
 }
 
 impl ControlFlowAnalyzer {
@@ -87,8 +221,15 @@ impl ControlFlowAnalyzer {
     pub fn new() -> Self {
         Self {
             dominators: HashMap::new(),
+            post_dominators: HashMap::new(),
             loops: Vec::new(),
-            visited: HashSet::new(),
+            successors: HashMap::new(),
+            predecessors: HashMap::new(),
+            rpo_index: HashMap::new(),
+            emitted: HashSet::new(),
+            goto_targets: HashSet::new(),
+            labels: HashSet::new(),
+            loop_stack: Vec::new(),
         }
     }
 
@@ -99,132 +240,132 @@ impl ControlFlowAnalyzer {
 
     /// CFGから制御構造を検出
     pub fn analyze(&mut self, cfg: &ControlFlowGraph) -> ControlStructure {
-        // 1. 支配木を計算
-        self.compute_dominators(cfg);
+        // 1. 辺と支配木・後支配木を計算
+        self.build_edges(cfg);
+        self.compute_dominators(cfg.entry_block);
+        self.compute_post_dominators();
 
         // 2. ループを検出
-        self.detect_loops(cfg);
+        self.detect_loops();
+
+        // 3. forループの帰納変数を検出
+        self.detect_induction_variables(cfg);
 
-        // 3. 制御構造を構築
-        self.build_control_structure(cfg, cfg.entry_block)
+        // 4. 制御構造を構築（1回目でgotoの飛び先を求め、2回目でラベルを付ける）
+        self.labels.clear();
+        self.build_control_structure(cfg.entry_block);
+        self.labels = std::mem::take(&mut self.goto_targets);
+        self.build_control_structure(cfg.entry_block)
     }
 
-    /// 支配木を計算（簡易版）
-    fn compute_dominators(&mut self, cfg: &ControlFlowGraph) {
-        let entry = cfg.entry_block;
-        let mut idom: HashMap<BlockId, Option<BlockId>> = HashMap::new();
+    /// 後続・先行ブロックの表を作成
+    fn build_edges(&mut self, cfg: &ControlFlowGraph) {
+        self.successors.clear();
+        self.predecessors.clear();
 
-        // 初期化
-        for &block_id in cfg.blocks.keys() {
-            if block_id == entry {
-                idom.insert(block_id, None);
-            } else {
-                idom.insert(block_id, None);
+        for (&block_id, block) in &cfg.blocks {
+            self.predecessors.entry(block_id).or_default();
+            let succs: Vec<BlockId> = block
+                .successors
+                .iter()
+                .copied()
+                .filter(|s| cfg.blocks.contains_key(s))
+                .collect();
+            for &succ in &succs {
+                self.predecessors.entry(succ).or_default().push(block_id);
             }
+            self.successors.insert(block_id, succs);
         }
+    }
 
-        // 逆ポストオーダー
-        let rpo = self.reverse_postorder(cfg, entry);
-
-        // 収束まで繰り返し
-        let mut changed = true;
-        while changed {
-            changed = false;
-
-            for &block_id in &rpo {
-                if block_id == entry {
-                    continue;
-                }
+    fn succs(&self, block_id: BlockId) -> &[BlockId] {
+        self.successors.get(&block_id).map(|v| v.as_slice()).unwrap_or(&[])
+    }
 
-                let block = &cfg.blocks[&block_id];
-                if block.predecessors.is_empty() {
-                    continue;
-                }
+    fn preds(&self, block_id: BlockId) -> &[BlockId] {
+        self.predecessors.get(&block_id).map(|v| v.as_slice()).unwrap_or(&[])
+    }
 
-                // 処理済みの先行ブロックから新しい支配者を計算
-                let mut new_idom: Option<BlockId> = None;
-                for &pred in &block.predecessors {
-                    if idom.get(&pred).and_then(|x| *x).is_some() || pred == entry {
-                        new_idom = Some(pred);
-                        break;
-                    }
-                }
+    /// 支配木を計算
+    fn compute_dominators(&mut self, entry: BlockId) {
+        let rpo = reverse_postorder(entry, &self.successors);
+        self.rpo_index = rpo.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+        self.dominators = compute_idoms(&rpo, &self.predecessors);
+    }
 
-                if new_idom != idom[&block_id] {
-                    idom.insert(block_id, new_idom);
-                    changed = true;
-                }
-            }
+    /// 後支配木を計算（仮想出口からの逆グラフ上の支配木）
+    fn compute_post_dominators(&mut self) {
+        let mut reverse_succs: HashMap<BlockId, Vec<BlockId>> = self.predecessors.clone();
+        let mut reverse_preds: HashMap<BlockId, Vec<BlockId>> = self.successors.clone();
+
+        let mut exits: Vec<BlockId> = self
+            .successors
+            .iter()
+            .filter(|(_, succs)| succs.is_empty())
+            .map(|(&b, _)| b)
+            .collect();
+        exits.sort_unstable();
+        for &exit in &exits {
+            reverse_preds.entry(exit).or_default().push(VIRTUAL_EXIT);
         }
+        reverse_succs.insert(VIRTUAL_EXIT, exits);
 
-        // 結果を保存
-        for (block_id, dom) in idom {
-            if let Some(dominator) = dom {
-                self.dominators.insert(block_id, dominator);
-            }
-        }
+        let rpo = reverse_postorder(VIRTUAL_EXIT, &reverse_succs);
+        self.post_dominators = compute_idoms(&rpo, &reverse_preds);
     }
 
-    /// 逆ポストオーダー
-    fn reverse_postorder(&self, cfg: &ControlFlowGraph, entry: BlockId) -> Vec<BlockId> {
-        let mut visited = HashSet::new();
-        let mut postorder = Vec::new();
-
-        fn dfs(
-            cfg: &ControlFlowGraph,
-            block_id: BlockId,
-            visited: &mut HashSet<BlockId>,
-            postorder: &mut Vec<BlockId>,
-        ) {
-            if visited.contains(&block_id) {
-                return;
-            }
-            visited.insert(block_id);
-
-            if let Some(block) = cfg.blocks.get(&block_id) {
-                for &successor in &block.successors {
-                    dfs(cfg, successor, visited, postorder);
-                }
-            }
-
-            postorder.push(block_id);
-        }
-
-        dfs(cfg, entry, &mut visited, &mut postorder);
-        postorder.reverse();
-        postorder
+    /// 即時後支配者（合流点）を取得
+    fn immediate_post_dominator(&self, block_id: BlockId) -> Option<BlockId> {
+        self.post_dominators
+            .get(&block_id)
+            .copied()
+            .filter(|&b| b != VIRTUAL_EXIT && b != block_id)
     }
 
     /// ループを検出
-    fn detect_loops(&mut self, cfg: &ControlFlowGraph) {
-        // バックエッジを検出（後続ブロックが支配者の場合）
-        let mut back_edges = Vec::new();
+    fn detect_loops(&mut self) {
+        self.loops.clear();
 
-        for (&block_id, block) in &cfg.blocks {
-            for &successor in &block.successors {
-                // successorがblock_idを支配する場合、これはバックエッジ
+        // バックエッジを検出（後続ブロックが支配者の場合）
+        let mut back_edges: HashMap<BlockId, Vec<(BlockId, BlockId)>> = HashMap::new();
+        for (&block_id, succs) in &self.successors {
+            if !self.rpo_index.contains_key(&block_id) {
+                continue;
+            }
+            for &successor in succs {
                 if self.dominates(successor, block_id) {
-                    back_edges.push((block_id, successor));
+                    back_edges.entry(successor).or_default().push((block_id, successor));
                 }
             }
         }
 
-        // 各バックエッジからループを構築
-        for (tail, header) in back_edges {
-            let body = self.find_loop_body(cfg, header, tail);
-            let loop_type = self.determine_loop_type(cfg, header, &body);
+        // ヘッダーごとにループを構築（同じヘッダーのバックエッジはまとめる）
+        let mut headers: Vec<BlockId> = back_edges.keys().copied().collect();
+        headers.sort_by_key(|h| self.rpo_index[h]);
+
+        for header in headers {
+            let mut edges = back_edges.remove(&header).unwrap_or_default();
+            edges.sort_unstable();
+
+            let mut body = HashSet::new();
+            for &(tail, _) in &edges {
+                body.extend(self.find_loop_body(header, tail));
+            }
+            let (loop_type, follow) = self.determine_loop_type(header, &body, &edges);
 
             self.loops.push(LoopInfo {
                 header,
                 body,
-                back_edges: vec![(tail, header)],
+                back_edges: edges,
                 loop_type,
+                follow,
+                induction: None,
             });
         }
     }
 
     /// ループ本体を検出
-    fn find_loop_body(&self, cfg: &ControlFlowGraph, header: BlockId, tail: BlockId) -> HashSet<BlockId> {
+    fn find_loop_body(&self, header: BlockId, tail: BlockId) -> HashSet<BlockId> {
         let mut body = HashSet::new();
         body.insert(header);
 
@@ -237,12 +378,10 @@ impl ControlFlowAnalyzer {
         body.insert(tail);
 
         while let Some(block_id) = worklist.pop_front() {
-            if let Some(block) = cfg.blocks.get(&block_id) {
-                for &pred in &block.predecessors {
-                    if !body.contains(&pred) && self.dominates(header, pred) {
-                        body.insert(pred);
-                        worklist.push_back(pred);
-                    }
+            for &pred in self.preds(block_id) {
+                if !body.contains(&pred) && self.dominates(header, pred) {
+                    body.insert(pred);
+                    worklist.push_back(pred);
                 }
             }
         }
@@ -250,31 +389,168 @@ impl ControlFlowAnalyzer {
         body
     }
 
-    /// ループの種類を判定
-    fn determine_loop_type(&self, cfg: &ControlFlowGraph, header: BlockId, body: &HashSet<BlockId>) -> LoopType {
-        // ヘッダーブロックの終端命令をチェック
-        if let Some(header_block) = cfg.blocks.get(&header) {
-            if let Some(last_op) = header_block.ops.last() {
-                match last_op.opcode {
-                    OpCode::CBranch => {
-                        // 条件分岐がある場合はwhileループ
-                        LoopType::While
-                    }
-                    OpCode::Branch => {
-                        // 無条件分岐の場合は無限ループの可能性
-                        LoopType::Infinite
-                    }
-                    _ => {
-                        // その他の場合はdo-whileと判定
-                        LoopType::DoWhile
-                    }
-                }
-            } else {
-                LoopType::While
+    /// ループの種類と脱出先を判定
+    fn determine_loop_type(
+        &self,
+        header: BlockId,
+        body: &HashSet<BlockId>,
+        back_edges: &[(BlockId, BlockId)],
+    ) -> (LoopType, Option<BlockId>) {
+        let outside = |succs: &[BlockId]| -> Vec<BlockId> {
+            succs.iter().copied().filter(|s| !body.contains(s)).collect()
+        };
+
+        // ヘッダーが条件分岐でループを抜ける: whileループ
+        let header_succs = self.succs(header);
+        let header_exits = outside(header_succs);
+        if header_succs.len() == 2 && header_exits.len() == 1 {
+            return (LoopType::While, Some(header_exits[0]));
+        }
+
+        // 唯一のバックエッジ元が条件分岐でループを抜ける: do-whileループ
+        if let [(latch, _)] = back_edges {
+            let latch_succs = self.succs(*latch);
+            let latch_exits = outside(latch_succs);
+            if latch_succs.len() == 2 && latch_exits.len() == 1 {
+                return (LoopType::DoWhile, Some(latch_exits[0]));
             }
-        } else {
-            LoopType::While
         }
+
+        // それ以外は無限ループ + break（最初に現れる脱出先をbreakの飛び先とする）
+        let follow = body
+            .iter()
+            .flat_map(|&b| outside(self.succs(b)))
+            .min_by_key(|b| self.rpo_index.get(b).copied().unwrap_or(usize::MAX));
+        (LoopType::Infinite, follow)
+    }
+
+    /// whileループのうち帰納変数を持つものをforループにする
+    fn detect_induction_variables(&mut self, cfg: &ControlFlowGraph) {
+        for index in 0..self.loops.len() {
+            if self.loops[index].loop_type != LoopType::While {
+                continue;
+            }
+            if let Some(induction) = self.find_induction_variable(cfg, &self.loops[index]) {
+                self.loops[index].loop_type = LoopType::For;
+                self.loops[index].induction = Some(induction);
+            }
+        }
+    }
+
+    /// SSA形式のループから帰納変数を探す
+    ///
+    /// ヘッダーのPhi-node `i2 = φ(i1, i3)` について、`i1` がループ外で定義され、
+    /// `i3 = i2 ± c` がループ内で定義され、ヘッダーの条件分岐が `i2` と上限の比較である場合に検出する
+    fn find_induction_variable(&self, cfg: &ControlFlowGraph, loop_info: &LoopInfo) -> Option<InductionVariable> {
+        let [(latch, header)] = loop_info.back_edges.as_slice() else {
+            return None;
+        };
+        let header_block = cfg.blocks.get(header)?;
+
+        // ヘッダーは Phi-node・比較・条件分岐のみで構成されていること
+        let branch = header_block.ops.last().filter(|op| op.opcode == OpCode::CBranch)?;
+        let condition = branch.inputs.get(1)?;
+        let find_def = |vn: &Varnode| header_block.ops.iter().find(|op| op.output.as_ref() == Some(vn));
+        let mut compare = find_def(condition)?;
+        let mut negated = false;
+        if compare.opcode == OpCode::BoolNegate {
+            compare = find_def(compare.inputs.first()?)?;
+            negated = true;
+        }
+        if !is_comparison(compare.opcode) || compare.inputs.len() != 2 {
+            return None;
+        }
+        let header_is_simple = header_block.ops.iter().all(|op| {
+            matches!(op.opcode, OpCode::MultiEqual | OpCode::BoolNegate | OpCode::CBranch) || op == compare
+        });
+        if !header_is_simple {
+            return None;
+        }
+
+        // ループ外からの先行ブロック（プリヘッダー）とバックエッジ元の位置
+        let preds = &header_block.predecessors;
+        let latch_index = preds.iter().position(|p| p == latch)?;
+        let outside: Vec<usize> = (0..preds.len()).filter(|&i| !loop_info.body.contains(&preds[i])).collect();
+        let [preheader_index] = outside.as_slice() else {
+            return None;
+        };
+
+        // 条件分岐が成立したときにループ本体へ進むか
+        let continue_on_true = self.succs(*header).first().is_some_and(|s| loop_info.body.contains(s)) != negated;
+
+        for phi in header_block.ops.iter().filter(|op| op.opcode == OpCode::MultiEqual) {
+            let Some(current) = phi.output.as_ref() else {
+                continue;
+            };
+            let Some(position) = compare.inputs.iter().position(|vn| vn == current) else {
+                continue;
+            };
+            let (Some(initial), Some(next)) = (phi.inputs.get(*preheader_index), phi.inputs.get(latch_index)) else {
+                continue;
+            };
+
+            let Some(step) = self.find_step(cfg, &loop_info.body, current, next) else {
+                continue;
+            };
+
+            let variable = current.base();
+            let init_value = self.find_initial_value(cfg, &loop_info.body, initial);
+            let init = PcodeOp::unary(OpCode::Copy, variable.clone(), init_value, compare.address);
+
+            let mut inputs: Vec<Varnode> = compare.inputs.iter().map(base_variable).collect();
+            inputs[position] = variable.clone();
+            let mut cond = PcodeOp::new(compare.opcode, None, inputs, compare.address);
+            if !continue_on_true {
+                cond = negate_comparison(cond);
+            }
+
+            return Some(InductionVariable {
+                variable,
+                init,
+                cond,
+                step,
+            });
+        }
+
+        None
+    }
+
+    /// ループ内で `next = current ± c` となる更新を探す（Copyを1段まで辿る）
+    fn find_step(&self, cfg: &ControlFlowGraph, body: &HashSet<BlockId>, current: &Varnode, next: &Varnode) -> Option<PcodeOp> {
+        let find_def = |vn: &Varnode| {
+            body.iter()
+                .filter_map(|b| cfg.blocks.get(b))
+                .flat_map(|b| b.ops.iter())
+                .find(|op| op.output.as_ref() == Some(vn))
+        };
+
+        let mut def = find_def(next)?;
+        if def.opcode == OpCode::Copy {
+            def = find_def(def.inputs.first()?)?;
+        }
+
+        let variable = current.base();
+        match (def.opcode, def.inputs.as_slice()) {
+            (OpCode::IntAdd | OpCode::IntSub, [lhs, rhs]) if lhs == current && rhs.space == AddressSpace::Const => {
+                Some(PcodeOp::binary(def.opcode, variable.clone(), variable, rhs.clone(), def.address))
+            }
+            (OpCode::IntAdd, [lhs, rhs]) if rhs == current && lhs.space == AddressSpace::Const => {
+                Some(PcodeOp::binary(def.opcode, variable.clone(), variable, lhs.clone(), def.address))
+            }
+            _ => None,
+        }
+    }
+
+    /// ループ外での初期値を求める（Copyで定義されていればその入力）
+    fn find_initial_value(&self, cfg: &ControlFlowGraph, body: &HashSet<BlockId>, initial: &Varnode) -> Varnode {
+        cfg.blocks
+            .values()
+            .filter(|b| !body.contains(&b.id))
+            .flat_map(|b| b.ops.iter())
+            .find(|op| op.output.as_ref() == Some(initial) && op.opcode == OpCode::Copy)
+            .and_then(|op| op.inputs.first())
+            .map(base_variable)
+            .unwrap_or_else(|| base_variable(initial))
     }
 
     /// ブロックAがブロックBを支配するか
@@ -298,83 +574,236 @@ impl ControlFlowAnalyzer {
     }
 
     /// 制御構造を構築
-    fn build_control_structure(&mut self, cfg: &ControlFlowGraph, block_id: BlockId) -> ControlStructure {
-        if self.visited.contains(&block_id) {
-            return ControlStructure::BasicBlock(block_id);
-        }
-        self.visited.insert(block_id);
+    fn build_control_structure(&mut self, entry: BlockId) -> ControlStructure {
+        self.emitted.clear();
+        self.goto_targets.clear();
+        self.loop_stack.clear();
 
-        // このブロックがループヘッダーか確認
-        if let Some(loop_info) = self.find_loop_by_header(block_id) {
-            return self.build_loop_structure(cfg, loop_info);
-        }
+        let sequence = self.build_region(entry, None, false);
+        make_sequence(sequence)
+    }
 
-        let block = match cfg.blocks.get(&block_id) {
-            Some(b) => b,
-            None => return ControlStructure::BasicBlock(block_id),
-        };
+    /// 領域を構築（開始ブロックから終了ブロックの手前まで）
+    ///
+    /// `skip_entry` が真の場合、開始ブロックをループヘッダーとして扱わない（ループ本体の構築用）
+    fn build_region(&mut self, start: BlockId, end: Option<BlockId>, skip_entry: bool) -> Vec<ControlStructure> {
+        let mut sequence = Vec::new();
+        let mut current = start;
+        let mut first = skip_entry;
 
-        // 後続ブロック数で分岐
-        match block.successors.len() {
-            0 => {
-                // リターンまたは終端
-                ControlStructure::BasicBlock(block_id)
-            }
-            1 => {
-                // 順次実行
-                let next = block.successors[0];
-                let next_struct = self.build_control_structure(cfg, next);
-                ControlStructure::Sequence(vec![
-                    ControlStructure::BasicBlock(block_id),
-                    next_struct,
-                ])
+        loop {
+            if Some(current) == end {
+                break;
             }
-            2 => {
-                // if文またはループ
-                self.build_if_structure(cfg, block_id, &block.successors)
+
+            if !first {
+                if let Some(jump) = self.jump_to(current) {
+                    sequence.push(jump);
+                    break;
+                }
+
+                // このブロックがループヘッダーか確認
+                if let Some(index) = self.loops.iter().position(|l| l.header == current) {
+                    self.push_label(current, &mut sequence);
+                    let loop_info = self.loops[index].clone();
+                    sequence.push(self.build_loop_structure(&loop_info));
+                    match loop_info.follow {
+                        Some(follow) => {
+                            current = follow;
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+
+                self.push_label(current, &mut sequence);
             }
-            _ => {
-                // switch文の可能性
-                self.build_switch_structure(cfg, block_id, &block.successors)
+            first = false;
+            self.emitted.insert(current);
+
+            // 後続ブロック数で分岐
+            let successors = self.succs(current).to_vec();
+            match successors.len() {
+                0 => {
+                    // リターンまたは終端
+                    sequence.push(ControlStructure::BasicBlock(current));
+                    break;
+                }
+                1 => {
+                    // 順次実行
+                    sequence.push(ControlStructure::BasicBlock(current));
+                    current = successors[0];
+                }
+                2 => {
+                    // if文（短絡評価を含む）
+                    match self.build_if_structure(current, successors[0], successors[1], &mut sequence) {
+                        Some(next) => current = next,
+                        None => break,
+                    }
+                }
+                _ => {
+                    // switch文
+                    let merge = self.region_merge_point(current);
+                    sequence.push(self.build_switch_structure(current, &successors, merge));
+                    match merge {
+                        Some(next) => current = next,
+                        None => break,
+                    }
+                }
             }
         }
+
+        sequence
     }
 
-    /// if文の構造を構築
-    fn build_if_structure(&mut self, cfg: &ControlFlowGraph, condition_block: BlockId, successors: &[BlockId]) -> ControlStructure {
-        if successors.len() != 2 {
-            return ControlStructure::BasicBlock(condition_block);
+    /// if文の構造を構築し、続きを構築するブロックを返す
+    fn build_if_structure(
+        &mut self,
+        condition_block: BlockId,
+        then_block: BlockId,
+        else_block: BlockId,
+        sequence: &mut Vec<ControlStructure>,
+    ) -> Option<BlockId> {
+        let (condition, then_block, else_block) =
+            self.build_short_circuit(condition_block, then_block, else_block);
+        let merge = self.region_merge_point(condition_block);
+
+        if merge.is_none() {
+            // 片方がbreak/continue/gotoだけなら、もう片方をそのまま続ける
+            if let Some(jump) = self.jump_to(then_block) {
+                sequence.push(ControlStructure::IfThen {
+                    condition,
+                    then_branch: Box::new(jump),
+                });
+                return Some(else_block);
+            }
+            if let Some(jump) = self.jump_to(else_block) {
+                sequence.push(ControlStructure::IfThen {
+                    condition: condition.negate(),
+                    then_branch: Box::new(jump),
+                });
+                return Some(then_block);
+            }
         }
 
-        let then_block = successors[0];
-        let else_block = successors[1];
-
-        // 合流点を探す
-        let merge_point = self.find_merge_point(cfg, then_block, else_block);
-
-        let then_branch = Box::new(self.build_region(cfg, then_block, merge_point));
-
-        // else分岐が空でないかチェック
-        if else_block == merge_point.unwrap_or(else_block) {
+        let structure = if Some(else_block) == merge {
             // else分岐なし
             ControlStructure::IfThen {
-                condition_block,
-                then_branch,
+                condition,
+                then_branch: Box::new(make_sequence(self.build_region(then_block, merge, false))),
+            }
+        } else if Some(then_block) == merge {
+            // then分岐なし
+            ControlStructure::IfThen {
+                condition: condition.negate(),
+                then_branch: Box::new(make_sequence(self.build_region(else_block, merge, false))),
             }
         } else {
-            let else_branch = Some(Box::new(self.build_region(cfg, else_block, merge_point)));
+            let then_branch = Box::new(make_sequence(self.build_region(then_block, merge, false)));
+            let else_branch = Some(Box::new(make_sequence(self.build_region(else_block, merge, false))));
             ControlStructure::IfThenElse {
-                condition_block,
+                condition,
                 then_branch,
                 else_branch,
             }
+        };
+
+        sequence.push(structure);
+        merge
+    }
+
+    /// 短絡評価（&&/||）の条件を組み立てる
+    ///
+    /// 条件ブロックの後続が、条件式の途中のブロックからのみ到達する別の条件ブロックで、
+    /// その分岐先の片方が元の分岐先と一致する場合に条件を結合する
+    fn build_short_circuit(
+        &mut self,
+        condition_block: BlockId,
+        mut then_block: BlockId,
+        mut else_block: BlockId,
+    ) -> (Condition, BlockId, BlockId) {
+        let mut condition = Condition::Block(condition_block);
+        let mut chain = HashSet::new();
+        chain.insert(condition_block);
+
+        loop {
+            if self.is_chained_condition(then_block, &chain) {
+                let succs = self.succs(then_block).to_vec();
+                if succs[1] == else_block {
+                    // if (A && B)
+                    condition = Condition::And(Box::new(condition), Box::new(Condition::Block(then_block)));
+                    chain.insert(then_block);
+                    self.emitted.insert(then_block);
+                    then_block = succs[0];
+                    continue;
+                }
+                if succs[0] == else_block {
+                    // if (A && !B)
+                    condition = Condition::And(Box::new(condition), Box::new(Condition::Block(then_block).negate()));
+                    chain.insert(then_block);
+                    self.emitted.insert(then_block);
+                    then_block = succs[1];
+                    continue;
+                }
+            }
+
+            if self.is_chained_condition(else_block, &chain) {
+                let succs = self.succs(else_block).to_vec();
+                if succs[0] == then_block {
+                    // if (A || B)
+                    condition = Condition::Or(Box::new(condition), Box::new(Condition::Block(else_block)));
+                    chain.insert(else_block);
+                    self.emitted.insert(else_block);
+                    else_block = succs[1];
+                    continue;
+                }
+                if succs[1] == then_block {
+                    // if (A || !B)
+                    condition = Condition::Or(Box::new(condition), Box::new(Condition::Block(else_block).negate()));
+                    chain.insert(else_block);
+                    self.emitted.insert(else_block);
+                    else_block = succs[0];
+                    continue;
+                }
+            }
+
+            break;
+        }
+
+        (condition, then_block, else_block)
+    }
+
+    /// 条件式の一部として結合できる条件ブロックか
+    fn is_chained_condition(&self, block_id: BlockId, chain: &HashSet<BlockId>) -> bool {
+        if self.succs(block_id).len() != 2 || self.emitted.contains(&block_id) {
+            return false;
+        }
+        if self.loops.iter().any(|l| l.header == block_id) {
+            return false;
+        }
+        if let Some(ctx) = self.loop_stack.last() {
+            if !ctx.body.contains(&block_id) || Some(block_id) == ctx.follow {
+                return false;
+            }
         }
+
+        let preds = self.preds(block_id);
+        !preds.is_empty() && preds.iter().all(|p| chain.contains(p))
     }
 
     /// switch文の構造を構築
-    fn build_switch_structure(&mut self, cfg: &ControlFlowGraph, condition_block: BlockId, successors: &[BlockId]) -> ControlStructure {
-        let mut cases = Vec::new();
+    fn build_switch_structure(&mut self, condition_block: BlockId, successors: &[BlockId], merge: Option<BlockId>) -> ControlStructure {
+        // switch内のbreakはループではなくswitchを抜けるため、ループ脱出はgotoにする
+        let in_loop = match self.loop_stack.last().cloned() {
+            Some(mut ctx) => {
+                ctx.in_switch = true;
+                self.loop_stack.push(ctx);
+                true
+            }
+            None => false,
+        };
 
+        let mut cases = Vec::new();
         for (i, &succ) in successors.iter().enumerate() {
             let case_value = if i == successors.len() - 1 {
                 None // default case
@@ -382,10 +811,14 @@ impl ControlFlowAnalyzer {
                 Some(i as i64)
             };
 
-            let case_struct = self.build_control_structure(cfg, succ);
+            let case_struct = make_sequence(self.build_region(succ, merge, false));
             cases.push((case_value, case_struct));
         }
 
+        if in_loop {
+            self.loop_stack.pop();
+        }
+
         ControlStructure::Switch {
             condition_block,
             cases,
@@ -393,141 +826,369 @@ impl ControlFlowAnalyzer {
     }
 
     /// ループ構造を構築
-    fn build_loop_structure(&mut self, cfg: &ControlFlowGraph, loop_info: LoopInfo) -> ControlStructure {
+    fn build_loop_structure(&mut self, loop_info: &LoopInfo) -> ControlStructure {
         let header = loop_info.header;
-        let body_blocks: Vec<BlockId> = loop_info.body.iter().copied().filter(|&b| b != header).collect();
-
-        // ループ本体を構築
-        let mut body_structures = Vec::new();
-        for &block_id in &body_blocks {
-            if !self.visited.contains(&block_id) {
-                let struct_node = self.build_control_structure(cfg, block_id);
-                body_structures.push(struct_node);
+        let continue_target = match loop_info.loop_type {
+            LoopType::DoWhile => loop_info.back_edges[0].0,
+            _ => header,
+        };
+        self.emitted.insert(header);
+        self.loop_stack.push(LoopContext {
+            header,
+            continue_target,
+            follow: loop_info.follow,
+            body: loop_info.body.clone(),
+            in_switch: false,
+        });
+
+        let structure = match loop_info.loop_type {
+            LoopType::While => {
+                let succs = self.succs(header).to_vec();
+                let (condition, body_start) = if loop_info.body.contains(&succs[0]) {
+                    (Condition::Block(header), succs[0])
+                } else {
+                    (Condition::Block(header).negate(), succs[1])
+                };
+                let body = if body_start == header {
+                    Vec::new()
+                } else {
+                    self.build_region(body_start, None, false)
+                };
+                ControlStructure::While {
+                    condition,
+                    body: Box::new(make_loop_body(body)),
+                }
+            }
+            LoopType::For => {
+                let succs = self.succs(header).to_vec();
+                let body_start = if loop_info.body.contains(&succs[0]) { succs[0] } else { succs[1] };
+                let body = if body_start == header {
+                    Vec::new()
+                } else {
+                    self.build_region(body_start, None, false)
+                };
+                let induction = loop_info.induction.clone().expect("forループには帰納変数がある");
+                ControlStructure::For {
+                    init: induction.init,
+                    cond: induction.cond,
+                    step: induction.step,
+                    body: Box::new(make_loop_body(body)),
+                }
+            }
+            LoopType::DoWhile => {
+                let latch = loop_info.back_edges[0].0;
+                let condition = if self.succs(latch)[0] == header {
+                    Condition::Block(latch)
+                } else {
+                    Condition::Block(latch).negate()
+                };
+                let body = self.build_region(header, Some(latch), true);
+                self.emitted.insert(latch);
+                ControlStructure::DoWhile {
+                    body: Box::new(make_loop_body(body)),
+                    condition,
+                }
+            }
+            LoopType::Infinite => {
+                let body = self.build_region(header, None, true);
+                ControlStructure::InfiniteLoop {
+                    body: Box::new(make_loop_body(body)),
+                }
             }
-        }
-
-        let body = if body_structures.is_empty() {
-            Box::new(ControlStructure::BasicBlock(header))
-        } else if body_structures.len() == 1 {
-            Box::new(body_structures.into_iter().next().unwrap())
-        } else {
-            Box::new(ControlStructure::Sequence(body_structures))
         };
 
-        match loop_info.loop_type {
-            LoopType::While => ControlStructure::While {
-                condition_block: header,
-                body,
-            },
-            LoopType::DoWhile => ControlStructure::DoWhile {
-                body,
-                condition_block: header,
-            },
-            LoopType::Infinite => ControlStructure::InfiniteLoop { body },
-        }
+        self.loop_stack.pop();
+        structure
     }
 
-    /// 領域を構築（開始ブロックから終了ブロックまで）
-    fn build_region(&mut self, cfg: &ControlFlowGraph, start: BlockId, end: Option<BlockId>) -> ControlStructure {
-        if Some(start) == end {
-            return ControlStructure::BasicBlock(start);
+    /// ブロックへの遷移がbreak/continue/gotoになる場合はその文を返す
+    fn jump_to(&mut self, target: BlockId) -> Option<ControlStructure> {
+        if let Some(ctx) = self.loop_stack.last() {
+            if target == ctx.header || target == ctx.continue_target {
+                return Some(ControlStructure::Continue);
+            }
+            if Some(target) == ctx.follow {
+                if ctx.in_switch {
+                    return Some(self.goto(target));
+                }
+                return Some(ControlStructure::Break);
+            }
+            if !ctx.body.contains(&target) {
+                // ループの脱出先が複数ある場合
+                return Some(self.goto(target));
+            }
         }
 
-        let mut current = start;
-        let mut sequence = Vec::new();
-
-        loop {
-            if Some(current) == end {
-                break;
-            }
+        if self.emitted.contains(&target) {
+            // 既に出力したブロックへの合流（共有された末尾・既約でない領域）
+            return Some(self.goto(target));
+        }
 
-            sequence.push(ControlStructure::BasicBlock(current));
+        None
+    }
 
-            let block = match cfg.blocks.get(&current) {
-                Some(b) => b,
-                None => break,
-            };
+    fn goto(&mut self, target: BlockId) -> ControlStructure {
+        self.goto_targets.insert(target);
+        ControlStructure::Goto(target)
+    }
 
-            if block.successors.is_empty() {
-                break;
-            }
+    fn push_label(&self, block_id: BlockId, sequence: &mut Vec<ControlStructure>) {
+        if self.labels.contains(&block_id) {
+            sequence.push(ControlStructure::Label(block_id));
+        }
+    }
 
-            if block.successors.len() == 1 {
-                current = block.successors[0];
-            } else {
-                // 分岐がある場合は再帰的に構築
-                let branch_struct = self.build_control_structure(cfg, current);
-                sequence.push(branch_struct);
-                break;
+    /// 分岐の合流点を求める（現在のループの外やヘッダーへの合流は合流点としない）
+    fn region_merge_point(&self, block_id: BlockId) -> Option<BlockId> {
+        let merge = self.immediate_post_dominator(block_id)?;
+        if let Some(ctx) = self.loop_stack.last() {
+            if merge == ctx.header || !ctx.body.contains(&merge) {
+                return None;
             }
         }
+        Some(merge)
+    }
+}
 
-        if sequence.is_empty() {
-            ControlStructure::BasicBlock(start)
-        } else if sequence.len() == 1 {
-            sequence.into_iter().next().unwrap()
+impl Default for ControlFlowAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 逆ポストオーダー
+fn reverse_postorder(entry: BlockId, successors: &HashMap<BlockId, Vec<BlockId>>) -> Vec<BlockId> {
+    let mut visited = HashSet::new();
+    let mut postorder = Vec::new();
+    let mut stack = vec![(entry, 0usize)];
+    visited.insert(entry);
+
+    while let Some((block_id, index)) = stack.pop() {
+        let succs = successors.get(&block_id).map(|v| v.as_slice()).unwrap_or(&[]);
+        if let Some(&succ) = succs.get(index) {
+            stack.push((block_id, index + 1));
+            if visited.insert(succ) {
+                stack.push((succ, 0));
+            }
         } else {
-            ControlStructure::Sequence(sequence)
+            postorder.push(block_id);
         }
     }
 
-    /// 合流点を見つける
-    fn find_merge_point(&self, cfg: &ControlFlowGraph, branch1: BlockId, branch2: BlockId) -> Option<BlockId> {
-        let mut visited1 = HashSet::new();
-        let mut queue1 = VecDeque::new();
-        queue1.push_back(branch1);
+    postorder.reverse();
+    postorder
+}
 
-        // branch1から到達可能なすべてのブロックを収集
-        while let Some(block_id) = queue1.pop_front() {
-            if visited1.contains(&block_id) {
-                continue;
+/// 即時支配者を計算（Cooper-Harvey-Kennedy法、`rpo[0]` が入口）
+fn compute_idoms(rpo: &[BlockId], predecessors: &HashMap<BlockId, Vec<BlockId>>) -> HashMap<BlockId, BlockId> {
+    let mut idom = HashMap::new();
+    let Some(&entry) = rpo.first() else {
+        return idom;
+    };
+    let order: HashMap<BlockId, usize> = rpo.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+    idom.insert(entry, entry);
+
+    let intersect = |idom: &HashMap<BlockId, BlockId>, mut a: BlockId, mut b: BlockId| {
+        while a != b {
+            while order[&a] > order[&b] {
+                a = idom[&a];
             }
-            visited1.insert(block_id);
-
-            if let Some(block) = cfg.blocks.get(&block_id) {
-                for &succ in &block.successors {
-                    queue1.push_back(succ);
-                }
+            while order[&b] > order[&a] {
+                b = idom[&b];
             }
         }
+        a
+    };
 
-        // branch2から到達可能で、branch1からも到達可能な最初のブロックを探す
-        let mut visited2 = HashSet::new();
-        let mut queue2 = VecDeque::new();
-        queue2.push_back(branch2);
+    let mut changed = true;
+    while changed {
+        changed = false;
 
-        while let Some(block_id) = queue2.pop_front() {
-            if visited2.contains(&block_id) {
-                continue;
+        for &block_id in rpo.iter().skip(1) {
+            let mut new_idom: Option<BlockId> = None;
+            for pred in predecessors.get(&block_id).into_iter().flatten() {
+                if !idom.contains_key(pred) {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => *pred,
+                    Some(current) => intersect(&idom, *pred, current),
+                });
             }
-            visited2.insert(block_id);
 
-            if visited1.contains(&block_id) {
-                return Some(block_id);
+            if let Some(new_idom) = new_idom {
+                if idom.get(&block_id) != Some(&new_idom) {
+                    idom.insert(block_id, new_idom);
+                    changed = true;
+                }
             }
+        }
+    }
 
-            if let Some(block) = cfg.blocks.get(&block_id) {
-                for &succ in &block.successors {
-                    queue2.push_back(succ);
-                }
+    idom
+}
+
+/// 比較演算か
+fn is_comparison(opcode: OpCode) -> bool {
+    matches!(
+        opcode,
+        OpCode::IntEqual
+            | OpCode::IntNotEqual
+            | OpCode::IntSLess
+            | OpCode::IntSLessEqual
+            | OpCode::IntLess
+            | OpCode::IntLessEqual
+    )
+}
+
+/// 比較を否定する（a < b → b <= a）
+fn negate_comparison(mut op: PcodeOp) -> PcodeOp {
+    op.opcode = match op.opcode {
+        OpCode::IntEqual => OpCode::IntNotEqual,
+        OpCode::IntNotEqual => OpCode::IntEqual,
+        OpCode::IntSLess => OpCode::IntSLessEqual,
+        OpCode::IntSLessEqual => OpCode::IntSLess,
+        OpCode::IntLess => OpCode::IntLessEqual,
+        OpCode::IntLessEqual => OpCode::IntLess,
+        other => other,
+    };
+    if !matches!(op.opcode, OpCode::IntEqual | OpCode::IntNotEqual) {
+        op.inputs.swap(0, 1);
+    }
+    op
+}
+
+/// 定数以外はSSAバージョンを除く
+fn base_variable(vn: &Varnode) -> Varnode {
+    if vn.space == AddressSpace::Const {
+        vn.clone()
+    } else {
+        vn.base()
+    }
+}
+
+/// for文のヘッダーを `for (i = 0; i < n; i++)` の形式で出力
+pub fn format_for_header(
+    init: &PcodeOp,
+    cond: &PcodeOp,
+    step: &PcodeOp,
+    name: &mut dyn FnMut(&Varnode) -> String,
+) -> String {
+    let init_text = match (&init.output, init.inputs.first()) {
+        (Some(variable), Some(value)) => format!("{} = {}", name(variable), name(value)),
+        _ => String::new(),
+    };
+
+    let cond_text = match cond.inputs.as_slice() {
+        [lhs, rhs] => {
+            let operator = match cond.opcode {
+                OpCode::IntEqual => "==",
+                OpCode::IntNotEqual => "!=",
+                OpCode::IntSLess | OpCode::IntLess => "<",
+                OpCode::IntSLessEqual | OpCode::IntLessEqual => "<=",
+                _ => "?",
+            };
+            format!("{} {} {}", name(lhs), operator, name(rhs))
+        }
+        _ => String::new(),
+    };
+
+    let step_text = match (&step.output, step.inputs.get(1)) {
+        (Some(variable), Some(amount)) => {
+            let variable = name(variable);
+            let bits = amount.size.min(8) * 8;
+            let signed = if bits < 64 {
+                ((amount.offset << (64 - bits)) as i64) >> (64 - bits)
+            } else {
+                amount.offset as i64
+            };
+            let delta = if step.opcode == OpCode::IntSub { signed.wrapping_neg() } else { signed };
+            match delta {
+                1 => format!("{}++", variable),
+                -1 => format!("{}--", variable),
+                d if d < 0 => format!("{} -= {}", variable, d.unsigned_abs()),
+                d => format!("{} += {}", variable, d),
             }
         }
+        _ => String::new(),
+    };
 
-        None
+    format!("for ({}; {}; {})", init_text, cond_text, step_text)
+}
+
+/// ControlStructurePrinter用の変数名
+fn default_variable_name(vn: &Varnode) -> String {
+    match vn.space {
+        AddressSpace::Register => format!("r{}", vn.offset),
+        AddressSpace::Ram => format!("ptr_0x{:x}", vn.offset),
+        AddressSpace::Stack => format!("stack_{}", vn.offset),
+        AddressSpace::Unique => format!("tmp_0x{:x}", vn.offset),
+        AddressSpace::Const => format!("{}", vn.offset),
     }
+}
 
-    /// ヘッダーでループを検索
-    fn find_loop_by_header(&self, header: BlockId) -> Option<LoopInfo> {
-        self.loops.iter().find(|l| l.header == header).cloned()
+fn make_sequence(mut sequence: Vec<ControlStructure>) -> ControlStructure {
+    if sequence.len() == 1 {
+        sequence.pop().unwrap()
+    } else {
+        ControlStructure::Sequence(sequence)
     }
 }
 
-impl Default for ControlFlowAnalyzer {
-    fn default() -> Self {
-        Self::new()
+/// ループ本体を作成（末尾のcontinueは不要なので取り除く）
+fn make_loop_body(sequence: Vec<ControlStructure>) -> ControlStructure {
+    let mut body = ControlStructure::Sequence(sequence);
+    strip_trailing_continue(&mut body);
+    match body {
+        ControlStructure::Sequence(sequence) => make_sequence(sequence),
+        other => other,
     }
 }
 
+fn strip_trailing_continue(structure: &mut ControlStructure) {
+    match structure {
+        ControlStructure::Continue => *structure = ControlStructure::Sequence(Vec::new()),
+        ControlStructure::Sequence(sequence) => {
+            if let Some(last) = sequence.last_mut() {
+                strip_trailing_continue(last);
+                if is_empty(last) {
+                    sequence.pop();
+                }
+            }
+        }
+        ControlStructure::IfThen { then_branch, .. } => strip_trailing_continue(then_branch),
+        ControlStructure::IfThenElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            strip_trailing_continue(then_branch);
+            if let Some(else_br) = else_branch.as_mut() {
+                strip_trailing_continue(else_br);
+            }
+
+            // 空になった分岐を取り除く
+            let else_empty = else_branch.as_ref().is_none_or(|b| is_empty(b));
+            if else_empty {
+                *structure = ControlStructure::IfThen {
+                    condition: condition.clone(),
+                    then_branch: then_branch.clone(),
+                };
+            } else if is_empty(then_branch) {
+                *structure = ControlStructure::IfThen {
+                    condition: condition.clone().negate(),
+                    then_branch: else_branch.take().unwrap(),
+                };
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_empty(structure: &ControlStructure) -> bool {
+    matches!(structure, ControlStructure::Sequence(sequence) if sequence.is_empty())
+}
+
 /// 制御構造を人間が読みやすい形式で出力
 pub struct ControlStructurePrinter {
     indent_level: usize,
@@ -549,12 +1210,12 @@ impl ControlStructurePrinter {
                 result
             }
             ControlStructure::IfThenElse {
-                condition_block,
+                condition,
                 then_branch,
                 else_branch,
             } => {
                 let indent = "  ".repeat(self.indent_level);
-                let mut result = format!("{}if (block_{}) {{\n", indent, condition_block);
+                let mut result = format!("{}if ({}) {{\n", indent, condition);
 
                 self.indent_level += 1;
                 result.push_str(&self.print(then_branch));
@@ -571,11 +1232,11 @@ impl ControlStructurePrinter {
                 result
             }
             ControlStructure::IfThen {
-                condition_block,
+                condition,
                 then_branch,
             } => {
                 let indent = "  ".repeat(self.indent_level);
-                let mut result = format!("{}if (block_{}) {{\n", indent, condition_block);
+                let mut result = format!("{}if ({}) {{\n", indent, condition);
 
                 self.indent_level += 1;
                 result.push_str(&self.print(then_branch));
@@ -585,11 +1246,28 @@ impl ControlStructurePrinter {
                 result
             }
             ControlStructure::While {
-                condition_block,
+                condition,
                 body,
             } => {
                 let indent = "  ".repeat(self.indent_level);
-                let mut result = format!("{}while (block_{}) {{\n", indent, condition_block);
+                let mut result = format!("{}while ({}) {{\n", indent, condition);
+
+                self.indent_level += 1;
+                result.push_str(&self.print(body));
+                self.indent_level -= 1;
+
+                result.push_str(&format!("{}}}\n", indent));
+                result
+            }
+            ControlStructure::For {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                let indent = "  ".repeat(self.indent_level);
+                let header = format_for_header(init, cond, step, &mut default_variable_name);
+                let mut result = format!("{}{} {{\n", indent, header);
 
                 self.indent_level += 1;
                 result.push_str(&self.print(body));
@@ -600,7 +1278,7 @@ impl ControlStructurePrinter {
             }
             ControlStructure::DoWhile {
                 body,
-                condition_block,
+                condition,
             } => {
                 let indent = "  ".repeat(self.indent_level);
                 let mut result = format!("{}do {{\n", indent);
@@ -609,7 +1287,7 @@ impl ControlStructurePrinter {
                 result.push_str(&self.print(body));
                 self.indent_level -= 1;
 
-                result.push_str(&format!("{}}} while (block_{});\n", indent, condition_block));
+                result.push_str(&format!("{}}} while ({});\n", indent, condition));
                 result
             }
             ControlStructure::InfiniteLoop { body } => {
@@ -660,6 +1338,14 @@ impl ControlStructurePrinter {
                 let indent = "  ".repeat(self.indent_level);
                 format!("{}continue;\n", indent)
             }
+            ControlStructure::Goto(id) => {
+                let indent = "  ".repeat(self.indent_level);
+                format!("{}goto label_{};\n", indent, id)
+            }
+            ControlStructure::Label(id) => {
+                let indent = "  ".repeat(self.indent_level);
+                format!("{}label_{}:\n", indent, id)
+            }
         }
     }
 }
@@ -745,4 +1431,126 @@ mod tests {
         assert!(!analyzer.loops.is_empty());
         println!("Detected {} loops", analyzer.loops.len());
     }
+
+    /// 辺のリストからCFGを作成（2分岐のブロックにはCBRANCHを置く）
+    fn build_cfg(edges: &[(BlockId, &[BlockId])]) -> ControlFlowGraph {
+        let mut cfg = ControlFlowGraph::new();
+        cfg.entry_block = 0;
+
+        for &(id, succs) in edges {
+            let mut block = BasicBlock::new(id, id as u64 * 0x10);
+            block.successors = succs.to_vec();
+            if succs.len() == 2 {
+                block.ops.push(PcodeOp::no_output(OpCode::CBranch, vec![], block.start_address));
+            }
+            cfg.blocks.insert(id, block);
+        }
+        for &(id, succs) in edges {
+            for succ in succs {
+                cfg.blocks.get_mut(succ).unwrap().predecessors.push(id);
+            }
+        }
+
+        cfg
+    }
+
+    fn structure_text(cfg: &ControlFlowGraph) -> String {
+        let mut analyzer = ControlFlowAnalyzer::new();
+        let structure = analyzer.analyze(cfg);
+        ControlStructurePrinter::new().print(&structure)
+    }
+
+    #[test]
+    fn test_short_circuit_conditions() {
+        // if (block_0 && block_1) { block_2 }
+        let and_cfg = build_cfg(&[(0, &[1, 3]), (1, &[2, 3]), (2, &[3]), (3, &[])]);
+        let text = structure_text(&and_cfg);
+        assert!(text.contains("if (block_0 && block_1) {"), "{}", text);
+        assert!(!text.contains("block_1;"), "{}", text);
+
+        // if (block_0 || block_1) { block_2 }
+        let or_cfg = build_cfg(&[(0, &[2, 1]), (1, &[2, 3]), (2, &[3]), (3, &[])]);
+        let text = structure_text(&or_cfg);
+        assert!(text.contains("if (block_0 || block_1) {"), "{}", text);
+        assert!(text.ends_with("block_3;\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_multi_exit_loop_break_continue() {
+        // while (block_0) { if (block_1) break; if (block_2) continue; block_3 }
+        let cfg = build_cfg(&[(0, &[1, 4]), (1, &[4, 2]), (2, &[0, 3]), (3, &[0]), (4, &[])]);
+        let text = structure_text(&cfg);
+
+        assert!(text.contains("while (block_0) {"), "{}", text);
+        assert!(text.contains("if (block_1) {\n    break;"), "{}", text);
+        assert!(text.contains("if (block_2) {\n    continue;"), "{}", text);
+        // 末尾のcontinueは出力しない
+        assert_eq!(text.matches("continue;").count(), 1, "{}", text);
+        assert!(!text.contains("goto"), "{}", text);
+    }
+
+    #[test]
+    fn test_irreducible_region_goto_fallback() {
+        // 0 -> 1, 0 -> 2, 1 <-> 2: どちらのブロックも他方を支配しない
+        let cfg = build_cfg(&[(0, &[1, 2]), (1, &[2]), (2, &[1, 3]), (3, &[])]);
+
+        let mut analyzer = ControlFlowAnalyzer::new();
+        let structure = analyzer.analyze(&cfg);
+        let text = ControlStructurePrinter::new().print(&structure);
+
+        assert!(analyzer.get_loops().is_empty());
+        assert!(text.contains("label_1:"), "{}", text);
+        assert!(text.contains("goto label_1;"), "{}", text);
+        // ブロックは重複して出力しない
+        assert_eq!(text.matches("block_1;").count(), 1, "{}", text);
+        assert_eq!(text.matches("block_3;").count(), 1, "{}", text);
+
+        // 合流しない共有末尾もgotoで表す
+        let shared_tail = build_cfg(&[(0, &[1, 2]), (1, &[3]), (2, &[3, 4]), (3, &[]), (4, &[])]);
+        let text = structure_text(&shared_tail);
+        assert_eq!(text.matches("block_3;").count(), 1, "{}", text);
+        assert!(text.contains("goto label_3;"), "{}", text);
+    }
+
+    #[test]
+    fn test_for_loop_recovery() {
+        use crate::decompiler_prototype::c_printer::CPrinter;
+        use crate::decompiler_prototype::ssa::SSATransform;
+        use crate::decompiler_prototype::type_inference::TypeInference;
+
+        // i = 0; while (i < n) { i = i + 1; }
+        let i = Varnode::register(0, 4);
+        let n = Varnode::register(64, 4);
+        let cmp = Varnode::unique(0x100, 1);
+
+        let mut cfg = build_cfg(&[(0, &[1]), (1, &[2, 3]), (2, &[1]), (3, &[])]);
+        cfg.blocks.get_mut(&0).unwrap().ops = vec![PcodeOp::unary(OpCode::Copy, i.clone(), Varnode::constant(0, 4), 0x00)];
+        cfg.blocks.get_mut(&1).unwrap().ops = vec![
+            PcodeOp::binary(OpCode::IntSLess, cmp.clone(), i.clone(), n.clone(), 0x10),
+            PcodeOp::no_output(OpCode::CBranch, vec![Varnode::constant(0x20, 8), cmp.clone()], 0x10),
+        ];
+        cfg.blocks.get_mut(&2).unwrap().ops = vec![
+            PcodeOp::binary(OpCode::IntAdd, i.clone(), i.clone(), Varnode::constant(1, 4), 0x20),
+            PcodeOp::no_output(OpCode::Branch, vec![Varnode::constant(0x10, 8)], 0x24),
+        ];
+        cfg.blocks.get_mut(&3).unwrap().ops = vec![PcodeOp::no_output(OpCode::Return, vec![], 0x30)];
+
+        SSATransform::new().transform(&mut cfg);
+
+        let mut analyzer = ControlFlowAnalyzer::new();
+        let structure = analyzer.analyze(&cfg);
+        let text = ControlStructurePrinter::new().print(&structure);
+
+        assert_eq!(analyzer.get_loops()[0].loop_type, LoopType::For);
+        assert!(text.contains("for (r0 = 0; r0 < r64; r0++) {\n  block_2;\n}"), "{}", text);
+
+        let ControlStructure::Sequence(items) = &structure else {
+            panic!("unexpected structure: {:?}", structure);
+        };
+        let Some(ControlStructure::For { init, cond, step, .. }) = items.get(1) else {
+            panic!("unexpected structure: {:?}", structure);
+        };
+        let mut printer = CPrinter::new(TypeInference::new());
+        assert_eq!(printer.print_for_header(init, cond, step), "for (r0 = 0; r0 < r64; r0++)");
+    }
 }
@@ -3,6 +3,7 @@
 /// Def-Use Chain（定義-使用連鎖）を構築してデータフローを追跡
 /// Ghidraのvarnode.hh/op.hhに基づく実装
 
+use crate::decompiler_prototype::memory_ssa::MemorySSA;
 use crate::decompiler_prototype::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
 use std::collections::{HashMap, HashSet};
 
@@ -33,7 +34,7 @@ impl From<&Varnode> for VarnodeId {
             space: vn.space,
             offset: vn.offset,
             size: vn.size,
-            generation: 0, // デフォルト世代
+            generation: vn.version as usize,
         }
     }
 }
@@ -161,8 +162,10 @@ impl DefUseChain {
                 return None;
             }
 
-            // 定義操作を取得
-            let def_op = self.get_def(&current)?;
+            // 定義操作を取得（定義のない入力値ならそれがコピー元）
+            let Some(def_op) = self.get_def(&current) else {
+                return Some(current);
+            };
 
             // Copy操作なら入力をさらに追跡
             if def_op.opcode == OpCode::Copy && !def_op.inputs.is_empty() {
@@ -227,19 +230,63 @@ pub struct DataFlowStats {
 /// Copy Propagation最適化
 ///
 /// V1 = V0; V2 = V1; => V2 = V0; のような連鎖コピーを削減
+/// Memory SSAを与えると [sp+8] = V0; V1 = [sp+8]; => V1 = V0; のようにStore→Loadも転送する
 pub struct CopyPropagation {
     du_chain: DefUseChain,
+    memory_ssa: Option<MemorySSA>,
+    forwarded_loads: usize,
 }
 
 impl CopyPropagation {
     pub fn new(du_chain: DefUseChain) -> Self {
-        Self { du_chain }
+        Self {
+            du_chain,
+            memory_ssa: None,
+            forwarded_loads: 0,
+        }
+    }
+
+    /// Store→Load転送を行うCopy propagationを作成
+    ///
+    /// `memory_ssa` は `apply` に渡す操作列から構築したものであること
+    pub fn with_memory_ssa(du_chain: DefUseChain, memory_ssa: MemorySSA) -> Self {
+        Self {
+            du_chain,
+            memory_ssa: Some(memory_ssa),
+            forwarded_loads: 0,
+        }
+    }
+
+    /// Copyに置き換えたLoadの数
+    pub fn forwarded_loads(&self) -> usize {
+        self.forwarded_loads
     }
 
     /// Copy propagationを適用
     pub fn apply(&mut self, ops: &mut Vec<PcodeOp>) -> usize {
         let mut propagation_count = 0;
 
+        // Store→Load転送: 値が確定しているLoadをCopyに置き換える
+        if let Some(memory_ssa) = self.memory_ssa.take() {
+            for (op_id, op) in ops.iter_mut().enumerate() {
+                if op.opcode != OpCode::Load {
+                    continue;
+                }
+                if let Some(value) = memory_ssa.forwarded_value(op_id) {
+                    op.opcode = OpCode::Copy;
+                    op.inputs = vec![value.clone()];
+                    self.forwarded_loads += 1;
+                }
+            }
+
+            if self.forwarded_loads > 0 {
+                propagation_count += self.forwarded_loads;
+                // 新しいCopyを含めて連鎖を辿れるようにする
+                self.du_chain = DefUseChain::new();
+                self.du_chain.build(ops);
+            }
+        }
+
         for op in ops.iter_mut() {
             // 入力Varnodeをコピー元まで追跡
             for input in &mut op.inputs {
@@ -259,17 +306,43 @@ impl CopyPropagation {
 /// Dead Code Elimination
 ///
 /// 到達不能な操作や未使用の定義を削除
+/// Memory SSAを与えると、読まれないスタックへのStore（レジスタ退避など）も削除する
 pub struct DeadCodeElimination {
     du_chain: DefUseChain,
+    memory_ssa: Option<MemorySSA>,
 }
 
 impl DeadCodeElimination {
     pub fn new(du_chain: DefUseChain) -> Self {
-        Self { du_chain }
+        Self {
+            du_chain,
+            memory_ssa: None,
+        }
+    }
+
+    /// 不要なStoreも削除するDead code eliminationを作成
+    ///
+    /// `memory_ssa` は `eliminate` に渡す操作列から構築したものであること
+    pub fn with_memory_ssa(du_chain: DefUseChain, memory_ssa: MemorySSA) -> Self {
+        Self {
+            du_chain,
+            memory_ssa: Some(memory_ssa),
+        }
     }
 
     /// Dead codeを除去
     pub fn eliminate(&self, ops: &mut Vec<PcodeOp>) -> usize {
+        let mut dead_stores = 0;
+        if let Some(ref memory_ssa) = self.memory_ssa {
+            let mut op_id = 0;
+            ops.retain(|_| {
+                let keep = !memory_ssa.is_dead_store(op_id);
+                op_id += 1;
+                keep
+            });
+            dead_stores = memory_ssa.dead_stores().len();
+        }
+
         let reachable = self.du_chain.collect_reachable_ops();
         let original_len = ops.len();
 
@@ -289,7 +362,7 @@ impl DeadCodeElimination {
             })
             .count();
 
-        removed
+        removed + dead_stores
     }
 }
 
@@ -338,4 +411,38 @@ mod tests {
         assert!(source.is_some());
         assert_eq!(source.unwrap(), v0);
     }
+
+    #[test]
+    fn test_store_load_forwarding_and_dead_store_elimination() {
+        let rsp = Varnode::register(32, 8);
+        let rcx = Varnode::register(8, 8);
+        let rax = Varnode::register(0, 8);
+        let slot = Varnode::unique(0x100, 8);
+
+        // mov [rsp+8], rcx; mov rax, [rsp+8]; ret
+        let mut ops = vec![
+            PcodeOp::binary(OpCode::IntAdd, slot.clone(), rsp.clone(), Varnode::constant(8, 8), 0x1000),
+            PcodeOp::no_output(OpCode::Store, vec![slot.clone(), rcx.clone()], 0x1000),
+            PcodeOp::unary(OpCode::Load, rax.clone(), slot.clone(), 0x1005),
+            PcodeOp::no_output(OpCode::Return, vec![rax.clone()], 0x100a),
+        ];
+
+        let mut du_chain = DefUseChain::new();
+        du_chain.build(&ops);
+        let mut copy_prop = CopyPropagation::with_memory_ssa(du_chain, MemorySSA::build(&ops));
+        copy_prop.apply(&mut ops);
+
+        assert_eq!(copy_prop.forwarded_loads(), 1);
+        assert_eq!(ops[2].opcode, OpCode::Copy);
+        assert_eq!(ops[2].inputs, vec![rcx.clone()]);
+        assert_eq!(ops[3].inputs, vec![rcx.clone()]);
+
+        // 転送後は退避Storeを読むLoadがないため削除できる
+        let mut du_chain = DefUseChain::new();
+        du_chain.build(&ops);
+        let dce = DeadCodeElimination::with_memory_ssa(du_chain, MemorySSA::build(&ops));
+        dce.eliminate(&mut ops);
+
+        assert!(ops.iter().all(|op| op.opcode != OpCode::Store));
+    }
 }
@@ -0,0 +1,481 @@
+/// デコンパイル結果のディスクストア
+///
+/// 1関数につき1レコードを、ファイルハッシュ + 関数ハッシュ + デコンパイラのバージョンで
+/// 決まるパスへ書き込む。書き込みは一時ファイルからのrenameで行うため、
+/// 並行して読むプロセスが書きかけのレコードを見ることはない
+/// 関数ハッシュは位置に依存しないため、別ビルドのレコードや関数の名前・注釈も引き継げる
+
+use super::function_hash::FINGERPRINT_VERSION;
+use super::parallel_analyzer::CachedFunctionResult;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// レコード形式のバージョン（形式を変えたら上げる）
+pub const STORE_SCHEMA_VERSION: u32 = 1;
+
+/// デコンパイルパイプラインのバージョン（解析結果が変わる変更をしたら上げる）
+pub const DECOMPILER_VERSION: u32 = 2;
+
+/// デフォルトのストア容量の上限
+pub const DEFAULT_MAX_STORE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 上限を超えたとき、この割合まで古いレコードを削除する
+const EVICTION_TARGET_PERCENT: u64 = 90;
+
+/// 一時ファイル名の重複を避けるための連番
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// ディスク上のレコード
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredRecord {
+    schema: u32,
+    decompiler_version: u32,
+    file_hash: String,
+    function_hash: u64,
+    result: CachedFunctionResult,
+}
+
+/// 関数の名前と注釈（関数ハッシュをキーにビルドをまたいで引き継ぐ）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionAnnotation {
+    /// ユーザーが付けた関数名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// コメント
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// ストアの統計情報
+#[derive(Debug, Clone, Default)]
+pub struct StoreStatistics {
+    /// レコードを持つバイナリの数
+    pub binaries: usize,
+    /// レコード数
+    pub records: usize,
+    /// レコードの合計サイズ（バイト）
+    pub bytes: u64,
+}
+
+/// デコンパイル結果のディスクストア
+pub struct DecompileStore {
+    /// 現在のスキーマ・デコンパイラバージョンのレコードを置くディレクトリ
+    root: PathBuf,
+    /// 関数の名前・注釈を置くディレクトリ（容量制限・デコンパイラのバージョンの対象外）
+    annotations: PathBuf,
+    /// 容量の上限（バイト）
+    max_bytes: u64,
+    /// レコードの合計サイズ（概算）
+    total_bytes: Mutex<u64>,
+}
+
+impl DecompileStore {
+    /// ストアを開く
+    ///
+    /// 別のスキーマ・デコンパイラバージョンのレコード、別の正規化方式の注釈、
+    /// 旧形式のキャッシュファイルは削除する
+    pub fn open<P: AsRef<Path>>(cache_dir: P, max_bytes: u64) -> Result<Self> {
+        let cache_dir = cache_dir.as_ref();
+        let current = Self::version_dir_name();
+        let annotations = format!("annotations-f{}", FINGERPRINT_VERSION);
+        fs::create_dir_all(cache_dir.join(&current))?;
+        fs::create_dir_all(cache_dir.join(&annotations))?;
+
+        for entry in fs::read_dir(cache_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let stale = (name.starts_with("store-") && name != current)
+                || (name.starts_with("annotations-") && name != annotations);
+            if path.is_dir() && stale {
+                let _ = fs::remove_dir_all(&path);
+            } else if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
+                // 全関数を1ファイルに書いていた旧形式
+                let _ = fs::remove_file(&path);
+            }
+        }
+
+        let store = Self {
+            root: cache_dir.join(current),
+            annotations: cache_dir.join(annotations),
+            max_bytes,
+            total_bytes: Mutex::new(0),
+        };
+        let bytes = store.statistics().bytes;
+        if let Ok(mut total) = store.total_bytes.lock() {
+            *total = bytes;
+        }
+
+        Ok(store)
+    }
+
+    fn version_dir_name() -> String {
+        format!("store-s{}-d{}", STORE_SCHEMA_VERSION, DECOMPILER_VERSION)
+    }
+
+    fn binary_dir(&self, file_hash: &str) -> PathBuf {
+        self.root.join(file_hash)
+    }
+
+    fn record_path(&self, file_hash: &str, function_hash: u64) -> PathBuf {
+        self.binary_dir(file_hash).join(format!("{:016x}.json", function_hash))
+    }
+
+    /// レコードを取得
+    pub fn get(&self, file_hash: &str, function_hash: u64) -> Option<CachedFunctionResult> {
+        let path = self.record_path(file_hash, function_hash);
+        let record = read_record(&path)?;
+        if record.file_hash != file_hash || record.function_hash != function_hash {
+            return None;
+        }
+
+        // 最近使ったレコードを削除対象から遠ざける
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(record.result)
+    }
+
+    /// 別のバイナリ（以前のビルドなど）から同じ関数ハッシュのレコードを探す
+    ///
+    /// 結果のアドレスは元のバイナリのものなので、呼び出し側で付け替える
+    pub fn find_function(&self, function_hash: u64) -> Option<CachedFunctionResult> {
+        let file_name = format!("{:016x}.json", function_hash);
+        let binaries = fs::read_dir(&self.root).ok()?;
+
+        binaries
+            .flatten()
+            .map(|binary| binary.path().join(&file_name))
+            .filter_map(|path| read_record(&path))
+            .find(|record| record.function_hash == function_hash)
+            .map(|record| record.result)
+    }
+
+    /// 関数の名前・注釈を取得
+    pub fn get_annotation(&self, function_hash: u64) -> Option<FunctionAnnotation> {
+        let data = fs::read(self.annotation_path(function_hash)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// 関数の名前・注釈を保存（空なら削除）
+    pub fn put_annotation(&self, function_hash: u64, annotation: &FunctionAnnotation) -> Result<()> {
+        let path = self.annotation_path(function_hash);
+        if annotation.name.is_none() && annotation.comment.is_none() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.annotations)?;
+        let temp = self.annotations.join(format!(
+            ".{:016x}.{}.{}.tmp",
+            function_hash,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp, serde_json::to_vec(annotation)?)?;
+        if let Err(e) = fs::rename(&temp, &path) {
+            let _ = fs::remove_file(&temp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn annotation_path(&self, function_hash: u64) -> PathBuf {
+        self.annotations.join(format!("{:016x}.json", function_hash))
+    }
+
+    /// バイナリの全レコードを取得（関数アドレス → 結果）
+    pub fn load_binary(&self, file_hash: &str) -> HashMap<u64, CachedFunctionResult> {
+        let Ok(entries) = fs::read_dir(self.binary_dir(file_hash)) else {
+            return HashMap::new();
+        };
+
+        entries
+            .flatten()
+            .filter(|entry| is_record(&entry.path()))
+            .filter_map(|entry| read_record(&entry.path()))
+            .filter(|record| record.file_hash == file_hash)
+            .map(|record| (record.result.address, record.result))
+            .collect()
+    }
+
+    /// レコードを書き込む
+    ///
+    /// 同じキーのレコードは同じ解析結果なので、既に存在すれば書き込まない
+    pub fn put(&self, file_hash: &str, function_hash: u64, result: &CachedFunctionResult) -> Result<()> {
+        let path = self.record_path(file_hash, function_hash);
+        if path.exists() {
+            return Ok(());
+        }
+
+        let record = StoredRecord {
+            schema: STORE_SCHEMA_VERSION,
+            decompiler_version: DECOMPILER_VERSION,
+            file_hash: file_hash.to_string(),
+            function_hash,
+            result: result.clone(),
+        };
+        let data = serde_json::to_vec(&record)?;
+
+        let dir = self.binary_dir(file_hash);
+        fs::create_dir_all(&dir)?;
+        let temp = dir.join(format!(
+            ".{:016x}.{}.{}.tmp",
+            function_hash,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp, &data)?;
+
+        if let Err(e) = fs::rename(&temp, &path) {
+            let _ = fs::remove_file(&temp);
+            // 他のプロセスが先に同じレコードを書いた
+            if !path.exists() {
+                return Err(e.into());
+            }
+            return Ok(());
+        }
+
+        let over_limit = match self.total_bytes.lock() {
+            Ok(mut total) => {
+                *total += data.len() as u64;
+                *total > self.max_bytes
+            }
+            Err(_) => false,
+        };
+        if over_limit {
+            self.evict()?;
+        }
+
+        Ok(())
+    }
+
+    /// 更新日時の古いレコードから、合計サイズが上限の90%以下になるまで削除
+    fn evict(&self) -> Result<()> {
+        let Ok(mut total) = self.total_bytes.lock() else {
+            return Ok(());
+        };
+
+        let mut records = Vec::new();
+        for path in self.record_files() {
+            if let Ok(metadata) = fs::metadata(&path) {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                records.push((modified, metadata.len(), path));
+            }
+        }
+        records.sort();
+
+        *total = records.iter().map(|(_, size, _)| size).sum();
+        let target = self.max_bytes / 100 * EVICTION_TARGET_PERCENT;
+
+        for (_, size, path) in records {
+            if *total <= target {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                *total -= size;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// バイナリのレコードをすべて削除
+    pub fn remove_binary(&self, file_hash: &str) -> Result<()> {
+        let dir = self.binary_dir(file_hash);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        if let Ok(mut total) = self.total_bytes.lock() {
+            *total = self.record_files().iter().filter_map(|path| fs::metadata(path).ok()).map(|m| m.len()).sum();
+        }
+        Ok(())
+    }
+
+    /// すべてのレコードを削除
+    pub fn clear(&self) -> Result<()> {
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)?;
+        }
+        fs::create_dir_all(&self.root)?;
+        if let Ok(mut total) = self.total_bytes.lock() {
+            *total = 0;
+        }
+        Ok(())
+    }
+
+    /// 統計情報
+    pub fn statistics(&self) -> StoreStatistics {
+        let mut stats = StoreStatistics::default();
+        let Ok(binaries) = fs::read_dir(&self.root) else {
+            return stats;
+        };
+
+        for binary in binaries.flatten() {
+            let Ok(records) = fs::read_dir(binary.path()) else {
+                continue;
+            };
+            let mut has_records = false;
+            for record in records.flatten() {
+                if is_record(&record.path()) {
+                    has_records = true;
+                    stats.records += 1;
+                    stats.bytes += record.metadata().map(|m| m.len()).unwrap_or(0);
+                }
+            }
+            if has_records {
+                stats.binaries += 1;
+            }
+        }
+
+        stats
+    }
+
+    /// すべてのレコードファイル
+    fn record_files(&self) -> Vec<PathBuf> {
+        let Ok(binaries) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+
+        binaries
+            .flatten()
+            .filter_map(|binary| fs::read_dir(binary.path()).ok())
+            .flat_map(|records| records.flatten().map(|record| record.path()))
+            .filter(|path| is_record(path))
+            .collect()
+    }
+}
+
+/// 書き込み途中の一時ファイルではないレコードか
+fn is_record(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("json")
+        && !path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .is_some_and(|name| name.starts_with('.'))
+}
+
+/// レコードを読み込む（壊れている・バージョンが違う場合はNone）
+fn read_record(path: &Path) -> Option<StoredRecord> {
+    let data = fs::read(path).ok()?;
+    let record: StoredRecord = serde_json::from_slice(&data).ok()?;
+    (record.schema == STORE_SCHEMA_VERSION && record.decompiler_version == DECOMPILER_VERSION).then_some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn result(address: u64) -> CachedFunctionResult {
+        CachedFunctionResult {
+            address,
+            function_hash: 0,
+            pcode_count: 10,
+            block_count: 1,
+            type_count: 2,
+            loop_count: 0,
+            control_structure: "x".repeat(200),
+            type_conflicts: Vec::new(),
+            cached_at: 0,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ghidra_mcp_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_put_get_and_stale_versions() -> Result<()> {
+        let dir = temp_dir("store_test");
+        fs::create_dir_all(dir.join("store-s0-d0/abc"))?;
+        fs::write(dir.join("abc.json"), "{}")?;
+
+        let store = DecompileStore::open(&dir, DEFAULT_MAX_STORE_BYTES)?;
+        // 旧バージョンのディレクトリと旧形式のファイルは削除される
+        assert!(!dir.join("store-s0-d0").exists());
+        assert!(!dir.join("abc.json").exists());
+
+        assert!(store.get("abc", 1).is_none());
+        store.put("abc", 1, &result(0x1000))?;
+        store.put("abc", 2, &result(0x1010))?;
+
+        assert_eq!(store.get("abc", 1).map(|r| r.address), Some(0x1000));
+        assert!(store.get("def", 1).is_none());
+        assert_eq!(store.load_binary("abc").len(), 2);
+
+        let stats = store.statistics();
+        assert_eq!((stats.binaries, stats.records), (1, 2));
+
+        // 別のビルドからは関数ハッシュだけで見つかる
+        assert_eq!(store.find_function(2).map(|r| r.address), Some(0x1010));
+        assert!(store.find_function(3).is_none());
+
+        store.remove_binary("abc")?;
+        assert!(store.load_binary("abc").is_empty());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_annotations_survive_clear() -> Result<()> {
+        let dir = temp_dir("store_annotation_test");
+        let store = DecompileStore::open(&dir, DEFAULT_MAX_STORE_BYTES)?;
+
+        let annotation = FunctionAnnotation {
+            name: Some("update_player".to_string()),
+            comment: None,
+        };
+        store.put_annotation(7, &annotation)?;
+        store.clear()?;
+        assert_eq!(store.get_annotation(7), Some(annotation));
+
+        // 空の注釈は削除になる
+        store.put_annotation(7, &FunctionAnnotation::default())?;
+        assert!(store.get_annotation(7).is_none());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_based_eviction() -> Result<()> {
+        let dir = temp_dir("store_eviction_test");
+        let record_size = serde_json::to_vec(&StoredRecord {
+            schema: STORE_SCHEMA_VERSION,
+            decompiler_version: DECOMPILER_VERSION,
+            file_hash: "abc".to_string(),
+            function_hash: 0,
+            result: result(0),
+        })?
+        .len() as u64;
+
+        // 4レコード分の容量
+        let store = DecompileStore::open(&dir, record_size * 4 + record_size / 2)?;
+        for i in 0..6u64 {
+            store.put("abc", i, &result(0x1000 + i))?;
+        }
+
+        let stats = store.statistics();
+        assert!(stats.records <= 4);
+        assert!(stats.bytes <= record_size * 4 + record_size / 2);
+        // 最後に書いたレコードは残る
+        assert!(store.get("abc", 5).is_some());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+/// 関数単位のコンテンツハッシュ
+///
+/// 位置に依存する部分（相対分岐・呼び出しの分岐先、RIP相対の変位、アドレスらしい即値）を
+/// 正規化した命令列からハッシュを求める
+/// パッチで関数の配置がずれても本体が同じなら同じハッシュになるため、
+/// キャッシュ済みの解析結果や名前・注釈をビルドをまたいで引き継げる
+
+use anyhow::{anyhow, Result};
+use capstone::arch::x86::{ArchMode, X86OperandType, X86Reg};
+use capstone::prelude::*;
+use goblin::Object;
+use xxhash_rust::xxh3::Xxh3;
+
+/// 正規化方式のバージョン（変えたらハッシュをキーにした保存データは引き継がれない）
+pub const FINGERPRINT_VERSION: u32 = 1;
+
+/// これより小さい即値・変位は定数とみなす
+const MIN_ADDRESS: u64 = 0x10000;
+
+/// 関数からこれ以上離れた即値・変位はアドレスとみなさない
+const ADDRESS_WINDOW: u64 = 0x1000_0000;
+
+/// 関数のフィンガープリント
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionFingerprint {
+    /// 正規化した命令列のハッシュ
+    pub hash: u64,
+    /// 関数本体のバイト数
+    pub size: usize,
+    /// 関数本体の命令数
+    pub instruction_count: usize,
+}
+
+/// 関数先頭からのコードを逆アセンブルし、関数本体のフィンガープリントを求める
+///
+/// 関数の終端は、関数内の分岐先をすべて過ぎた後のret/jmp/hlt/int3/ud2とする
+pub fn fingerprint_function(code: &[u8], address: u64, max_instructions: usize) -> Result<FunctionFingerprint> {
+    let cs = Capstone::new()
+        .x86()
+        .mode(ArchMode::Mode64)
+        .detail(true)
+        .build()
+        .map_err(|e| anyhow!("Failed to create Capstone engine: {}", e))?;
+
+    let insns = cs
+        .disasm_count(code, address, max_instructions)
+        .map_err(|e| anyhow!("Disassembly failed: {}", e))?;
+    if insns.is_empty() {
+        return Err(anyhow!("No instructions at 0x{:x}", address));
+    }
+
+    let window_end = address + code.len() as u64;
+    let mut hasher = Xxh3::new();
+    hasher.update(&FINGERPRINT_VERSION.to_le_bytes());
+
+    // 関数内の最も先の分岐先
+    let mut furthest_target = address;
+    let mut size = 0;
+    let mut instruction_count = 0;
+
+    for insn in insns.iter() {
+        let detail = cs
+            .insn_detail(insn)
+            .map_err(|e| anyhow!("Failed to get instruction detail: {}", e))?;
+        let arch_detail = detail.arch_detail();
+        let x86 = arch_detail.x86().ok_or_else(|| anyhow!("Not an x86 instruction"))?;
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let is_call = mnemonic == "call";
+        let is_branch = mnemonic.starts_with('j') || mnemonic.starts_with("loop") || is_call;
+
+        // 命令の骨格（プレフィックス・オペコード・REX・ModR/M・SIB）
+        hasher.update(x86.prefix());
+        hasher.update(x86.opcode());
+        hasher.update(&[x86.rex(), x86.modrm(), x86.sib()]);
+
+        for operand in x86.operands() {
+            match operand.op_type {
+                X86OperandType::Reg(reg) => {
+                    hasher.update(&[0x01]);
+                    hasher.update(&reg.0.to_le_bytes());
+                }
+                X86OperandType::Imm(value) if is_branch => {
+                    let target = value as u64;
+                    if !is_call && target >= address && target < window_end {
+                        // 関数内の分岐は先頭からのオフセットで表す
+                        hasher.update(&[0x02]);
+                        hasher.update(&(target - address).to_le_bytes());
+                        furthest_target = furthest_target.max(target);
+                    } else {
+                        // 関数外への分岐・呼び出しは分岐先を区別しない
+                        hasher.update(&[0x03]);
+                    }
+                }
+                X86OperandType::Imm(value) => hash_value(&mut hasher, value, address),
+                X86OperandType::Mem(mem) => {
+                    hasher.update(&[0x06]);
+                    hasher.update(&mem.segment().0.to_le_bytes());
+                    hasher.update(&mem.base().0.to_le_bytes());
+                    hasher.update(&mem.index().0.to_le_bytes());
+                    hasher.update(&mem.scale().to_le_bytes());
+                    if mem.base().0 as u32 == X86Reg::X86_REG_RIP {
+                        hasher.update(&[0x07]);
+                    } else {
+                        hash_value(&mut hasher, mem.disp(), address);
+                    }
+                }
+                X86OperandType::Invalid => {}
+            }
+        }
+
+        let insn_end = insn.address() + insn.len() as u64;
+        size = (insn_end - address) as usize;
+        instruction_count += 1;
+
+        let terminates = mnemonic.starts_with("ret") || matches!(mnemonic, "jmp" | "hlt" | "int3" | "ud2");
+        if terminates && insn_end > furthest_target {
+            break;
+        }
+    }
+
+    Ok(FunctionFingerprint {
+        hash: hasher.digest(),
+        size,
+        instruction_count,
+    })
+}
+
+/// 即値・変位をハッシュに加える（アドレスらしい値は値を区別しない）
+fn hash_value(hasher: &mut Xxh3, value: i64, function_address: u64) {
+    let unsigned = value as u64;
+    if unsigned >= MIN_ADDRESS && unsigned.abs_diff(function_address) < ADDRESS_WINDOW {
+        hasher.update(&[0x04]);
+    } else {
+        hasher.update(&[0x05]);
+        hasher.update(&value.to_le_bytes());
+    }
+}
+
+/// 仮想アドレスをファイルオフセットに変換
+///
+/// PEはイメージベースからのRVA、ELFはセクションのアドレスで変換する
+/// 解析できない形式ではアドレスをそのままオフセットとみなす
+pub fn file_offset_for_address(binary_data: &[u8], address: u64) -> Option<usize> {
+    let offset = match Object::parse(binary_data) {
+        Ok(Object::PE(pe)) => {
+            let image_base = pe.image_base as u64;
+            let rva = address.checked_sub(image_base).unwrap_or(address);
+            pe.sections.iter().find_map(|s| {
+                let start = s.virtual_address as u64;
+                let size = s.virtual_size.max(s.size_of_raw_data) as u64;
+                (rva >= start && rva < start + size).then(|| rva - start + s.pointer_to_raw_data as u64)
+            })?
+        }
+        Ok(Object::Elf(elf)) => elf.section_headers.iter().find_map(|sh| {
+            let in_section = sh.sh_type != goblin::elf::section_header::SHT_NOBITS
+                && sh.sh_addr != 0
+                && address >= sh.sh_addr
+                && address < sh.sh_addr + sh.sh_size;
+            in_section.then(|| address - sh.sh_addr + sh.sh_offset)
+        })?,
+        _ => address,
+    };
+
+    let offset = usize::try_from(offset).ok()?;
+    (offset < binary_data.len()).then_some(offset)
+}
+
+/// バイナリ中の関数のフィンガープリントを求める
+pub fn fingerprint_at(binary_data: &[u8], address: u64, max_instructions: usize) -> Result<FunctionFingerprint> {
+    let offset = file_offset_for_address(binary_data, address)
+        .ok_or_else(|| anyhow!("Address 0x{:x} is not backed by file data", address))?;
+    let end = std::cmp::min(offset + max_instructions * 15, binary_data.len());
+    fingerprint_function(&binary_data[offset..end], address, max_instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// call rel32 / lea rax, [rip+disp32] / 関数内の条件分岐を含む関数
+    fn sample_function(call_disp: i32, rip_disp: i32) -> Vec<u8> {
+        let mut code = vec![0x48, 0x83, 0xec, 0x28]; // sub rsp, 0x28
+        code.push(0xe8); // call rel32
+        code.extend_from_slice(&call_disp.to_le_bytes());
+        code.extend_from_slice(&[0x48, 0x8d, 0x05]); // lea rax, [rip+disp32]
+        code.extend_from_slice(&rip_disp.to_le_bytes());
+        code.extend_from_slice(&[0x85, 0xc0, 0x74, 0x01]); // test eax, eax; je +1
+        code.push(0xc3); // ret
+        code.extend_from_slice(&[0x48, 0x83, 0xc4, 0x28, 0xc3]); // add rsp, 0x28; ret
+        code
+    }
+
+    #[test]
+    fn test_hash_ignores_position_dependent_operands() {
+        let mut first = sample_function(0x1000, 0x2000);
+        let mut second = sample_function(-0x3456, 0x7777);
+        // 関数の後ろには無関係なコード
+        first.extend_from_slice(&[0x90; 16]);
+        second.extend_from_slice(&[0xcc; 16]);
+
+        let a = fingerprint_function(&first, 0x140001000, 100).unwrap();
+        let b = fingerprint_function(&second, 0x140005230, 100).unwrap();
+
+        assert_eq!(a.hash, b.hash);
+        // 分岐先を過ぎた2つ目のretで関数が終わる
+        assert_eq!(a.size, sample_function(0, 0).len());
+        assert_eq!(a.instruction_count, 8);
+    }
+
+    #[test]
+    fn test_hash_distinguishes_bodies() {
+        let original = sample_function(0x1000, 0x2000);
+        let mut patched = original.clone();
+        // add rsp, 0x28 → add rsp, 0x38
+        let len = patched.len();
+        patched[len - 2] = 0x38;
+
+        let a = fingerprint_function(&original, 0x1000, 100).unwrap();
+        let b = fingerprint_function(&patched, 0x1000, 100).unwrap();
+        assert_ne!(a.hash, b.hash);
+    }
+}
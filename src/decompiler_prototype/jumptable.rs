@@ -2,25 +2,69 @@
 ///
 /// Ghidraのjumptable.ccに基づく実装
 /// 間接ジャンプ（jmp [rip+rax*8]等）からswitch-case構造を復元
+/// 分岐先の計算式をP-code列上で遡って線形式に展開し、テーブルの形式と
+/// ガード比較（cmp idx, N; ja default）からエントリ数を求める
 
+use crate::decompiler_prototype::cfg::branch_target;
 use crate::decompiler_prototype::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
 use crate::decompiler_prototype::dataflow::DefUseChain;
-use std::collections::{HashMap, HashSet};
-use anyhow::Result;
+use crate::decompiler_prototype::x86_64::X86Register;
+use anyhow::{anyhow, Result};
+
+/// 計算式を遡る最大の深さ
+const MAX_EXPR_DEPTH: usize = 32;
+
+/// 境界が求まらなかったテーブルを読み取る最大エントリ数
+const MAX_UNBOUNDED_ENTRIES: usize = 256;
+
+/// ジャンプテーブルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpTableKind {
+    /// エントリが分岐先の絶対アドレス
+    Absolute,
+    /// 分岐先 = base + エントリ
+    ///
+    /// MSVCはイメージベースからのRVA（movsxdの場合は符号付き）、
+    /// GCC/ClangのPICコードはテーブル先頭からの符号付きオフセット
+    Relative { base: u64, signed: bool },
+}
 
 /// ジャンプテーブル情報
 #[derive(Debug, Clone)]
 pub struct JumpTable {
     /// ジャンプテーブルのアドレス
     pub table_address: u64,
-    /// エントリ数
+    /// case値の個数（ガード比較から求めた値、不明な場合は0）
     pub num_entries: usize,
     /// エントリサイズ（バイト）
     pub entry_size: usize,
-    /// ジャンプ先アドレスのリスト
+    /// case値ごとのジャンプ先アドレスのリスト
     pub destinations: Vec<u64>,
     /// スイッチ変数（インデックス）
     pub switch_var: Varnode,
+    /// テーブルの形式
+    pub kind: JumpTableKind,
+    /// 2段テーブルのバイトインデックステーブルのアドレス
+    pub index_table: Option<u64>,
+    /// 先頭エントリに対応するcase値
+    pub case_base: i64,
+    /// 範囲外の値の分岐先（ガード比較の分岐先）
+    pub default_target: Option<u64>,
+    /// 間接分岐命令のアドレス
+    pub branch_address: u64,
+}
+
+impl JumpTable {
+    /// 間接分岐の分岐先（重複を除く）
+    pub fn targets(&self) -> Vec<u64> {
+        let mut targets: Vec<u64> = Vec::new();
+        for &target in &self.destinations {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
 }
 
 /// Switch-Case構造
@@ -45,6 +89,119 @@ pub struct CaseBranch {
     pub target: u64,
 }
 
+/// アドレス計算式の線形表現（constant + Σ 係数 * 項、2^64を法とする）
+#[derive(Debug, Clone)]
+struct LinearExpr {
+    constant: u64,
+    terms: Vec<(u64, Term)>,
+}
+
+/// 線形式の項
+#[derive(Debug, Clone)]
+enum Term {
+    /// 定義を遡れない値
+    Value(Varnode),
+    /// メモリからの読み出し（valueは読み出し先のVarnode）
+    Load {
+        addr: Box<LinearExpr>,
+        size: usize,
+        signed: bool,
+        value: Varnode,
+    },
+}
+
+impl LinearExpr {
+    fn constant(value: u64) -> Self {
+        Self { constant: value, terms: Vec::new() }
+    }
+
+    fn term(term: Term) -> Self {
+        Self { constant: 0, terms: vec![(1, term)] }
+    }
+
+    fn add(mut self, other: LinearExpr) -> Self {
+        self.constant = self.constant.wrapping_add(other.constant);
+        for (coef, term) in other.terms {
+            match self.terms.iter_mut().find(|(_, existing)| existing.same_as(&term)) {
+                Some(entry) => entry.0 = entry.0.wrapping_add(coef),
+                None => self.terms.push((coef, term)),
+            }
+        }
+        self.terms.retain(|(coef, _)| *coef != 0);
+        self
+    }
+
+    fn scale(mut self, factor: u64) -> Self {
+        self.constant = self.constant.wrapping_mul(factor);
+        for (coef, _) in &mut self.terms {
+            *coef = coef.wrapping_mul(factor);
+        }
+        self.terms.retain(|(coef, _)| *coef != 0);
+        self
+    }
+
+    fn as_constant(&self) -> Option<u64> {
+        self.terms.is_empty().then_some(self.constant)
+    }
+
+    /// 項が1つだけの場合、その係数と項
+    fn single_term(&self) -> Option<(u64, &Term)> {
+        match self.terms.as_slice() {
+            [(coef, term)] => Some((*coef, term)),
+            _ => None,
+        }
+    }
+
+    fn same_as(&self, other: &LinearExpr) -> bool {
+        self.constant == other.constant
+            && self.terms.len() == other.terms.len()
+            && self.terms.iter().all(|(coef, term)| {
+                other.terms.iter().any(|(other_coef, other_term)| coef == other_coef && term.same_as(other_term))
+            })
+    }
+}
+
+impl Term {
+    /// 同じ値を表すか（レジスタの部分アクセスは同一視する）
+    fn same_as(&self, other: &Term) -> bool {
+        match (self, other) {
+            (Term::Value(a), Term::Value(b)) => same_storage(a, b),
+            (Term::Load { addr: a, .. }, Term::Load { addr: b, .. }) => a.same_as(b),
+            _ => false,
+        }
+    }
+
+    /// 項の値を保持するVarnode
+    fn variable(&self) -> &Varnode {
+        match self {
+            Term::Value(vn) => vn,
+            Term::Load { value, .. } => value,
+        }
+    }
+}
+
+/// ガード比較から求めたインデックスの範囲
+struct Guard {
+    /// 比較される値 = offset + インデックス変数
+    offset: u64,
+    /// 範囲内の値の個数
+    count: usize,
+    /// 範囲外の値の分岐先
+    default_target: Option<u64>,
+}
+
+/// テーブルの候補
+struct Candidate<'a> {
+    /// インデックス変数
+    index: &'a Term,
+    /// インデックス変数が0のときのエントリ（2段テーブルではインデックス）のアドレス
+    base: u64,
+    /// 1エントリあたりのバイト数
+    stride: u64,
+    /// 2段テーブルの場合、分岐先テーブルのアドレス
+    outer_table: Option<u64>,
+}
+
 /// ジャンプテーブル検出器
 pub struct JumpTableDetector {
     du_chain: DefUseChain,
@@ -59,10 +216,10 @@ impl JumpTableDetector {
     pub fn detect(&self, ops: &[PcodeOp]) -> Vec<JumpTable> {
         let mut tables = Vec::new();
 
-        for op in ops {
+        for (pos, op) in ops.iter().enumerate() {
             // 間接ジャンプ命令を探す
             if op.opcode == OpCode::BranchInd {
-                if let Some(table) = self.analyze_indirect_branch(op, ops) {
+                if let Some(table) = self.analyze_indirect_branch(ops, pos) {
                     tables.push(table);
                 }
             }
@@ -72,78 +229,274 @@ impl JumpTableDetector {
     }
 
     /// 間接ジャンプ命令を解析
-    fn analyze_indirect_branch(&self, op: &PcodeOp, _ops: &[PcodeOp]) -> Option<JumpTable> {
-        if op.inputs.is_empty() {
+    fn analyze_indirect_branch(&self, ops: &[PcodeOp], pos: usize) -> Option<JumpTable> {
+        let op = &ops[pos];
+        let target_vn = op.inputs.first()?;
+
+        // パターン: target = [base +] ext(Load(table + index * entry_size))
+        let target = self.value_expr(ops, pos, target_vn, 0);
+        let (1, Term::Load { addr, size, signed, .. }) = target.single_term()? else {
             return None;
+        };
+
+        let kind = if target.constant == 0 {
+            JumpTableKind::Absolute
+        } else {
+            JumpTableKind::Relative { base: target.constant, signed: *signed }
+        };
+
+        self.analyze_load_pattern(ops, pos, addr, *size, kind)
+    }
+
+    /// Load操作のアドレス計算パターンを解析
+    ///
+    /// パターン例:
+    /// - [rip + index * 8]
+    /// - [table_base + index * 4]
+    /// - [table_base + byte [index_table + index] * 4]（2段テーブル）
+    fn analyze_load_pattern(
+        &self,
+        ops: &[PcodeOp],
+        pos: usize,
+        addr: &LinearExpr,
+        entry_size: usize,
+        kind: JumpTableKind,
+    ) -> Option<JumpTable> {
+        let (stride, index) = addr.single_term()?;
+        if stride != entry_size as u64 {
+            return None;
+        }
+
+        // バイトインデックス経由の2段テーブルを優先し、ガード比較が見つかる候補を採用
+        let mut candidates = Vec::new();
+        if let Term::Load { addr: index_addr, size: 1, .. } = index {
+            if let Some((1, inner)) = index_addr.single_term() {
+                candidates.push(Candidate {
+                    index: inner,
+                    base: index_addr.constant,
+                    stride: 1,
+                    outer_table: Some(addr.constant),
+                });
+            }
         }
+        candidates.push(Candidate {
+            index,
+            base: addr.constant,
+            stride,
+            outer_table: None,
+        });
+
+        let guarded = candidates
+            .iter()
+            .find_map(|candidate| self.find_guard(ops, pos, candidate.index).map(|guard| (candidate, Some(guard))));
+        let (candidate, guard) = guarded.unwrap_or((&candidates[0], None));
+
+        // 比較される値 = offset + index の範囲 [0, count) をテーブルの先頭に合わせる
+        let (offset, num_entries, default_target) = match guard {
+            Some(guard) => (guard.offset, guard.count, guard.default_target),
+            None => (0, 0, None),
+        };
+        let start = candidate.base.wrapping_sub(candidate.stride.wrapping_mul(offset));
+
+        let (table_address, index_table) = match candidate.outer_table {
+            Some(outer) => (outer, Some(start)),
+            None => (start, None),
+        };
 
-        // ジャンプ先アドレスを計算するVarnodeを取得
-        let target_vn = &op.inputs[0];
+        Some(JumpTable {
+            table_address,
+            num_entries,
+            entry_size,
+            destinations: Vec::new(), // メモリ読み取りで埋める
+            switch_var: candidate.index.variable().clone(),
+            kind,
+            index_table,
+            case_base: (offset as i64).wrapping_neg(),
+            default_target,
+            branch_address: ops[pos].address,
+        })
+    }
 
-        // Load操作からジャンプテーブルを検出
-        // パターン: target = Load(table_base + index * entry_size)
-        if let Some(load_op) = self.du_chain.get_def(target_vn) {
-            if load_op.opcode == OpCode::Load && load_op.inputs.len() >= 2 {
-                return self.analyze_load_pattern(&load_op.inputs[1], op.address);
+    /// 間接分岐より前の条件分岐からインデックスの範囲を求める
+    fn find_guard(&self, ops: &[PcodeOp], pos: usize, index: &Term) -> Option<Guard> {
+        for branch_pos in (0..pos).rev() {
+            let branch = &ops[branch_pos];
+            if branch.opcode != OpCode::CBranch || branch.inputs.len() < 2 {
+                continue;
             }
+
+            let Some((value, count, taken_out_of_range)) = self.condition_bound(ops, branch_pos, &branch.inputs[1], 0) else {
+                continue;
+            };
+            let Some((1, term)) = value.single_term() else {
+                continue;
+            };
+            if !term.same_as(index) {
+                continue;
+            }
+
+            let default_target = if taken_out_of_range {
+                branch_target(branch)
+            } else {
+                next_instruction_address(ops, branch_pos)
+            };
+
+            return Some(Guard {
+                offset: value.constant,
+                count,
+                default_target,
+            });
         }
 
         None
     }
 
-    /// Load操作のアドレス計算パターンを解析
+    /// 分岐条件を「値が[0, count)の範囲外」の形に直す
     ///
-    /// パターン例:
-    /// - [rip + index * 8]
-    /// - [table_base + index * 4]
-    fn analyze_load_pattern(&self, addr_vn: &Varnode, _switch_addr: u64) -> Option<JumpTable> {
-        // アドレス計算の定義を取得
-        let addr_op = self.du_chain.get_def(addr_vn)?;
-
-        // PtrAdd: base + offset
-        if addr_op.opcode == OpCode::PtrAdd && addr_op.inputs.len() >= 2 {
-            let base = &addr_op.inputs[0];
-            let offset = &addr_op.inputs[1];
-
-            // 定数ベースアドレス
-            if base.space == AddressSpace::Const {
-                let table_address = base.offset;
-
-                // オフセットが乗算（index * entry_size）の場合
-                if let Some(mult_op) = self.du_chain.get_def(offset) {
-                    if mult_op.opcode == OpCode::IntMult && mult_op.inputs.len() >= 2 {
-                        let switch_var = mult_op.inputs[0].clone();
-                        let entry_size = if mult_op.inputs[1].space == AddressSpace::Const {
-                            mult_op.inputs[1].offset as usize
-                        } else {
-                            8 // デフォルト64bitポインタ
-                        };
-
-                        // 簡易版: エントリ数は推定（実際にはメモリ読み取りが必要）
-                        let num_entries = 10; // 暫定値
-
-                        return Some(JumpTable {
-                            table_address,
-                            num_entries,
-                            entry_size,
-                            destinations: Vec::new(), // メモリ読み取りで埋める
-                            switch_var,
-                        });
+    /// 戻り値は（比較される値, count, 条件成立時に範囲外へ分岐するか）
+    fn condition_bound(&self, ops: &[PcodeOp], pos: usize, cond: &Varnode, depth: usize) -> Option<(LinearExpr, usize, bool)> {
+        if depth >= MAX_EXPR_DEPTH {
+            return None;
+        }
+
+        let def_pos = find_def(ops, pos, cond)?;
+        let op = &ops[def_pos];
+
+        match op.opcode {
+            OpCode::BoolNegate => {
+                let (value, count, out_of_range) = self.condition_bound(ops, def_pos, op.inputs.first()?, depth + 1)?;
+                Some((value, count, !out_of_range))
+            }
+            // ja: !CF && !ZF、jbe: CF || ZF
+            OpCode::BoolAnd | OpCode::BoolOr if op.inputs.len() == 2 => {
+                let negated = op.opcode == OpCode::BoolAnd;
+                let mut compares = Vec::new();
+                for input in &op.inputs {
+                    let mut compare_pos = find_def(ops, def_pos, input)?;
+                    if negated {
+                        let negate = &ops[compare_pos];
+                        if negate.opcode != OpCode::BoolNegate {
+                            return None;
+                        }
+                        compare_pos = find_def(ops, compare_pos, negate.inputs.first()?)?;
                     }
+                    compares.push(self.compare_operands(ops, compare_pos)?);
                 }
 
-                // 直接オフセット（entry_size=1と仮定）
-                return Some(JumpTable {
-                    table_address,
-                    num_entries: 10,
-                    entry_size: 8,
-                    destinations: Vec::new(),
-                    switch_var: offset.clone(),
-                });
+                // (x < N) || (x == N) は x <= N
+                let (less, equal) = match (compares[0].0, compares[1].0) {
+                    (OpCode::IntLess, OpCode::IntEqual) => (&compares[0], &compares[1]),
+                    (OpCode::IntEqual, OpCode::IntLess) => (&compares[1], &compares[0]),
+                    _ => return None,
+                };
+                if !less.1.same_as(&equal.1) || !less.2.same_as(&equal.2) {
+                    return None;
+                }
+
+                let (value, count, out_of_range) = bound_from_compare(OpCode::IntLessEqual, &less.1, &less.2)?;
+                Some((value, count, out_of_range != negated))
+            }
+            OpCode::IntLess | OpCode::IntLessEqual => {
+                let (opcode, lhs, rhs) = self.compare_operands(ops, def_pos)?;
+                bound_from_compare(opcode, &lhs, &rhs)
             }
+            _ => None,
         }
+    }
 
-        None
+    /// 比較操作のオペコードと両辺の値
+    fn compare_operands(&self, ops: &[PcodeOp], pos: usize) -> Option<(OpCode, LinearExpr, LinearExpr)> {
+        let op = &ops[pos];
+        if !matches!(op.opcode, OpCode::IntLess | OpCode::IntLessEqual | OpCode::IntEqual) || op.inputs.len() != 2 {
+            return None;
+        }
+        Some((
+            op.opcode,
+            self.value_expr(ops, pos, &op.inputs[0], 0),
+            self.value_expr(ops, pos, &op.inputs[1], 0),
+        ))
+    }
+
+    /// pos番目の操作が読むVarnodeの値を線形式に展開
+    fn value_expr(&self, ops: &[PcodeOp], pos: usize, vn: &Varnode, depth: usize) -> LinearExpr {
+        if vn.space == AddressSpace::Const {
+            return LinearExpr::constant(vn.offset);
+        }
+        if depth >= MAX_EXPR_DEPTH {
+            return LinearExpr::term(Term::Value(vn.clone()));
+        }
+
+        // RIP相対アドレスは次の命令のアドレスを基準とする
+        if vn.space == AddressSpace::Register && vn.offset == X86Register::RIP as u64 {
+            if let Some(next) = next_instruction_address(ops, pos) {
+                return LinearExpr::constant(next);
+            }
+        }
+
+        if let Some(def_pos) = find_def(ops, pos, vn) {
+            return self.op_expr(ops, def_pos, &ops[def_pos], depth + 1);
+        }
+
+        // SSA形式では与えられた範囲外の定義もdef-useチェーンから辿れる
+        if vn.version != 0 {
+            if let Some(def) = self.du_chain.get_def(vn) {
+                return self.op_expr(ops, pos, def, depth + 1);
+            }
+        }
+
+        LinearExpr::term(Term::Value(vn.clone()))
+    }
+
+    /// 操作の出力値を線形式に展開（入力はpos番目の位置での値）
+    fn op_expr(&self, ops: &[PcodeOp], pos: usize, op: &PcodeOp, depth: usize) -> LinearExpr {
+        let input = |i: usize| self.value_expr(ops, pos, &op.inputs[i], depth);
+        let opaque = || match &op.output {
+            Some(out) => LinearExpr::term(Term::Value(out.clone())),
+            None => LinearExpr::constant(0),
+        };
+
+        match (op.opcode, op.inputs.len()) {
+            (OpCode::Copy, 1) => input(0),
+            (OpCode::IntAdd, 2) | (OpCode::PtrSub, 2) | (OpCode::PtrAdd, 2) => input(0).add(input(1)),
+            (OpCode::PtrAdd, 3) => match input(2).as_constant() {
+                Some(size) => input(0).add(input(1).scale(size)),
+                None => opaque(),
+            },
+            (OpCode::IntSub, 2) => input(0).add(input(1).scale(u64::MAX)),
+            (OpCode::IntMult, 2) => {
+                let (lhs, rhs) = (input(0), input(1));
+                if let Some(factor) = rhs.as_constant() {
+                    lhs.scale(factor)
+                } else if let Some(factor) = lhs.as_constant() {
+                    rhs.scale(factor)
+                } else {
+                    opaque()
+                }
+            }
+            (OpCode::IntLeft, 2) => match input(1).as_constant() {
+                Some(shift) if shift < 64 => input(0).scale(1 << shift),
+                _ => opaque(),
+            },
+            (OpCode::IntZExt, 1) | (OpCode::IntSExt, 1) => {
+                let mut value = input(0);
+                if value.constant == 0 {
+                    if let [(1, Term::Load { signed, .. })] = value.terms.as_mut_slice() {
+                        *signed = op.opcode == OpCode::IntSExt;
+                    }
+                }
+                value
+            }
+            (OpCode::Load, _) => match (op.inputs.last(), &op.output) {
+                (Some(addr), Some(out)) => LinearExpr::term(Term::Load {
+                    addr: Box::new(self.value_expr(ops, pos, addr, depth)),
+                    size: out.size,
+                    signed: false,
+                    value: out.clone(),
+                }),
+                _ => opaque(),
+            },
+            _ => opaque(),
+        }
     }
 
     /// ジャンプテーブルからSwitch文を復元
@@ -151,22 +504,63 @@ impl JumpTableDetector {
         let mut cases = Vec::new();
 
         // 各エントリをcaseラベルに変換
-        for (label, &target) in table.destinations.iter().enumerate() {
+        for (i, &target) in table.destinations.iter().enumerate() {
+            // 2段テーブルでは欠番のcase値もdefaultへのエントリとして現れる
+            if Some(target) == table.default_target {
+                continue;
+            }
             cases.push(CaseBranch {
-                label: label as u64,
+                label: table.case_base.wrapping_add(i as i64) as u64,
                 target,
             });
         }
 
         SwitchStatement {
-            address: table.table_address,
+            address: table.branch_address,
             switch_var: table.switch_var.clone(),
             cases,
-            default_case: None,
+            default_case: table.default_target,
         }
     }
 }
 
+/// 比較から「値が[0, count)の範囲外」となる条件を求める
+///
+/// 戻り値は（比較される値, count, 比較が成立したとき範囲外か）
+fn bound_from_compare(opcode: OpCode, lhs: &LinearExpr, rhs: &LinearExpr) -> Option<(LinearExpr, usize, bool)> {
+    let (value, bound, out_of_range, inclusive) = match (lhs.as_constant(), rhs.as_constant()) {
+        // x < N, x <= N
+        (None, Some(n)) => (lhs, n, false, opcode == OpCode::IntLessEqual),
+        // N < x, N <= x
+        (Some(n), None) => (rhs, n, true, opcode == OpCode::IntLess),
+        _ => return None,
+    };
+
+    let count = if inclusive { bound.checked_add(1)? } else { bound };
+    if count == 0 || count > u16::MAX as u64 {
+        return None;
+    }
+    Some((value.clone(), count as usize, out_of_range))
+}
+
+/// 同じ記憶域を指すか（サイズの違いは部分レジスタとして同一視する）
+fn same_storage(a: &Varnode, b: &Varnode) -> bool {
+    a.space == b.space && a.offset == b.offset && a.version == b.version
+}
+
+/// pos番目より前でVarnodeを最後に定義した操作の位置
+fn find_def(ops: &[PcodeOp], pos: usize, vn: &Varnode) -> Option<usize> {
+    ops[..pos]
+        .iter()
+        .rposition(|op| op.output.as_ref().is_some_and(|out| same_storage(out, vn)))
+}
+
+/// pos番目の操作の次の命令のアドレス
+fn next_instruction_address(ops: &[PcodeOp], pos: usize) -> Option<u64> {
+    let address = ops[pos].address;
+    ops[pos + 1..].iter().map(|op| op.address).find(|&next| next != address)
+}
+
 /// Switch文のC疑似コード生成
 pub struct SwitchPrinter {
     indent_level: usize,
@@ -224,41 +618,94 @@ impl JumpTableLoader {
     }
 
     /// ジャンプテーブルのエントリを読み取り
+    ///
+    /// 2段テーブルではcase値ごとにインデックステーブルを引いてから分岐先を求める
+    /// エントリ数が不明な場合は、分岐先がバイナリの範囲外になるまで読み進める
     pub fn load_entries(&self, table: &mut JumpTable, image_base: u64) -> Result<()> {
         // RVAをファイルオフセットに変換（簡易版）
         let file_offset = self.rva_to_offset(table.table_address, image_base)?;
 
         table.destinations.clear();
 
-        for i in 0..table.num_entries {
-            let entry_offset = file_offset + i * table.entry_size;
-
-            if entry_offset + table.entry_size > self.binary_data.len() {
-                break;
+        // case値 → エントリ番号
+        let indices: Vec<usize> = match table.index_table {
+            Some(index_table) => {
+                let index_offset = self.rva_to_offset(index_table, image_base)?;
+                (0..table.num_entries)
+                    .map(|i| self.read_value(index_offset + i, 1, false).map(|index| index as usize))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| anyhow!("Index table at 0x{:x} is out of range", index_table))?
             }
-
-            // エントリサイズに応じて読み取り
-            let entry_value = match table.entry_size {
-                4 => {
-                    let bytes = &self.binary_data[entry_offset..entry_offset + 4];
-                    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64
-                }
-                8 => {
-                    let bytes = &self.binary_data[entry_offset..entry_offset + 8];
-                    u64::from_le_bytes([
-                        bytes[0], bytes[1], bytes[2], bytes[3],
-                        bytes[4], bytes[5], bytes[6], bytes[7],
-                    ])
+            None if table.num_entries == 0 => {
+                for i in 0..MAX_UNBOUNDED_ENTRIES {
+                    let Some(target) = self.read_target(table, file_offset, i) else {
+                        break;
+                    };
+                    if !self.is_valid_target(target, image_base) {
+                        break;
+                    }
+                    table.destinations.push(target);
                 }
-                _ => continue,
-            };
+                return Ok(());
+            }
+            None => (0..table.num_entries).collect(),
+        };
 
-            table.destinations.push(entry_value);
+        for index in indices {
+            let Some(target) = self.read_target(table, file_offset, index) else {
+                break;
+            };
+            table.destinations.push(target);
         }
 
         Ok(())
     }
 
+    /// index番目のエントリから分岐先を求める
+    fn read_target(&self, table: &JumpTable, file_offset: usize, index: usize) -> Option<u64> {
+        let entry_offset = file_offset.checked_add(index.checked_mul(table.entry_size)?)?;
+
+        match table.kind {
+            JumpTableKind::Absolute => self.read_value(entry_offset, table.entry_size, false),
+            JumpTableKind::Relative { base, signed } => {
+                let entry = self.read_value(entry_offset, table.entry_size, signed)?;
+                Some(base.wrapping_add(entry))
+            }
+        }
+    }
+
+    /// エントリサイズに応じて読み取り（signedなら64bitに符号拡張）
+    fn read_value(&self, offset: usize, size: usize, signed: bool) -> Option<u64> {
+        let bytes = self.binary_data.get(offset..offset.checked_add(size)?)?;
+
+        let value = match size {
+            1 => if signed { bytes[0] as i8 as u64 } else { bytes[0] as u64 },
+            2 => {
+                let raw = u16::from_le_bytes([bytes[0], bytes[1]]);
+                if signed { raw as i16 as u64 } else { raw as u64 }
+            }
+            4 => {
+                let raw = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                if signed { raw as i32 as u64 } else { raw as u64 }
+            }
+            8 => u64::from_le_bytes([
+                bytes[0], bytes[1], bytes[2], bytes[3],
+                bytes[4], bytes[5], bytes[6], bytes[7],
+            ]),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// 分岐先としてあり得るアドレスか
+    fn is_valid_target(&self, target: u64, image_base: u64) -> bool {
+        target >= image_base
+            && self
+                .rva_to_offset(target, image_base)
+                .is_ok_and(|offset| offset < self.binary_data.len())
+    }
+
     /// RVAをファイルオフセットに変換
     fn rva_to_offset(&self, rva: u64, image_base: u64) -> Result<usize> {
         // 簡易変換: .textセクション仮定
@@ -282,6 +729,126 @@ impl JumpTableLoader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decompiler_prototype::cfg::{BlockId, ControlFlowGraph};
+    use crate::decompiler_prototype::x86_64::X86Decoder;
+
+    /// lea dest, [base + index * scale + disp]
+    fn lea(decoder: &mut X86Decoder, dest: Varnode, base: X86Register, index: Option<X86Register>, scale: u8, disp: i64, address: u64) -> Vec<PcodeOp> {
+        let (mut ops, addr) = decoder.compute_memory_address(Some(base), index, scale, disp, address);
+        ops.push(PcodeOp::unary(OpCode::Copy, dest, addr, address));
+        ops
+    }
+
+    /// dest = ext(size byte [base + index * scale + disp])
+    fn load_ext(decoder: &mut X86Decoder, opcode: OpCode, dest: Varnode, base: X86Register, index: X86Register, scale: u8, disp: i64, size: usize, address: u64) -> Vec<PcodeOp> {
+        let (mut ops, addr) = decoder.compute_memory_address(Some(base), Some(index), scale, disp, address);
+        let loaded = Varnode::unique(0x9000 + address, size);
+        ops.push(PcodeOp::unary(OpCode::Load, loaded.clone(), addr, address));
+        ops.push(PcodeOp::unary(opcode, dest, loaded, address));
+        ops
+    }
+
+    fn detector(ops: &[PcodeOp]) -> JumpTableDetector {
+        let mut du_chain = DefUseChain::new();
+        du_chain.build(ops);
+        JumpTableDetector::new(du_chain)
+    }
+
+    #[test]
+    fn test_msvc_relative_table_with_guard() {
+        let mut decoder = X86Decoder::new();
+        let mut ops = Vec::new();
+        // cmp ecx, 4; ja default
+        ops.extend(decoder.decode_cmp_imm(X86Register::RCX, 4, 4, 0x140001000));
+        ops.extend(decoder.decode_ja(0x140001080, 0x140001003));
+        // lea rdx, [rip - 0x100c] (__ImageBase)
+        ops.extend(lea(&mut decoder, X86Register::RDX.to_varnode_64(), X86Register::RIP, None, 1, -0x100c, 0x140001005));
+        // movsxd rax, dword [rdx + rcx*4 + 0x3000]
+        ops.extend(load_ext(&mut decoder, OpCode::IntSExt, X86Register::RAX.to_varnode_64(), X86Register::RDX, X86Register::RCX, 4, 0x3000, 4, 0x14000100c));
+        // add rax, rdx; jmp rax
+        ops.extend(decoder.decode_add(X86Register::RAX, X86Register::RDX, 8, 0x140001014));
+        ops.extend(decoder.decode_jmp_indirect(X86Register::RAX, 0x140001017));
+        for target in [0x140001020, 0x140001030, 0x140001040, 0x140001050, 0x140001080] {
+            ops.extend(decoder.decode_ret(target));
+        }
+
+        let tables = detector(&ops).detect(&ops);
+        assert_eq!(tables.len(), 1);
+        let mut table = tables[0].clone();
+        assert_eq!(table.kind, JumpTableKind::Relative { base: 0x140000000, signed: true });
+        assert_eq!(table.table_address, 0x140003000);
+        assert_eq!(table.entry_size, 4);
+        assert_eq!(table.num_entries, 5);
+        assert_eq!(table.default_target, Some(0x140001080));
+        assert_eq!(table.switch_var.offset, X86Register::RCX as u64);
+        assert_eq!(table.index_table, None);
+
+        // .text仮定のRVA変換で0x140003000はファイルオフセット0x2400
+        let mut binary = vec![0u8; 0x2400];
+        for rva in [0x1020u32, 0x1030, 0x1040, 0x1030, 0x1050, 0xdeadbeef] {
+            binary.extend_from_slice(&rva.to_le_bytes());
+        }
+        JumpTableLoader::new(binary).load_entries(&mut table, 0x140000000).unwrap();
+        assert_eq!(table.destinations, vec![0x140001020, 0x140001030, 0x140001040, 0x140001030, 0x140001050]);
+
+        // 解決した分岐先が間接分岐ブロックの後続になる
+        let cfg = ControlFlowGraph::from_pcodes_with_jump_tables(ops, &[table]);
+        let block_at = |address: u64| cfg.blocks.values().find(|b| b.start_address == address).unwrap().id;
+        let switch_block = cfg.blocks.values().find(|b| b.ops.last().unwrap().opcode == OpCode::BranchInd).unwrap();
+        let expected: Vec<BlockId> = [0x140001020, 0x140001030, 0x140001040, 0x140001050].iter().map(|&a| block_at(a)).collect();
+        assert_eq!(switch_block.successors, expected);
+        assert_eq!(cfg.blocks[&block_at(0x140001030)].predecessors, vec![switch_block.id]);
+        assert_eq!(cfg.blocks[&cfg.entry_block].successors, vec![block_at(0x140001080), switch_block.id]);
+    }
+
+    #[test]
+    fn test_two_level_pic_table_with_biased_index() {
+        let mut decoder = X86Decoder::new();
+        let eax = X86Register::RAX.to_varnode(4);
+        let mut ops = Vec::new();
+        // lea eax, [rdi - 1]; cmp eax, 9; ja default
+        ops.extend(lea(&mut decoder, eax.clone(), X86Register::RDI, None, 1, -1, 0x1000));
+        ops.extend(decoder.decode_cmp_imm(X86Register::RAX, 9, 4, 0x1003));
+        ops.extend(decoder.decode_ja(0x1100, 0x1006));
+        // lea rdx, [rip + 0x1ff1]; movzx eax, byte [rdx + rax]
+        ops.extend(lea(&mut decoder, X86Register::RDX.to_varnode_64(), X86Register::RIP, None, 1, 0x1ff1, 0x1008));
+        ops.extend(load_ext(&mut decoder, OpCode::IntZExt, eax, X86Register::RDX, X86Register::RAX, 1, 0, 1, 0x100f));
+        // lea rcx, [rip + 0x1ff6]; movsxd rax, dword [rcx + rax*4]; add rax, rcx; jmp rax
+        ops.extend(lea(&mut decoder, X86Register::RCX.to_varnode_64(), X86Register::RIP, None, 1, 0x1ff6, 0x1013));
+        ops.extend(load_ext(&mut decoder, OpCode::IntSExt, X86Register::RAX.to_varnode_64(), X86Register::RCX, X86Register::RAX, 4, 0, 4, 0x101a));
+        ops.extend(decoder.decode_add(X86Register::RAX, X86Register::RCX, 8, 0x101e));
+        ops.extend(decoder.decode_jmp_indirect(X86Register::RAX, 0x1021));
+
+        let detector = detector(&ops);
+        let tables = detector.detect(&ops);
+        assert_eq!(tables.len(), 1);
+        let mut table = tables[0].clone();
+        assert_eq!(table.kind, JumpTableKind::Relative { base: 0x3010, signed: true });
+        assert_eq!(table.table_address, 0x3010);
+        assert_eq!(table.index_table, Some(0x3000));
+        assert_eq!(table.num_entries, 10);
+        assert_eq!(table.case_base, 1);
+        assert_eq!(table.default_target, Some(0x1100));
+        assert_eq!(table.switch_var.offset, X86Register::RDI as u64);
+
+        // インデックステーブル（ファイルオフセット0x2400）と、テーブル先頭からの相対オフセット
+        let mut binary = vec![0u8; 0x2400];
+        binary.extend_from_slice(&[0, 1, 3, 1, 2, 3, 3, 3, 3, 0]);
+        binary.resize(0x2410, 0);
+        for target in [0x1040i64, 0x1050, 0x1060, 0x1100] {
+            binary.extend_from_slice(&((target - 0x3010) as i32).to_le_bytes());
+        }
+        JumpTableLoader::new(binary).load_entries(&mut table, 0).unwrap();
+        assert_eq!(table.destinations.len(), 10);
+        assert_eq!(table.targets(), vec![0x1040, 0x1050, 0x1100, 0x1060]);
+
+        // defaultへのエントリはcaseにしない
+        let switch = detector.recover_switch(&table);
+        let labels: Vec<u64> = switch.cases.iter().map(|case| case.label).collect();
+        assert_eq!(labels, vec![1, 2, 4, 5, 10]);
+        assert_eq!(switch.cases[2].target, 0x1050);
+        assert_eq!(switch.default_case, Some(0x1100));
+    }
 
     #[test]
     fn test_switch_printer() {
@@ -0,0 +1,258 @@
+/// Memory SSA
+///
+/// 各Storeをメモリ定義（MemoryDef）、各Loadをメモリ使用（MemoryUse）とみなし、
+/// Loadが読む値をどの定義が供給しているかを求める
+/// エイリアス解析の結果を用いて、Store→Loadの転送や不要なStoreの検出に使う
+
+use crate::decompiler_prototype::alias_analysis::{AliasAnalysis, AliasResult, MemoryLocation, MemoryRegion};
+use crate::decompiler_prototype::dataflow::OpId;
+use crate::decompiler_prototype::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
+use std::collections::{HashMap, HashSet};
+
+/// Loadに到達するメモリ状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccess {
+    /// 直線コード領域の入口の状態（定義を特定できない）
+    LiveOnEntry,
+    /// Store操作による定義
+    Def(OpId),
+    /// 呼び出しによる書き換え
+    Clobber(OpId),
+}
+
+/// Memory SSA
+pub struct MemorySSA {
+    alias: AliasAnalysis,
+    /// Load → 到達する定義とそのエイリアス関係
+    reaching: HashMap<OpId, (MemoryAccess, AliasResult)>,
+    /// Load → 転送できる値
+    forwarded: HashMap<OpId, Varnode>,
+    /// 読まれることのないStore
+    dead_stores: HashSet<OpId>,
+}
+
+impl MemorySSA {
+    /// P-code操作列からMemory SSAを構築
+    pub fn build(ops: &[PcodeOp]) -> Self {
+        let alias = AliasAnalysis::analyze(ops);
+        let mut memory_ssa = Self {
+            alias,
+            reaching: HashMap::new(),
+            forwarded: HashMap::new(),
+            dead_stores: HashSet::new(),
+        };
+
+        memory_ssa.link_loads(ops);
+        memory_ssa.find_dead_stores(ops);
+        memory_ssa
+    }
+
+    /// エイリアス解析の結果を取得
+    pub fn alias_analysis(&self) -> &AliasAnalysis {
+        &self.alias
+    }
+
+    /// Loadに到達するメモリ定義を取得
+    pub fn reaching_def(&self, load: OpId) -> Option<MemoryAccess> {
+        self.reaching.get(&load).map(|(access, _)| *access)
+    }
+
+    /// Loadの値として転送できるStoreの値を取得
+    pub fn forwarded_value(&self, load: OpId) -> Option<&Varnode> {
+        self.forwarded.get(&load)
+    }
+
+    /// 削除できるStoreかどうか
+    pub fn is_dead_store(&self, store: OpId) -> bool {
+        self.dead_stores.contains(&store)
+    }
+
+    /// 削除できるStoreの一覧
+    pub fn dead_stores(&self) -> &HashSet<OpId> {
+        &self.dead_stores
+    }
+
+    /// 各Loadを、同じ直線コード領域内で直前にその位置を書き換えうる操作と結び付ける
+    fn link_loads(&mut self, ops: &[PcodeOp]) {
+        // 現在の領域内のメモリ定義（Storeまたは呼び出し）
+        let mut defs: Vec<OpId> = Vec::new();
+
+        for (op_id, op) in ops.iter().enumerate() {
+            if self.alias.is_region_start(op_id) {
+                defs.clear();
+            }
+
+            match op.opcode {
+                OpCode::Store | OpCode::Call | OpCode::CallInd => defs.push(op_id),
+                OpCode::Load => {
+                    let Some(location) = self.alias.location(op_id).copied() else {
+                        continue;
+                    };
+
+                    let (access, result) = self.find_clobber(ops, &defs, &location);
+                    self.reaching.insert(op_id, (access, result));
+
+                    if let (MemoryAccess::Def(store), AliasResult::Must) = (access, result) {
+                        if let Some(value) = ops[store].inputs.last() {
+                            if !redefined_between(ops, store, op_id, value) {
+                                self.forwarded.insert(op_id, value.clone());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 位置を書き換えうる直近の定義を探す
+    fn find_clobber(&self, ops: &[PcodeOp], defs: &[OpId], location: &MemoryLocation) -> (MemoryAccess, AliasResult) {
+        for &def in defs.iter().rev() {
+            if ops[def].opcode == OpCode::Store {
+                let Some(stored) = self.alias.location(def) else {
+                    return (MemoryAccess::Def(def), AliasResult::May);
+                };
+                match self.alias.alias(stored, location) {
+                    AliasResult::No => continue,
+                    result => return (MemoryAccess::Def(def), result),
+                }
+            } else if self.alias.clobbered_by_call(location) {
+                return (MemoryAccess::Clobber(def), AliasResult::May);
+            }
+        }
+        (MemoryAccess::LiveOnEntry, AliasResult::May)
+    }
+
+    /// 不要なStoreを求める
+    ///
+    /// エスケープしていないスタックスロットへのStoreのうち、関数内のどのLoadからも
+    /// 読まれないもの、または同じ領域内で読まれる前に同じ位置へ上書きされるものを不要とみなす
+    fn find_dead_stores(&mut self, ops: &[PcodeOp]) {
+        if self.alias.stack_escaped() {
+            return;
+        }
+
+        let loads: Vec<MemoryLocation> = ops
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| op.opcode == OpCode::Load)
+            .filter_map(|(op_id, _)| self.alias.location(op_id).copied())
+            .collect();
+
+        for (op_id, op) in ops.iter().enumerate() {
+            if op.opcode != OpCode::Store {
+                continue;
+            }
+            let Some(location) = self.alias.location(op_id).copied() else {
+                continue;
+            };
+            if !matches!(location.region, MemoryRegion::Stack(_)) {
+                continue;
+            }
+
+            let never_read = loads
+                .iter()
+                .all(|load| self.alias.alias(&location, load) == AliasResult::No);
+
+            if never_read || self.overwritten_before_read(ops, op_id, &location) {
+                self.dead_stores.insert(op_id);
+            }
+        }
+    }
+
+    /// 同じ領域内で読まれる前に完全に上書きされるか
+    fn overwritten_before_read(&self, ops: &[PcodeOp], store: OpId, location: &MemoryLocation) -> bool {
+        for (op_id, op) in ops.iter().enumerate().skip(store + 1) {
+            if self.alias.is_region_start(op_id) {
+                return false;
+            }
+            let Some(other) = self.alias.location(op_id) else {
+                continue;
+            };
+            match (op.opcode, self.alias.alias(location, other)) {
+                (OpCode::Load, AliasResult::No) => {}
+                (OpCode::Load, _) => return false,
+                (OpCode::Store, AliasResult::Must) => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+/// StoreからLoadまでの間に値のVarnodeが再定義されていないか
+///
+/// 呼び出しはレジスタを書き換えうるため、レジスタの値は呼び出しを越えて転送しない
+fn redefined_between(ops: &[PcodeOp], from: OpId, to: OpId, value: &Varnode) -> bool {
+    ops[from + 1..to].iter().any(|op| {
+        op.output.as_ref().is_some_and(|out| overlaps(out, value))
+            || (matches!(op.opcode, OpCode::Call | OpCode::CallInd) && value.space == AddressSpace::Register)
+    })
+}
+
+/// 2つのVarnodeが同じ記憶域の一部を共有するか
+fn overlaps(a: &Varnode, b: &Varnode) -> bool {
+    a.space == b.space && a.offset < b.offset + b.size as u64 && b.offset < a.offset + a.size as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompiler_prototype::x86_64::X86Register;
+
+    #[test]
+    fn test_spill_forwarding_and_dead_store() {
+        let rsp = X86Register::RSP.to_varnode_64();
+        let rcx = X86Register::RCX.to_varnode_64();
+        let rax = X86Register::RAX.to_varnode_64();
+        let slot = Varnode::unique(0x100, 8);
+
+        let ops = vec![
+            // mov [rsp+8], rcx
+            PcodeOp::binary(OpCode::IntAdd, slot.clone(), rsp.clone(), Varnode::constant(8, 8), 0x1000),
+            PcodeOp::no_output(OpCode::Store, vec![slot.clone(), rcx.clone()], 0x1000),
+            // mov [rsp+8], 0 (上書き)
+            PcodeOp::no_output(OpCode::Store, vec![slot.clone(), Varnode::constant(0, 8)], 0x1005),
+            // mov [0x140003000], rcx
+            PcodeOp::no_output(OpCode::Store, vec![Varnode::constant(0x140003000, 8), rcx.clone()], 0x100e),
+            // mov rax, [rsp+8]
+            PcodeOp::unary(OpCode::Load, rax.clone(), slot.clone(), 0x1015),
+        ];
+
+        let memory_ssa = MemorySSA::build(&ops);
+
+        // グローバルへのStoreを越えてスタックスロットの値が届く
+        assert_eq!(memory_ssa.reaching_def(4), Some(MemoryAccess::Def(2)));
+        assert_eq!(memory_ssa.forwarded_value(4), Some(&Varnode::constant(0, 8)));
+
+        // 読まれる前に上書きされたStoreのみ不要
+        assert!(memory_ssa.is_dead_store(1));
+        assert!(!memory_ssa.is_dead_store(2));
+        assert!(!memory_ssa.is_dead_store(3));
+    }
+
+    #[test]
+    fn test_call_clobbers_heap_but_not_stack() {
+        let rsp = X86Register::RSP.to_varnode_64();
+        let rbx = X86Register::RBX.to_varnode_64();
+        let rdx = X86Register::RDX.to_varnode_64();
+        let slot = Varnode::unique(0x100, 8);
+
+        let ops = vec![
+            PcodeOp::binary(OpCode::IntAdd, slot.clone(), rsp.clone(), Varnode::constant(0x10, 8), 0x1000),
+            PcodeOp::no_output(OpCode::Store, vec![slot.clone(), Varnode::constant(7, 8)], 0x1000),
+            PcodeOp::no_output(OpCode::Store, vec![rdx.clone(), rbx.clone()], 0x1005),
+            PcodeOp::no_output(OpCode::Call, vec![Varnode::constant(0x2000, 8)], 0x1008),
+            PcodeOp::unary(OpCode::Load, rbx.clone(), slot.clone(), 0x100d),
+            PcodeOp::unary(OpCode::Load, rbx.clone(), rdx.clone(), 0x1012),
+        ];
+
+        let memory_ssa = MemorySSA::build(&ops);
+
+        // エスケープしていないスタックスロットは呼び出しを越えて転送できる
+        assert_eq!(memory_ssa.reaching_def(4), Some(MemoryAccess::Def(1)));
+        assert_eq!(memory_ssa.forwarded_value(4), Some(&Varnode::constant(7, 8)));
+        assert_eq!(memory_ssa.reaching_def(5), Some(MemoryAccess::Clobber(3)));
+        assert_eq!(memory_ssa.forwarded_value(5), None);
+    }
+}
@@ -12,15 +12,21 @@ pub mod printer;
 pub mod capstone_translator;
 pub mod ssa;
 pub mod ssa_advanced;
+pub mod out_of_ssa;
 pub mod nzmask;
 pub mod optimizer;
 pub mod control_flow;
 pub mod type_inference;
 pub mod function_analyzer;
 pub mod parallel_analyzer;
+pub mod decompile_store;
+pub mod function_hash;
+pub mod binary_diff;
 pub mod c_printer;
 pub mod symbol_recovery;
 pub mod dataflow;
+pub mod alias_analysis;
+pub mod memory_ssa;
 pub mod jumptable;
 
 pub use pcode::{OpCode, Varnode, PcodeOp, AddressSpace};
@@ -30,13 +36,19 @@ pub use printer::SimplePrinter;
 pub use capstone_translator::CapstoneTranslator;
 pub use ssa::SSATransform;
 pub use ssa_advanced::{VariableStack, AdvancedSSATransform};
+pub use out_of_ssa::{OutOfSSA, OutOfSSAStats};
 pub use nzmask::{NZMaskAnalyzer, NZMaskStats};
 pub use optimizer::{Optimizer, OptimizationStats, OptimizationRule};
-pub use control_flow::{ControlFlowAnalyzer, ControlStructure, ControlStructurePrinter};
-pub use type_inference::{TypeInference, Type, IntType, FloatType};
+pub use control_flow::{ControlFlowAnalyzer, ControlStructure, ControlStructurePrinter, Condition, InductionVariable};
+pub use type_inference::{TypeInference, Type, IntType, FloatType, TypeConflict, ConflictOrigin};
 pub use function_analyzer::{FunctionDetector, FunctionInfo, FunctionStatistics};
-pub use parallel_analyzer::{ParallelDecompiler, CachedFunctionResult, CacheStatistics, HashStrategy};
+pub use parallel_analyzer::{ParallelDecompiler, CachedFunctionResult, CacheStatistics, HashStrategy, BatchOptions, BatchProgress, BatchFailure, BatchReport};
+pub use decompile_store::{DecompileStore, StoreStatistics, FunctionAnnotation, DECOMPILER_VERSION, STORE_SCHEMA_VERSION};
+pub use function_hash::{FunctionFingerprint, fingerprint_function, fingerprint_at, file_offset_for_address, FINGERPRINT_VERSION};
+pub use binary_diff::{diff_binaries, BinaryDiff, BinaryDiffPage, DiffEntry, DiffStatus, DiffSummary, MatchKind};
 pub use c_printer::CPrinter;
 pub use symbol_recovery::{SymbolTable, Symbol, SymbolKind};
 pub use dataflow::{DefUseChain, CopyPropagation, DeadCodeElimination, DataFlowStats};
-pub use jumptable::{JumpTable, JumpTableDetector, SwitchStatement, SwitchPrinter};
+pub use alias_analysis::{AliasAnalysis, AliasResult, MemoryLocation, MemoryRegion};
+pub use memory_ssa::{MemorySSA, MemoryAccess};
+pub use jumptable::{JumpTable, JumpTableDetector, JumpTableKind, JumpTableLoader, SwitchStatement, SwitchPrinter};
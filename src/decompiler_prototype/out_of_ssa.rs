@@ -0,0 +1,583 @@
+/// SSA形式の解除（Out-of-SSA）
+///
+/// Phi-node（MultiEqual）をコピーに置き換え、干渉しないSSA変数を合体させて
+/// 元の変数名に戻す。CPrinterがSSAバージョンを意識せずに安定した
+/// 変数名で出力できるようにするためのパス
+
+use crate::decompiler_prototype::cfg::{BasicBlock, BlockId, ControlFlowGraph};
+use crate::decompiler_prototype::pcode::{AddressSpace, OpCode, PcodeOp, Varnode};
+use std::collections::{HashMap, HashSet};
+
+/// SSA解除の統計情報
+#[derive(Debug, Clone, Default)]
+pub struct OutOfSSAStats {
+    /// 削除したPhi-nodeの数
+    pub phis_removed: usize,
+    /// 挿入したコピー命令の数
+    pub copies_inserted: usize,
+    /// 合体によって不要になったコピーの数
+    pub copies_coalesced: usize,
+    /// コピー挿入のために分割したクリティカルエッジの数
+    pub edges_split: usize,
+}
+
+/// 辺上の並列コピー (先行ブロック, 合流ブロック, 先行ブロック位置, [(dst, src)])
+type EdgeCopies = (BlockId, BlockId, usize, Vec<(Varnode, Varnode)>);
+
+/// SSA解除パス
+///
+/// 1. Phi-nodeを考慮した生存解析で干渉関係を求める
+/// 2. Phi-nodeの結果と引数を、干渉しなければ同じ変数に合体（コピー合体）
+/// 3. 同じ元変数を持つ残りの変数も干渉しなければ合体
+/// 4. 合体できなかったPhi-nodeは先行ブロック末尾の並列コピーに変換
+pub struct OutOfSSA {
+    /// 合体クラス（Union-Find の親）
+    parent: HashMap<Varnode, Varnode>,
+    /// 干渉する変数の組
+    interference: HashSet<(Varnode, Varnode)>,
+    /// 統計情報
+    stats: OutOfSSAStats,
+}
+
+impl OutOfSSA {
+    pub fn new() -> Self {
+        Self {
+            parent: HashMap::new(),
+            interference: HashSet::new(),
+            stats: OutOfSSAStats::default(),
+        }
+    }
+
+    /// CFGをSSA形式から通常の変数に戻す
+    pub fn apply(&mut self, cfg: &mut ControlFlowGraph) -> OutOfSSAStats {
+        self.parent.clear();
+        self.interference.clear();
+        self.stats = OutOfSSAStats::default();
+
+        let live_out = Self::compute_live_out(cfg);
+        self.build_interference(cfg, &live_out);
+        self.coalesce_phis(cfg);
+        self.coalesce_same_variable(cfg);
+
+        let names = self.assign_names(cfg);
+        self.eliminate_phis(cfg, &names);
+
+        self.stats.clone()
+    }
+
+    /// 統計情報を取得
+    pub fn stats(&self) -> &OutOfSSAStats {
+        &self.stats
+    }
+
+    /// ブロック出口で生存しているSSA変数を計算
+    ///
+    /// Phi-nodeの入力は対応する先行ブロックの出口で使われるものとして扱う
+    fn compute_live_out(cfg: &ControlFlowGraph) -> HashMap<BlockId, HashSet<Varnode>> {
+        let mut uses: HashMap<BlockId, HashSet<Varnode>> = HashMap::new();
+        let mut defs: HashMap<BlockId, HashSet<Varnode>> = HashMap::new();
+        let mut phi_uses: HashMap<BlockId, HashSet<Varnode>> = HashMap::new();
+
+        for (&block_id, block) in &cfg.blocks {
+            let block_uses = uses.entry(block_id).or_default();
+            let block_defs = defs.entry(block_id).or_default();
+
+            for op in &block.ops {
+                if op.opcode == OpCode::MultiEqual {
+                    for (i, input) in op.inputs.iter().enumerate() {
+                        if let (Some(&pred), true) = (block.predecessors.get(i), is_variable(input)) {
+                            phi_uses.entry(pred).or_default().insert(input.clone());
+                        }
+                    }
+                } else {
+                    for input in op.inputs.iter().filter(|v| is_variable(v)) {
+                        if !block_defs.contains(input) {
+                            block_uses.insert(input.clone());
+                        }
+                    }
+                }
+                if let Some(ref output) = op.output {
+                    block_defs.insert(output.clone());
+                }
+            }
+        }
+
+        let mut live_in: HashMap<BlockId, HashSet<Varnode>> = uses.clone();
+        let mut live_out: HashMap<BlockId, HashSet<Varnode>> = cfg.blocks
+            .keys()
+            .map(|&b| (b, phi_uses.get(&b).cloned().unwrap_or_default()))
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (&block_id, block) in &cfg.blocks {
+                for succ in &block.successors {
+                    let succ_live: Vec<Varnode> = live_in.get(succ).map(|s| s.iter().cloned().collect()).unwrap_or_default();
+                    let out = live_out.get_mut(&block_id).unwrap();
+                    for var in succ_live {
+                        if out.insert(var) {
+                            changed = true;
+                        }
+                    }
+                }
+
+                let out: Vec<Varnode> = live_out[&block_id].iter().cloned().collect();
+                let entry = live_in.get_mut(&block_id).unwrap();
+                for var in out {
+                    if !defs[&block_id].contains(&var) && entry.insert(var) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        live_out
+    }
+
+    /// 干渉グラフを構築
+    fn build_interference(&mut self, cfg: &ControlFlowGraph, live_out: &HashMap<BlockId, HashSet<Varnode>>) {
+        for (&block_id, block) in &cfg.blocks {
+            let mut live = live_out.get(&block_id).cloned().unwrap_or_default();
+            let mut phi_defs = Vec::new();
+
+            for op in block.ops.iter().rev() {
+                if op.opcode == OpCode::MultiEqual {
+                    if let Some(ref output) = op.output {
+                        phi_defs.push(output.clone());
+                    }
+                    continue;
+                }
+
+                if let Some(ref output) = op.output {
+                    // コピー元とは干渉しない（同じ値を持つため）
+                    let copy_source = if op.opcode == OpCode::Copy { op.inputs.first() } else { None };
+                    for other in &live {
+                        if other != output && Some(other) != copy_source {
+                            self.add_interference(output, other);
+                        }
+                    }
+                    live.remove(output);
+                }
+
+                for input in op.inputs.iter().filter(|v| is_variable(v)) {
+                    live.insert(input.clone());
+                }
+            }
+
+            // Phi-nodeの出力はブロック先頭で同時に定義される
+            for (i, def) in phi_defs.iter().enumerate() {
+                for other in phi_defs.iter().skip(i + 1) {
+                    self.add_interference(def, other);
+                }
+                for other in &live {
+                    if other != def {
+                        self.add_interference(def, other);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_interference(&mut self, a: &Varnode, b: &Varnode) {
+        self.interference.insert((a.clone(), b.clone()));
+        self.interference.insert((b.clone(), a.clone()));
+    }
+
+    /// Union-Find: 代表元を取得
+    fn find(&mut self, vn: &Varnode) -> Varnode {
+        let parent = match self.parent.get(vn) {
+            Some(p) if p != vn => p.clone(),
+            _ => return vn.clone(),
+        };
+        let root = self.find(&parent);
+        self.parent.insert(vn.clone(), root.clone());
+        root
+    }
+
+    /// クラスのメンバーを列挙
+    fn members(&mut self, root: &Varnode, all: &[Varnode]) -> Vec<Varnode> {
+        all.iter().filter(|v| &self.find(v) == root).cloned().collect()
+    }
+
+    /// 2つのクラスを干渉しなければ合体
+    fn try_union(&mut self, a: &Varnode, b: &Varnode, all: &[Varnode]) -> bool {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return true;
+        }
+
+        let members_a = self.members(&root_a, all);
+        let members_b = self.members(&root_b, all);
+        let interferes = members_a.iter().any(|x| {
+            members_b.iter().any(|y| self.interference.contains(&(x.clone(), y.clone())))
+        });
+        if interferes {
+            return false;
+        }
+
+        self.parent.insert(root_b, root_a);
+        true
+    }
+
+    /// Phi-nodeの結果と引数を合体
+    fn coalesce_phis(&mut self, cfg: &ControlFlowGraph) {
+        let all = all_variables(cfg);
+
+        for block in ordered_blocks(cfg) {
+            for op in block.ops.iter().filter(|op| op.opcode == OpCode::MultiEqual) {
+                let output = match op.output {
+                    Some(ref output) => output,
+                    None => continue,
+                };
+                for input in op.inputs.iter().filter(|v| is_variable(v)) {
+                    if self.find(output) != self.find(input) && self.try_union(output, input, &all) {
+                        self.stats.copies_coalesced += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// 同じ元変数を持つクラス同士を、干渉しなければ合体
+    fn coalesce_same_variable(&mut self, cfg: &ControlFlowGraph) {
+        let all = all_variables(cfg);
+        let mut by_base: HashMap<Varnode, Vec<Varnode>> = HashMap::new();
+        for vn in &all {
+            by_base.entry(vn.base()).or_default().push(vn.clone());
+        }
+
+        let mut bases: Vec<Varnode> = by_base.keys().cloned().collect();
+        bases.sort_by_key(varnode_order);
+
+        for base in bases {
+            let versions = &by_base[&base];
+            for (i, a) in versions.iter().enumerate() {
+                for b in versions.iter().skip(i + 1) {
+                    self.try_union(a, b, &all);
+                }
+            }
+        }
+    }
+
+    /// 合体クラスごとに最終的な変数を決める
+    ///
+    /// 元変数ごとに最初のクラス（関数入力を含むか、最小バージョン）が
+    /// バージョン0を受け継ぎ、残りは1から順に番号を振る
+    fn assign_names(&mut self, cfg: &ControlFlowGraph) -> HashMap<Varnode, Varnode> {
+        let all = all_variables(cfg);
+
+        let mut classes: HashMap<Varnode, Vec<Varnode>> = HashMap::new();
+        for vn in &all {
+            let root = self.find(vn);
+            classes.entry(root).or_default().push(vn.clone());
+        }
+
+        // 元変数ごとにクラスを並べる
+        let mut by_base: HashMap<Varnode, Vec<Vec<Varnode>>> = HashMap::new();
+        for (_, mut members) in classes {
+            members.sort_by_key(|v| v.version);
+            by_base.entry(members[0].base()).or_default().push(members);
+        }
+
+        let mut names = HashMap::new();
+        for (base, mut groups) in by_base {
+            groups.sort_by_key(|members| members[0].version);
+            for (i, members) in groups.into_iter().enumerate() {
+                let name = base.with_version(i as u32);
+                for member in members {
+                    names.insert(member, name.clone());
+                }
+            }
+        }
+
+        names
+    }
+
+    /// Phi-nodeをコピーに置き換え、全Varnodeを最終的な変数に書き換える
+    fn eliminate_phis(&mut self, cfg: &mut ControlFlowGraph, names: &HashMap<Varnode, Varnode>) {
+        let rename = |vn: &Varnode| names.get(vn).cloned().unwrap_or_else(|| vn.clone());
+        let mut next_temp = next_unique_offset(cfg);
+
+        // 辺ごとの並列コピーを収集
+        let mut edge_copies: Vec<EdgeCopies> = Vec::new();
+        let mut block_ids: Vec<BlockId> = cfg.blocks.keys().copied().collect();
+        block_ids.sort_unstable();
+
+        for &block_id in &block_ids {
+            let block = &cfg.blocks[&block_id];
+            let phis: Vec<&PcodeOp> = block.ops.iter().filter(|op| op.opcode == OpCode::MultiEqual).collect();
+            if phis.is_empty() {
+                continue;
+            }
+
+            for (i, &pred) in block.predecessors.iter().enumerate() {
+                let copies: Vec<(Varnode, Varnode)> = phis
+                    .iter()
+                    .filter_map(|phi| {
+                        let dst = rename(phi.output.as_ref()?);
+                        let src = rename(phi.inputs.get(i)?);
+                        if dst == src { None } else { Some((dst, src)) }
+                    })
+                    .collect();
+                if !copies.is_empty() {
+                    edge_copies.push((pred, block_id, i, copies));
+                }
+            }
+        }
+
+        // Phi-nodeを削除し、残りの命令の変数を書き換える
+        for block in cfg.blocks.values_mut() {
+            let before = block.ops.len();
+            block.ops.retain(|op| op.opcode != OpCode::MultiEqual);
+            self.stats.phis_removed += before - block.ops.len();
+
+            for op in &mut block.ops {
+                if let Some(ref mut output) = op.output {
+                    *output = rename(output);
+                }
+                for input in op.inputs.iter_mut().filter(|v| is_variable(v)) {
+                    *input = rename(input);
+                }
+            }
+        }
+
+        // 並列コピーを逐次化して挿入
+        for (pred, succ, pred_index, copies) in edge_copies {
+            let target = if cfg.blocks.get(&pred).map(|b| b.successors.len() > 1).unwrap_or(false) {
+                self.split_edge(cfg, pred, succ, pred_index)
+            } else {
+                pred
+            };
+
+            let block = match cfg.blocks.get_mut(&target) {
+                Some(b) => b,
+                None => continue,
+            };
+            let address = block.ops.last().map(|op| op.address).unwrap_or(block.start_address);
+            let sequential = sequentialize(copies, &mut next_temp, address);
+            self.stats.copies_inserted += sequential.len();
+
+            // 終端の分岐命令の直前に挿入
+            let pos = if block.is_branch() { block.ops.len() - 1 } else { block.ops.len() };
+            for (k, op) in sequential.into_iter().enumerate() {
+                block.ops.insert(pos + k, op);
+            }
+        }
+    }
+
+    /// クリティカルエッジ pred -> succ を分割し、新しいブロックのIDを返す
+    fn split_edge(&mut self, cfg: &mut ControlFlowGraph, pred: BlockId, succ: BlockId, pred_index: usize) -> BlockId {
+        let new_id = cfg.next_block_id.max(cfg.blocks.keys().max().map(|m| m + 1).unwrap_or(0));
+        cfg.next_block_id = new_id + 1;
+
+        let start_address = cfg.blocks.get(&succ).map(|b| b.start_address).unwrap_or(0);
+        let mut block = BasicBlock::new(new_id, start_address);
+        block.predecessors.push(pred);
+        block.successors.push(succ);
+        cfg.blocks.insert(new_id, block);
+
+        if let Some(pred_block) = cfg.blocks.get_mut(&pred) {
+            if let Some(s) = pred_block.successors.iter_mut().find(|s| **s == succ) {
+                *s = new_id;
+            }
+        }
+        if let Some(succ_block) = cfg.blocks.get_mut(&succ) {
+            if let Some(p) = succ_block.predecessors.get_mut(pred_index) {
+                *p = new_id;
+            }
+        }
+
+        self.stats.edges_split += 1;
+        new_id
+    }
+}
+
+impl Default for OutOfSSA {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 並列コピーを逐次的なコピー列に変換
+///
+/// 他のコピーの読み取り元になっていない宛先から順に書き込み、
+/// 循環（swap）が残った場合は一時変数で断ち切る
+fn sequentialize(mut pending: Vec<(Varnode, Varnode)>, next_temp: &mut u64, address: u64) -> Vec<PcodeOp> {
+    let mut ops = Vec::new();
+
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(dst, _)| !pending.iter().any(|(_, src)| src == dst));
+
+        match ready {
+            Some(pos) => {
+                let (dst, src) = pending.remove(pos);
+                ops.push(PcodeOp::unary(OpCode::Copy, dst, src, address));
+            }
+            None => {
+                // 循環: 最初の宛先の現在値を一時変数に退避
+                let dst = pending[0].0.clone();
+                let temp = Varnode::unique(*next_temp, dst.size);
+                *next_temp += dst.size as u64;
+                ops.push(PcodeOp::unary(OpCode::Copy, temp.clone(), dst.clone(), address));
+                for (_, src) in pending.iter_mut() {
+                    if *src == dst {
+                        *src = temp.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    ops
+}
+
+/// 変数（定数以外）か
+fn is_variable(vn: &Varnode) -> bool {
+    vn.space != AddressSpace::Const
+}
+
+/// Varnodeの決定的な並び順
+fn varnode_order(vn: &Varnode) -> (u8, u64, usize, u32) {
+    (vn.space as u8, vn.offset, vn.size, vn.version)
+}
+
+/// ブロックをID順に取得
+fn ordered_blocks(cfg: &ControlFlowGraph) -> Vec<&BasicBlock> {
+    cfg.blocks_in_order()
+}
+
+/// CFG中のすべての変数を決定的な順序で収集
+fn all_variables(cfg: &ControlFlowGraph) -> Vec<Varnode> {
+    let mut vars = HashSet::new();
+    for block in cfg.blocks.values() {
+        for op in &block.ops {
+            if let Some(ref output) = op.output {
+                vars.insert(output.clone());
+            }
+            for input in op.inputs.iter().filter(|v| is_variable(v)) {
+                vars.insert(input.clone());
+            }
+        }
+    }
+
+    let mut vars: Vec<Varnode> = vars.into_iter().collect();
+    vars.sort_by_key(varnode_order);
+    vars
+}
+
+/// 既存の一時変数と衝突しないUnique空間のオフセット
+fn next_unique_offset(cfg: &ControlFlowGraph) -> u64 {
+    cfg.blocks
+        .values()
+        .flat_map(|b| b.ops.iter())
+        .flat_map(|op| op.output.iter().chain(op.inputs.iter()))
+        .filter(|v| v.space == AddressSpace::Unique)
+        .map(|v| v.offset + v.size as u64)
+        .max()
+        .unwrap_or(0)
+        .max(0x30000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompiler_prototype::c_printer::CPrinter;
+    use crate::decompiler_prototype::ssa::SSATransform;
+    use crate::decompiler_prototype::type_inference::TypeInference;
+
+    fn link(cfg: &mut ControlFlowGraph, from: BlockId, to: BlockId) {
+        cfg.blocks.get_mut(&from).unwrap().successors.push(to);
+        cfg.blocks.get_mut(&to).unwrap().predecessors.push(from);
+    }
+
+    #[test]
+    fn test_loop_round_trip() {
+        // Block 0: x = 0
+        // Block 1: if (x < 10) goto 2 else goto 3
+        // Block 2: x = x + 1 -> Block 1
+        // Block 3: return x
+        let x = Varnode::register(0, 8);
+        let cond = Varnode::unique(0x100, 1);
+
+        let mut cfg = ControlFlowGraph::new();
+        for (id, addr) in [(0, 0x1000), (1, 0x1010), (2, 0x1020), (3, 0x1030)] {
+            cfg.blocks.insert(id, BasicBlock::new(id, addr));
+        }
+        cfg.next_block_id = 4;
+        cfg.blocks.get_mut(&0).unwrap().add_op(PcodeOp::unary(OpCode::Copy, x.clone(), Varnode::constant(0, 8), 0x1000));
+        cfg.blocks.get_mut(&1).unwrap().add_op(PcodeOp::binary(OpCode::IntSLess, cond.clone(), x.clone(), Varnode::constant(10, 8), 0x1010));
+        cfg.blocks.get_mut(&1).unwrap().add_op(PcodeOp::no_output(OpCode::CBranch, vec![Varnode::constant(0x1020, 8), cond], 0x1014));
+        cfg.blocks.get_mut(&2).unwrap().add_op(PcodeOp::binary(OpCode::IntAdd, x.clone(), x.clone(), Varnode::constant(1, 8), 0x1020));
+        cfg.blocks.get_mut(&3).unwrap().add_op(PcodeOp::no_output(OpCode::Return, vec![x.clone()], 0x1030));
+        link(&mut cfg, 0, 1);
+        link(&mut cfg, 1, 2);
+        link(&mut cfg, 1, 3);
+        link(&mut cfg, 2, 1);
+
+        let mut ssa = SSATransform::new();
+        ssa.transform(&mut cfg);
+        assert_eq!(ssa.phi_count(), 1);
+
+        let stats = OutOfSSA::new().apply(&mut cfg);
+        assert_eq!(stats.phis_removed, 1);
+        assert_eq!(stats.copies_inserted, 0);
+
+        // すべての変数がバージョン0（元の名前）に戻っている
+        for block in cfg.blocks.values() {
+            for op in &block.ops {
+                assert_ne!(op.opcode, OpCode::MultiEqual);
+                for vn in op.output.iter().chain(op.inputs.iter()) {
+                    assert_eq!(vn.version, 0, "{}", op);
+                }
+            }
+        }
+
+        let ops: Vec<PcodeOp> = cfg.blocks_in_order().iter().flat_map(|b| b.ops.clone()).collect();
+        let code = CPrinter::new(TypeInference::new()).print(&ops);
+        assert!(code.contains("r0 = (r0 + 1);"));
+        assert!(!code.contains("r0_"));
+    }
+
+    #[test]
+    fn test_swap_needs_temporary() {
+        // Block 0: x1 = 1; y1 = 2
+        // Block 1: x2 = phi(x1, y2); y2 = phi(y1, x2); if (c) goto 1 else goto 2
+        // Block 2: return
+        let x = Varnode::register(0, 8);
+        let y = Varnode::register(8, 8);
+        let c = Varnode::register(16, 1);
+
+        let mut cfg = ControlFlowGraph::new();
+        for (id, addr) in [(0, 0x2000), (1, 0x2010), (2, 0x2020)] {
+            cfg.blocks.insert(id, BasicBlock::new(id, addr));
+        }
+        cfg.next_block_id = 3;
+        let b0 = cfg.blocks.get_mut(&0).unwrap();
+        b0.add_op(PcodeOp::unary(OpCode::Copy, x.with_version(1), Varnode::constant(1, 8), 0x2000));
+        b0.add_op(PcodeOp::unary(OpCode::Copy, y.with_version(1), Varnode::constant(2, 8), 0x2004));
+        let b1 = cfg.blocks.get_mut(&1).unwrap();
+        b1.add_op(PcodeOp::new(OpCode::MultiEqual, Some(x.with_version(2)), vec![x.with_version(1), y.with_version(2)], 0x2010));
+        b1.add_op(PcodeOp::new(OpCode::MultiEqual, Some(y.with_version(2)), vec![y.with_version(1), x.with_version(2)], 0x2010));
+        b1.add_op(PcodeOp::no_output(OpCode::CBranch, vec![Varnode::constant(0x2010, 8), c], 0x2014));
+        cfg.blocks.get_mut(&2).unwrap().add_op(PcodeOp::no_output(OpCode::Return, vec![x.with_version(2)], 0x2020));
+        link(&mut cfg, 0, 1);
+        link(&mut cfg, 1, 1);
+        link(&mut cfg, 1, 2);
+
+        let stats = OutOfSSA::new().apply(&mut cfg);
+        assert_eq!(stats.phis_removed, 2);
+        assert_eq!(stats.edges_split, 1);
+        // x <-> y の入れ替えには一時変数を含む3コピーが必要
+        assert_eq!(stats.copies_inserted, 3);
+
+        let split = &cfg.blocks[&3];
+        assert_eq!(split.successors, vec![1]);
+        assert_eq!(split.ops[0].output.as_ref().unwrap().space, AddressSpace::Unique);
+        assert!(cfg.blocks[&1].successors.contains(&3));
+    }
+}
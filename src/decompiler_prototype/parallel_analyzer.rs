@@ -9,10 +9,13 @@ use super::optimizer::*;
 use super::type_inference::*;
 use super::control_flow::*;
 use super::capstone_translator::*;
-use anyhow::Result;
+use super::decompile_store::{DecompileStore, FunctionAnnotation, DEFAULT_MAX_STORE_BYTES};
+use super::function_hash::fingerprint_function;
+use anyhow::{bail, Result};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use xxhash_rust::xxh3::Xxh3;
 
@@ -49,6 +52,9 @@ pub struct DecompileCache {
 pub struct CachedFunctionResult {
     /// 関数アドレス
     pub address: u64,
+    /// 関数本体の位置に依存しないハッシュ（ディスクストアのキー）
+    #[serde(default)]
+    pub function_hash: u64,
     /// P-code命令数
     pub pcode_count: usize,
     /// 基本ブロック数
@@ -59,16 +65,125 @@ pub struct CachedFunctionResult {
     pub loop_count: usize,
     /// 制御構造の文字列表現
     pub control_structure: String,
+    /// 型推論で検出された型衝突
+    #[serde(default)]
+    pub type_conflicts: Vec<TypeConflict>,
     /// キャッシュ作成時刻（UNIX timestamp）
     pub cached_at: u64,
 }
 
+impl CachedFunctionResult {
+    /// 別のアドレスにある同じ本体の関数の結果として付け替える
+    ///
+    /// 型衝突の命令アドレスは関数先頭からの相対位置を保って移す
+    pub fn rebased(&self, address: u64) -> Self {
+        let mut result = self.clone();
+        let delta = address.wrapping_sub(self.address);
+        result.address = address;
+        for conflict in &mut result.type_conflicts {
+            for origin in &mut conflict.origins {
+                origin.address = origin.address.wrapping_add(delta);
+            }
+        }
+        result
+    }
+}
+
+/// 一括デコンパイルの設定
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// 1関数あたりの最大命令数
+    pub max_instructions: usize,
+    /// 1関数あたりの制限時間（解析の各段階の間で確認する）
+    pub function_timeout: Duration,
+    /// 1関数あたりのP-code命令数の上限（メモリ使用量の上限）
+    pub max_pcode_ops: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            max_instructions: 1000,
+            function_timeout: Duration::from_secs(10),
+            max_pcode_ops: 200_000,
+        }
+    }
+}
+
+/// 一括デコンパイルの進捗
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgress {
+    /// 処理済みの関数数
+    pub completed: usize,
+    /// 対象の関数数
+    pub total: usize,
+    /// キャッシュ済みで再解析しなかった関数数
+    pub cached: usize,
+    /// 失敗した関数数
+    pub failed: usize,
+    /// 直前に処理した関数のアドレス
+    pub last_address: u64,
+}
+
+/// 一括デコンパイルで失敗した関数
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchFailure {
+    pub address: u64,
+    pub reason: String,
+}
+
+/// 一括デコンパイルの結果
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    /// 結果を格納したキャッシュのファイルハッシュ
+    pub file_hash: String,
+    pub total: usize,
+    /// 新たにデコンパイルした関数数
+    pub decompiled: usize,
+    /// キャッシュ済みだった関数数
+    pub cached: usize,
+    pub failures: Vec<BatchFailure>,
+    pub elapsed_ms: u64,
+}
+
+/// 1関数の解析に課す制限
+struct FunctionLimits {
+    deadline: Option<Instant>,
+    max_pcode_ops: Option<usize>,
+}
+
+impl FunctionLimits {
+    fn none() -> Self {
+        Self { deadline: None, max_pcode_ops: None }
+    }
+
+    /// 制限時間を過ぎていればエラー
+    fn check_deadline(&self, stage: &str) -> Result<()> {
+        if let Some(deadline) = self.deadline {
+            if Instant::now() > deadline {
+                bail!("timed out after {}", stage);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 一括デコンパイル中の集計
+struct BatchState {
+    progress: BatchProgress,
+    decompiled: usize,
+    failures: Vec<BatchFailure>,
+    store_error: Option<anyhow::Error>,
+}
+
 /// 並列デコンパイラ
 pub struct ParallelDecompiler {
     /// キャッシュディレクトリ
     cache_dir: PathBuf,
     /// メモリ内キャッシュ
     memory_cache: Arc<Mutex<HashMap<String, DecompileCache>>>,
+    /// 関数単位のディスクストア
+    store: DecompileStore,
     /// ハッシュ計算戦略
     hash_strategy: HashStrategy,
 }
@@ -83,10 +198,12 @@ impl ParallelDecompiler {
     pub fn with_strategy<P: AsRef<Path>>(cache_dir: P, strategy: HashStrategy) -> Result<Self> {
         let cache_dir = cache_dir.as_ref().to_path_buf();
         std::fs::create_dir_all(&cache_dir)?;
+        let store = DecompileStore::open(&cache_dir, DEFAULT_MAX_STORE_BYTES)?;
 
         Ok(Self {
             cache_dir,
             memory_cache: Arc::new(Mutex::new(HashMap::new())),
+            store,
             hash_strategy: strategy,
         })
     }
@@ -157,9 +274,42 @@ impl ParallelDecompiler {
         format!("{:x}", hasher.digest())
     }
 
-    /// キャッシュファイルのパスを取得
-    fn get_cache_path(&self, file_hash: &str) -> PathBuf {
-        self.cache_dir.join(format!("{}.json", file_hash))
+    /// 解析対象のコード範囲（関数先頭から最大命令数分）
+    fn code_slice(binary_data: &[u8], file_offset: usize, max_instructions: usize) -> &[u8] {
+        if file_offset < binary_data.len() {
+            let end = std::cmp::min(file_offset + max_instructions * 15, binary_data.len());
+            &binary_data[file_offset..end]
+        } else {
+            &[]
+        }
+    }
+
+    /// 関数本体のコードとハッシュ
+    ///
+    /// 位置に依存しない正規化ハッシュを求め、解析対象を関数本体に絞る
+    /// 逆アセンブルできない場合はコード範囲の生バイトとアドレスから求める
+    fn function_body(binary_data: &[u8], function_address: u64, file_offset: usize, max_instructions: usize) -> (u64, &[u8]) {
+        let code = Self::code_slice(binary_data, file_offset, max_instructions);
+        match fingerprint_function(code, function_address, max_instructions) {
+            Ok(fingerprint) => {
+                let mut hasher = Xxh3::new();
+                hasher.update(&fingerprint.hash.to_le_bytes());
+                hasher.update(&(max_instructions as u64).to_le_bytes());
+                (hasher.digest(), &code[..fingerprint.size])
+            }
+            Err(_) => {
+                let mut hasher = Xxh3::new();
+                hasher.update(&function_address.to_le_bytes());
+                hasher.update(&(max_instructions as u64).to_le_bytes());
+                hasher.update(code);
+                (hasher.digest(), code)
+            }
+        }
+    }
+
+    /// 関数のハッシュを計算
+    fn compute_function_hash(binary_data: &[u8], function_address: u64, file_offset: usize, max_instructions: usize) -> u64 {
+        Self::function_body(binary_data, function_address, file_offset, max_instructions).0
     }
 
     /// キャッシュをロード
@@ -171,22 +321,26 @@ impl ParallelDecompiler {
             }
         }
 
-        // ディスクキャッシュを確認
-        let cache_path = self.get_cache_path(file_hash);
-        if let Ok(data) = std::fs::read_to_string(&cache_path) {
-            if let Ok(cache) = serde_json::from_str::<DecompileCache>(&data) {
-                // メモリキャッシュに格納
-                if let Ok(mut mem_cache) = self.memory_cache.lock() {
-                    mem_cache.insert(file_hash.to_string(), cache.clone());
-                }
-                return Some(cache);
-            }
+        // ディスクストアを確認
+        let results = self.store.load_binary(file_hash);
+        if results.is_empty() {
+            return None;
         }
 
-        None
+        let cache = DecompileCache {
+            file_hash: file_hash.to_string(),
+            results,
+        };
+        // メモリキャッシュに格納
+        if let Ok(mut mem_cache) = self.memory_cache.lock() {
+            mem_cache.insert(file_hash.to_string(), cache.clone());
+        }
+        Some(cache)
     }
 
     /// キャッシュを保存
+    ///
+    /// 各結果を関数単位のレコードとして書き込む（既存のレコードは書き直さない）
     pub fn save_cache(&self, file_hash: &str, cache: &DecompileCache) -> Result<()> {
         // メモリキャッシュに格納
         if let Ok(mut mem_cache) = self.memory_cache.lock() {
@@ -194,13 +348,72 @@ impl ParallelDecompiler {
         }
 
         // ディスクに保存
-        let cache_path = self.get_cache_path(file_hash);
-        let json = serde_json::to_string_pretty(cache)?;
-        std::fs::write(&cache_path, json)?;
+        for result in cache.results.values() {
+            self.store.put(file_hash, result.function_hash, result)?;
+        }
 
         Ok(())
     }
 
+    /// 1関数の結果をメモリキャッシュとディスクストアに格納
+    fn store_result(&self, file_hash: &str, result: &CachedFunctionResult) -> Result<()> {
+        if let Ok(mut mem_cache) = self.memory_cache.lock() {
+            mem_cache
+                .entry(file_hash.to_string())
+                .or_insert_with(|| DecompileCache {
+                    file_hash: file_hash.to_string(),
+                    results: HashMap::new(),
+                })
+                .results
+                .insert(result.address, result.clone());
+        }
+        self.store.put(file_hash, result.function_hash, result)
+    }
+
+    /// キャッシュ済みの結果を取得
+    ///
+    /// 同じバイナリに結果がなければ、以前のビルドなど別のバイナリから
+    /// 同じ本体の関数の結果を探し、アドレスを付け替えて現在のバイナリに登録する
+    fn cached_result(&self, file_hash: &str, function_address: u64, function_hash: u64) -> Option<CachedFunctionResult> {
+        if let Ok(cache) = self.memory_cache.lock() {
+            let cached = cache
+                .get(file_hash)
+                .and_then(|cache| cache.results.get(&function_address))
+                .filter(|result| result.function_hash == function_hash);
+            if let Some(result) = cached {
+                return Some(result.clone());
+            }
+        }
+
+        if let Some(result) = self.store.get(file_hash, function_hash) {
+            return Some(result.rebased(function_address));
+        }
+
+        let result = self.store.find_function(function_hash)?.rebased(function_address);
+        let _ = self.store_result(file_hash, &result);
+        Some(result)
+    }
+
+    /// 関数の名前・注釈を取得
+    pub fn function_annotation(&self, binary_data: &[u8], function_address: u64, file_offset: usize, max_instructions: usize) -> Option<FunctionAnnotation> {
+        let function_hash = Self::compute_function_hash(binary_data, function_address, file_offset, max_instructions);
+        self.store.get_annotation(function_hash)
+    }
+
+    /// 関数に名前・注釈を付ける（同じ本体の関数には別のビルドでも引き継がれる）
+    pub fn annotate_function(
+        &self,
+        binary_data: &[u8],
+        function_address: u64,
+        file_offset: usize,
+        max_instructions: usize,
+        annotation: &FunctionAnnotation,
+    ) -> Result<u64> {
+        let function_hash = Self::compute_function_hash(binary_data, function_address, file_offset, max_instructions);
+        self.store.put_annotation(function_hash, annotation)?;
+        Ok(function_hash)
+    }
+
     /// 関数をデコンパイル（キャッシュあり）
     pub fn decompile_function_cached(
         &self,
@@ -211,12 +424,11 @@ impl ParallelDecompiler {
         max_instructions: usize,
     ) -> Result<CachedFunctionResult> {
         let file_hash = self.compute_file_hash(binary_path, binary_data);
+        let function_hash = Self::compute_function_hash(binary_data, function_address, file_offset, max_instructions);
 
         // キャッシュを確認
-        if let Some(cache) = self.load_cache(&file_hash) {
-            if let Some(result) = cache.results.get(&function_address) {
-                return Ok(result.clone());
-            }
+        if let Some(result) = self.cached_result(&file_hash, function_address, function_hash) {
+            return Ok(result);
         }
 
         // キャッシュがなければデコンパイル実行
@@ -228,13 +440,7 @@ impl ParallelDecompiler {
         )?;
 
         // キャッシュに保存
-        let mut cache = self.load_cache(&file_hash).unwrap_or(DecompileCache {
-            file_hash: file_hash.clone(),
-            results: HashMap::new(),
-        });
-
-        cache.results.insert(function_address, result.clone());
-        self.save_cache(&file_hash, &cache)?;
+        self.store_result(&file_hash, &result)?;
 
         Ok(result)
     }
@@ -247,21 +453,35 @@ impl ParallelDecompiler {
         file_offset: usize,
         max_instructions: usize,
     ) -> Result<CachedFunctionResult> {
-        // コードスライスを抽出
-        let code_slice = if file_offset < binary_data.len() {
-            let end = std::cmp::min(file_offset + max_instructions * 15, binary_data.len());
-            &binary_data[file_offset..end]
-        } else {
-            &[]
-        };
+        self.decompile_function_limited(binary_data, function_address, file_offset, max_instructions, &FunctionLimits::none())
+    }
+
+    /// 関数をデコンパイル（時間・P-code数の制限付き）
+    fn decompile_function_limited(
+        &self,
+        binary_data: &[u8],
+        function_address: u64,
+        file_offset: usize,
+        max_instructions: usize,
+        limits: &FunctionLimits,
+    ) -> Result<CachedFunctionResult> {
+        // 関数本体を抽出
+        let (function_hash, code_slice) = Self::function_body(binary_data, function_address, file_offset, max_instructions);
 
         // P-codeに変換
         let mut translator = CapstoneTranslator::new()?;
         let mut pcodes = translator.translate(code_slice, function_address, max_instructions)?;
+        if let Some(max_pcode_ops) = limits.max_pcode_ops {
+            if pcodes.len() > max_pcode_ops {
+                bail!("P-code count {} exceeds the limit of {}", pcodes.len(), max_pcode_ops);
+            }
+        }
+        limits.check_deadline("translation")?;
 
         // Phase 7: P-code最適化パス
         let optimizer = Optimizer::new();
         let _opt_stats = optimizer.optimize(&mut pcodes);
+        limits.check_deadline("optimization")?;
 
         // CFG構築
         let mut cfg = ControlFlowGraph::from_pcodes(pcodes.clone());
@@ -269,6 +489,7 @@ impl ParallelDecompiler {
         // SSA変換（基本）
         let mut ssa = SSATransform::new();
         ssa.transform(&mut cfg);
+        limits.check_deadline("SSA construction")?;
 
         // Phase 7: 高度なSSA変換（VariableStack方式）
         // let dom_tree = DominanceTree::compute(&cfg);
@@ -278,10 +499,12 @@ impl ParallelDecompiler {
         // 型推論
         let mut type_inference = TypeInference::new();
         type_inference.run(&pcodes);
+        limits.check_deadline("type inference")?;
 
         // 制御構造検出
         let mut analyzer = ControlFlowAnalyzer::new();
         let structure = analyzer.analyze(&cfg);
+        limits.check_deadline("control flow structuring")?;
 
         // 結果整形
         let mut printer = ControlStructurePrinter::new();
@@ -289,11 +512,13 @@ impl ParallelDecompiler {
 
         let result = CachedFunctionResult {
             address: function_address,
+            function_hash,
             pcode_count: pcodes.len(),
             block_count: cfg.blocks.len(),
             type_count: type_inference.get_all_types().len(),
             loop_count: analyzer.get_loops().len(),
             control_structure: structure_str,
+            type_conflicts: type_inference.get_conflicts().to_vec(),
             cached_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
@@ -324,6 +549,104 @@ impl ParallelDecompiler {
         results.into_iter().collect()
     }
 
+    /// 関数一覧をまとめてデコンパイル
+    ///
+    /// キャッシュ済みの関数は再解析せず、結果は1関数ごとにディスクストアへ書き込む
+    /// `parallel`フィーチャー有効時はrayonで並列に処理する
+    /// 1関数の失敗は全体を止めず、`BatchReport::failures`に記録する
+    pub fn decompile_all(
+        &self,
+        binary_path: Option<&Path>,
+        binary_data: &[u8],
+        functions: &[(u64, usize)], // (VA, file_offset)
+        options: &BatchOptions,
+        progress: &(dyn Fn(&BatchProgress) + Sync),
+    ) -> Result<BatchReport> {
+        let started = Instant::now();
+        let file_hash = self.compute_file_hash(binary_path, binary_data);
+
+        let state = Mutex::new(BatchState {
+            progress: BatchProgress {
+                completed: 0,
+                total: functions.len(),
+                cached: 0,
+                failed: 0,
+                last_address: 0,
+            },
+            decompiled: 0,
+            failures: Vec::new(),
+            store_error: None,
+        });
+
+        let process = |&(address, offset): &(u64, usize)| {
+            let function_hash = Self::compute_function_hash(binary_data, address, offset, options.max_instructions);
+            let is_cached = self.cached_result(&file_hash, address, function_hash).is_some();
+
+            let outcome = (!is_cached).then(|| {
+                let limits = FunctionLimits {
+                    deadline: Some(Instant::now() + options.function_timeout),
+                    max_pcode_ops: Some(options.max_pcode_ops),
+                };
+                self.decompile_function_limited(binary_data, address, offset, options.max_instructions, &limits)
+            });
+
+            // 結果は完了した順にディスクストアへ書き込む
+            let stored = match &outcome {
+                Some(Ok(result)) => self.store_result(&file_hash, result),
+                _ => Ok(()),
+            };
+
+            let Ok(mut state) = state.lock() else {
+                return;
+            };
+            match outcome {
+                None => state.progress.cached += 1,
+                Some(Ok(_)) => state.decompiled += 1,
+                Some(Err(e)) => {
+                    state.progress.failed += 1;
+                    state.failures.push(BatchFailure {
+                        address,
+                        reason: e.to_string(),
+                    });
+                }
+            }
+            if let Err(e) = stored {
+                state.store_error.get_or_insert(e);
+            }
+            state.progress.completed += 1;
+            state.progress.last_address = address;
+
+            progress(&state.progress);
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            functions.par_iter().for_each(process);
+        }
+        #[cfg(not(feature = "parallel"))]
+        functions.iter().for_each(process);
+
+        let state = state
+            .into_inner()
+            .map_err(|_| anyhow::anyhow!("Batch state lock poisoned"))?;
+        if let Some(e) = state.store_error {
+            return Err(e);
+        }
+
+        let mut failures = state.failures;
+        failures.sort_by_key(|failure| failure.address);
+
+        Ok(BatchReport {
+            file_hash,
+            total: functions.len(),
+            decompiled: state.decompiled,
+            cached: state.progress.cached,
+            failures,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
     /// キャッシュ統計情報
     pub fn get_cache_stats(&self) -> CacheStatistics {
         let mem_size = if let Ok(cache) = self.memory_cache.lock() {
@@ -332,13 +655,13 @@ impl ParallelDecompiler {
             0
         };
 
-        let disk_files = std::fs::read_dir(&self.cache_dir)
-            .map(|entries| entries.count())
-            .unwrap_or(0);
+        let disk = self.store.statistics();
 
         CacheStatistics {
             memory_cached_binaries: mem_size,
-            disk_cached_binaries: disk_files,
+            disk_cached_binaries: disk.binaries,
+            disk_cached_functions: disk.records,
+            disk_bytes: disk.bytes,
             cache_directory: self.cache_dir.display().to_string(),
         }
     }
@@ -351,14 +674,7 @@ impl ParallelDecompiler {
         }
 
         // ディスクキャッシュをクリア
-        for entry in std::fs::read_dir(&self.cache_dir)? {
-            let entry = entry?;
-            if entry.path().extension().and_then(|s| s.to_str()) == Some("json") {
-                std::fs::remove_file(entry.path())?;
-            }
-        }
-
-        Ok(())
+        self.store.clear()
     }
 }
 
@@ -367,6 +683,8 @@ impl ParallelDecompiler {
 pub struct CacheStatistics {
     pub memory_cached_binaries: usize,
     pub disk_cached_binaries: usize,
+    pub disk_cached_functions: usize,
+    pub disk_bytes: u64,
     pub cache_directory: String,
 }
 
@@ -402,4 +720,87 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_decompile_all_progress_and_limits() -> Result<()> {
+        let temp_dir = env::temp_dir().join(format!("ghidra_mcp_batch_test_{}", std::process::id()));
+        let decompiler = ParallelDecompiler::with_strategy(&temp_dir, HashStrategy::Full)?;
+
+        // 0x00: mov eax, 1; ret  /  0x10: xor eax, eax; ret
+        let mut binary_data = vec![0xccu8; 0x20];
+        binary_data[..6].copy_from_slice(&[0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3]);
+        binary_data[0x10..0x13].copy_from_slice(&[0x31, 0xc0, 0xc3]);
+        let functions = vec![(0x1000, 0x00), (0x1010, 0x10)];
+
+        let updates = Mutex::new(Vec::new());
+        let record = |progress: &BatchProgress| updates.lock().unwrap().push(progress.completed);
+
+        // P-code数の上限を超える関数は失敗として記録される
+        let strict = BatchOptions { max_pcode_ops: 1, ..BatchOptions::default() };
+        let report = decompiler.decompile_all(None, &binary_data, &functions, &strict, &record)?;
+        assert_eq!(report.decompiled, 0);
+        assert_eq!(report.failures.len(), 2);
+        assert!(report.failures[0].reason.contains("exceeds"));
+
+        let report = decompiler.decompile_all(None, &binary_data, &functions, &BatchOptions::default(), &record)?;
+        assert_eq!(report.decompiled, 2);
+        assert!(report.failures.is_empty());
+
+        // 2回目はキャッシュから返る
+        let report = decompiler.decompile_all(None, &binary_data, &functions, &BatchOptions::default(), &record)?;
+        assert_eq!(report.cached, 2);
+        assert_eq!(report.decompiled, 0);
+
+        let mut completed = updates.into_inner().unwrap();
+        completed.sort_unstable();
+        assert_eq!(completed, vec![1, 1, 1, 2, 2, 2]);
+
+        decompiler.clear_cache()?;
+        std::fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_results_reused_across_builds() -> Result<()> {
+        let temp_dir = env::temp_dir().join(format!("ghidra_mcp_rebuild_test_{}", std::process::id()));
+        let decompiler = ParallelDecompiler::with_strategy(&temp_dir, HashStrategy::Full)?;
+
+        // call rel32; ret
+        let function = |call_disp: i32| {
+            let mut code = vec![0xe8];
+            code.extend_from_slice(&call_disp.to_le_bytes());
+            code.push(0xc3);
+            code
+        };
+
+        // 旧ビルド: 0x1000に関数
+        let mut old_build = vec![0xccu8; 0x40];
+        old_build[..6].copy_from_slice(&function(0x100));
+        let old = decompiler.decompile_function_cached(None, &old_build, 0x1000, 0x00, 100)?;
+        decompiler.annotate_function(
+            &old_build,
+            0x1000,
+            0x00,
+            100,
+            &FunctionAnnotation { name: Some("tick".to_string()), comment: None },
+        )?;
+
+        // 新ビルド: 関数が0x1020に移動し、呼び出し先の変位も変わった
+        let mut new_build = vec![0x90u8; 0x40];
+        new_build[0x20..0x26].copy_from_slice(&function(0x2a0));
+        let (new_hash, _) = ParallelDecompiler::function_body(&new_build, 0x1020, 0x20, 100);
+        assert_eq!(new_hash, old.function_hash);
+
+        let reused = decompiler.decompile_function_cached(None, &new_build, 0x1020, 0x20, 100)?;
+        assert_eq!(reused.address, 0x1020);
+        assert_eq!(reused.cached_at, old.cached_at);
+        assert_eq!(
+            decompiler.function_annotation(&new_build, 0x1020, 0x20, 100).and_then(|a| a.name),
+            Some("tick".to_string())
+        );
+
+        decompiler.clear_cache()?;
+        std::fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
 }
@@ -119,12 +119,25 @@ pub struct Varnode {
     pub space: AddressSpace,
     pub offset: u64,
     pub size: usize,
+    /// SSAバージョン（0 = SSA変換前、または関数入力の値）
+    #[serde(default)]
+    pub version: u32,
 }
 
 impl Varnode {
     /// 新しいVarnodeを作成
     pub fn new(space: AddressSpace, offset: u64, size: usize) -> Self {
-        Self { space, offset, size }
+        Self { space, offset, size, version: 0 }
+    }
+
+    /// 指定したSSAバージョンのVarnodeを作成
+    pub fn with_version(&self, version: u32) -> Self {
+        Self { version, ..self.clone() }
+    }
+
+    /// SSAバージョンを除いた元の変数
+    pub fn base(&self) -> Self {
+        self.with_version(0)
     }
 
     /// レジスタVarnodeを作成
@@ -149,7 +162,7 @@ impl Varnode {
 }
 
 /// P-code命令
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PcodeOp {
     pub opcode: OpCode,
     pub output: Option<Varnode>,
@@ -268,7 +281,11 @@ impl std::fmt::Display for Varnode {
             AddressSpace::Unique => "uniq",
             AddressSpace::Stack => "stack",
         };
-        write!(f, "{}:0x{:x}:{}", space_str, self.offset, self.size)
+        write!(f, "{}:0x{:x}:{}", space_str, self.offset, self.size)?;
+        if self.version > 0 {
+            write!(f, "#{}", self.version)?;
+        }
+        Ok(())
     }
 }
 
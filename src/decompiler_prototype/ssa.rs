@@ -6,15 +6,21 @@ use super::cfg::*;
 use std::collections::{HashMap, HashSet, VecDeque};
 
 /// SSA変換エンジン
+///
+/// 反復支配境界（Iterated Dominance Frontier）のうち、変数が生存している
+/// ブロックにだけPhi-nodeを置く枝刈りSSA（pruned SSA）を構築する。
+/// レジスタだけでなくRam/Stack空間のVarnodeもバージョン付けの対象
 pub struct SSATransform {
     /// 変数の定義カウンタ（各変数の世代番号を追跡）
-    def_counters: HashMap<Varnode, usize>,
+    def_counters: HashMap<Varnode, u32>,
     /// 変数スタック（各変数の現在の世代を保持）
-    var_stacks: HashMap<Varnode, Vec<usize>>,
+    var_stacks: HashMap<Varnode, Vec<u32>>,
     /// 支配木
     dominance_tree: DominanceTree,
     /// 支配境界（Dominance Frontier）
     dominance_frontier: HashMap<BlockId, HashSet<BlockId>>,
+    /// 挿入したPhi-nodeの数
+    phi_count: usize,
 }
 
 /// 支配木構造
@@ -43,20 +49,17 @@ impl DominanceTree {
 
         // Cooper-Harvey-Kennedy アルゴリズムで支配木を計算
         let entry = cfg.entry_block;
-        let blocks: Vec<BlockId> = cfg.blocks.keys().copied().collect();
-
-        // 初期化: エントリブロック以外はすべて未定義
-        let mut idom: HashMap<BlockId, Option<BlockId>> = HashMap::new();
-        for &block_id in &blocks {
-            if block_id == entry {
-                idom.insert(block_id, None);
-            } else {
-                idom.insert(block_id, None);
-            }
+        if !cfg.blocks.contains_key(&entry) {
+            return tree;
         }
 
-        // 逆ポストオーダーでブロックを処理
+        // 逆ポストオーダーでブロックを処理（到達不能ブロックは含まれない）
         let rpo = Self::reverse_postorder(cfg, entry);
+        let rpo_pos: HashMap<BlockId, usize> = rpo.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+
+        // エントリブロックは自分自身を直接支配者とみなす
+        let mut idom: HashMap<BlockId, BlockId> = HashMap::new();
+        idom.insert(entry, entry);
 
         // 収束するまで繰り返し
         let mut changed = true;
@@ -68,41 +71,38 @@ impl DominanceTree {
                     continue;
                 }
 
-                // このブロックの先行ブロックを取得
-                let block = &cfg.blocks[&block_id];
-                let predecessors: Vec<BlockId> = block.predecessors.clone();
-
-                if predecessors.is_empty() {
-                    continue;
-                }
-
                 // 処理済みの先行ブロックから新しい支配者を計算
                 let mut new_idom: Option<BlockId> = None;
-                for &pred in &predecessors {
-                    if idom.get(&pred).and_then(|x| *x).is_some() || pred == entry {
-                        if new_idom.is_none() {
-                            new_idom = Some(pred);
-                        } else {
-                            new_idom = Some(Self::intersect(&idom, new_idom.unwrap(), pred, &rpo));
-                        }
+                for &pred in &cfg.blocks[&block_id].predecessors {
+                    if !idom.contains_key(&pred) {
+                        continue;
                     }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(current) => Self::intersect(&idom, current, pred, &rpo_pos),
+                    });
                 }
 
                 // 支配者が変更されたかチェック
-                if new_idom != idom[&block_id] {
-                    idom.insert(block_id, new_idom);
-                    changed = true;
+                if let Some(new_idom) = new_idom {
+                    if idom.get(&block_id) != Some(&new_idom) {
+                        idom.insert(block_id, new_idom);
+                        changed = true;
+                    }
                 }
             }
         }
 
         // 結果を支配木に格納
-        for (block_id, dom) in idom {
-            if let Some(dominator) = dom {
+        for (block_id, dominator) in idom {
+            if block_id != entry {
                 tree.idom.insert(block_id, dominator);
-                tree.children.entry(dominator).or_insert_with(Vec::new).push(block_id);
+                tree.children.entry(dominator).or_default().push(block_id);
             }
         }
+        for children in tree.children.values_mut() {
+            children.sort_unstable();
+        }
 
         // 支配関係を計算
         tree.compute_dominates(entry);
@@ -112,27 +112,17 @@ impl DominanceTree {
 
     /// 2つのブロックの共通支配者を見つける
     fn intersect(
-        idom: &HashMap<BlockId, Option<BlockId>>,
+        idom: &HashMap<BlockId, BlockId>,
         mut b1: BlockId,
         mut b2: BlockId,
-        rpo: &[BlockId],
+        rpo_pos: &HashMap<BlockId, usize>,
     ) -> BlockId {
-        let rpo_pos: HashMap<BlockId, usize> = rpo.iter().enumerate().map(|(i, &b)| (b, i)).collect();
-
         while b1 != b2 {
-            while rpo_pos.get(&b1).copied().unwrap_or(999) < rpo_pos.get(&b2).copied().unwrap_or(999) {
-                if let Some(Some(next)) = idom.get(&b1) {
-                    b1 = *next;
-                } else {
-                    break;
-                }
+            while rpo_pos[&b1] > rpo_pos[&b2] {
+                b1 = idom[&b1];
             }
-            while rpo_pos.get(&b2).copied().unwrap_or(999) < rpo_pos.get(&b1).copied().unwrap_or(999) {
-                if let Some(Some(next)) = idom.get(&b2) {
-                    b2 = *next;
-                } else {
-                    break;
-                }
+            while rpo_pos[&b2] > rpo_pos[&b1] {
+                b2 = idom[&b2];
             }
         }
 
@@ -201,6 +191,39 @@ impl DominanceTree {
     pub fn immediate_dominator(&self, block: BlockId) -> Option<BlockId> {
         self.idom.get(&block).copied()
     }
+
+    /// 支配境界を計算
+    ///
+    /// 合流点（先行ブロック2個以上）から各先行ブロックの支配者を
+    /// 合流点の直接支配者に到達するまで遡る
+    pub fn frontiers(&self, cfg: &ControlFlowGraph) -> HashMap<BlockId, HashSet<BlockId>> {
+        let mut frontier: HashMap<BlockId, HashSet<BlockId>> = HashMap::new();
+        let reachable = |b: BlockId| b == cfg.entry_block || self.idom.contains_key(&b);
+
+        for (&block_id, block) in &cfg.blocks {
+            if block.predecessors.len() < 2 || !reachable(block_id) {
+                continue;
+            }
+
+            let stop = self.immediate_dominator(block_id);
+            for &pred in &block.predecessors {
+                if !reachable(pred) {
+                    continue;
+                }
+
+                let mut runner = pred;
+                while Some(runner) != stop {
+                    frontier.entry(runner).or_default().insert(block_id);
+                    match self.immediate_dominator(runner) {
+                        Some(next) => runner = next,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        frontier
+    }
 }
 
 impl Default for DominanceTree {
@@ -209,6 +232,27 @@ impl Default for DominanceTree {
     }
 }
 
+/// 反復支配境界 DF+(blocks) を計算
+pub fn iterated_dominance_frontier(
+    frontier: &HashMap<BlockId, HashSet<BlockId>>,
+    blocks: &HashSet<BlockId>,
+) -> HashSet<BlockId> {
+    let mut result = HashSet::new();
+    let mut worklist: VecDeque<BlockId> = blocks.iter().copied().collect();
+
+    while let Some(block_id) = worklist.pop_front() {
+        if let Some(df) = frontier.get(&block_id) {
+            for &df_block in df {
+                if result.insert(df_block) {
+                    worklist.push_back(df_block);
+                }
+            }
+        }
+    }
+
+    result
+}
+
 impl SSATransform {
     /// 新しいSSA変換エンジンを作成
     pub fn new() -> Self {
@@ -217,6 +261,7 @@ impl SSATransform {
             var_stacks: HashMap::new(),
             dominance_tree: DominanceTree::new(),
             dominance_frontier: HashMap::new(),
+            phi_count: 0,
         }
     }
 
@@ -226,7 +271,7 @@ impl SSATransform {
         self.dominance_tree = DominanceTree::compute(cfg);
 
         // 2. 支配境界を計算
-        self.compute_dominance_frontier(cfg);
+        self.dominance_frontier = self.dominance_tree.frontiers(cfg);
 
         // 3. Phi-nodeを挿入
         self.insert_phi_nodes(cfg);
@@ -235,180 +280,165 @@ impl SSATransform {
         self.rename_variables(cfg, cfg.entry_block);
     }
 
-    /// 支配境界を計算
-    fn compute_dominance_frontier(&mut self, cfg: &ControlFlowGraph) {
-        for (&block_id, block) in &cfg.blocks {
-            // 先行ブロックが2個以上ある場合のみ処理
-            if block.predecessors.len() >= 2 {
-                for &pred in &block.predecessors {
-                    let mut runner = pred;
-
-                    // predから支配者まで遡る
-                    loop {
-                        // runnerの支配境界にblock_idを追加
-                        self.dominance_frontier
-                            .entry(runner)
-                            .or_insert_with(HashSet::new)
-                            .insert(block_id);
-
-                        // runnerがblock_idを厳密に支配する場合は終了
-                        if let Some(idom) = self.dominance_tree.immediate_dominator(block_id) {
-                            if runner == idom {
-                                break;
-                            }
-                        }
+    /// 挿入したPhi-nodeの数を取得
+    pub fn phi_count(&self) -> usize {
+        self.phi_count
+    }
 
-                        // 次の支配者に進む
-                        if let Some(next) = self.dominance_tree.immediate_dominator(runner) {
-                            runner = next;
-                        } else {
-                            break;
-                        }
-                    }
-                }
-            }
-        }
+    /// 計算済みの支配木を取得
+    pub fn dominance_tree(&self) -> &DominanceTree {
+        &self.dominance_tree
     }
 
-    /// Phi-nodeを挿入
+    /// Phi-nodeを挿入（枝刈りSSA）
     fn insert_phi_nodes(&mut self, cfg: &mut ControlFlowGraph) {
-        // 各変数について処理
-        let all_vars = self.collect_all_variables(cfg);
+        let live_in = Self::compute_live_in(cfg);
 
-        for var in all_vars {
-            // この変数が定義されているブロックを収集
-            let mut def_blocks = HashSet::new();
-            for (&block_id, block) in &cfg.blocks {
-                for op in &block.ops {
-                    if let Some(ref output) = op.output {
-                        if Self::same_variable(output, &var) {
-                            def_blocks.insert(block_id);
-                        }
+        // 変数ごとに定義ブロックを収集
+        let mut def_blocks: HashMap<Varnode, HashSet<BlockId>> = HashMap::new();
+        for (&block_id, block) in &cfg.blocks {
+            for op in &block.ops {
+                if let Some(ref output) = op.output {
+                    if output.space != AddressSpace::Const {
+                        def_blocks.entry(output.base()).or_default().insert(block_id);
                     }
                 }
             }
+        }
 
-            // Phi-nodeを挿入する場所を計算
-            let mut phi_blocks = HashSet::new();
-            let mut worklist: VecDeque<BlockId> = def_blocks.iter().copied().collect();
-
-            while let Some(block_id) = worklist.pop_front() {
-                // このブロックの支配境界にPhi-nodeを挿入
-                if let Some(frontier) = self.dominance_frontier.get(&block_id) {
-                    for &df_block in frontier {
-                        if !phi_blocks.contains(&df_block) {
-                            // Phi-nodeを挿入
-                            phi_blocks.insert(df_block);
-                            worklist.push_back(df_block);
-
-                            // 実際のPhi-node命令を追加
-                            if let Some(block) = cfg.blocks.get_mut(&df_block) {
-                                let num_preds = block.predecessors.len();
-                                let phi_inputs = vec![var.clone(); num_preds];
-
-                                let phi_op = PcodeOp::new(
-                                    OpCode::MultiEqual,
-                                    Some(var.clone()),
-                                    phi_inputs,
-                                    block.start_address,
-                                );
-
-                                // Phi-nodeはブロックの先頭に挿入
-                                block.ops.insert(0, phi_op);
-                            }
-                        }
-                    }
+        let mut vars: Vec<Varnode> = def_blocks.keys().cloned().collect();
+        vars.sort_by_key(|v| (v.space as u8, v.offset, v.size));
+
+        for var in vars {
+            // 反復支配境界のうち、変数が生存しているブロックにだけ置く
+            let mut phi_blocks: Vec<BlockId> = iterated_dominance_frontier(&self.dominance_frontier, &def_blocks[&var])
+                .into_iter()
+                .filter(|b| live_in.get(b).map(|live| live.contains(&var)).unwrap_or(false))
+                .collect();
+            phi_blocks.sort_unstable();
+
+            for df_block in phi_blocks {
+                if let Some(block) = cfg.blocks.get_mut(&df_block) {
+                    let num_preds = block.predecessors.len();
+                    let phi_inputs = vec![var.clone(); num_preds];
+
+                    let phi_op = PcodeOp::new(
+                        OpCode::MultiEqual,
+                        Some(var.clone()),
+                        phi_inputs,
+                        block.start_address,
+                    );
+
+                    // Phi-nodeはブロック先頭（既存のPhi-nodeの後ろ）に挿入
+                    let pos = block.ops.iter().take_while(|op| op.opcode == OpCode::MultiEqual).count();
+                    block.ops.insert(pos, phi_op);
+                    self.phi_count += 1;
                 }
             }
         }
     }
 
-    /// すべての変数を収集
-    fn collect_all_variables(&self, cfg: &ControlFlowGraph) -> Vec<Varnode> {
-        let mut vars = HashSet::new();
-
-        for block in cfg.blocks.values() {
+    /// 各ブロック先頭で生存している変数を計算（SSA変換前のVarnode単位）
+    fn compute_live_in(cfg: &ControlFlowGraph) -> HashMap<BlockId, HashSet<Varnode>> {
+        // ブロック内で定義より先に使われる変数（upward exposed）と定義される変数
+        let mut uses: HashMap<BlockId, HashSet<Varnode>> = HashMap::new();
+        let mut defs: HashMap<BlockId, HashSet<Varnode>> = HashMap::new();
+        for (&block_id, block) in &cfg.blocks {
+            let block_uses = uses.entry(block_id).or_default();
+            let block_defs = defs.entry(block_id).or_default();
             for op in &block.ops {
-                // 出力変数
+                for input in &op.inputs {
+                    if input.space != AddressSpace::Const && !block_defs.contains(&input.base()) {
+                        block_uses.insert(input.base());
+                    }
+                }
                 if let Some(ref output) = op.output {
-                    vars.insert(output.clone());
+                    block_defs.insert(output.base());
                 }
+            }
+        }
 
-                // 入力変数
-                for input in &op.inputs {
-                    // 定数は除外
-                    if input.space != AddressSpace::Const {
-                        vars.insert(input.clone());
+        let mut live_in: HashMap<BlockId, HashSet<Varnode>> = uses.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (&block_id, block) in &cfg.blocks {
+                let mut live_out = HashSet::new();
+                for succ in &block.successors {
+                    if let Some(succ_live) = live_in.get(succ) {
+                        live_out.extend(succ_live.iter().cloned());
+                    }
+                }
+
+                let entry = live_in.get_mut(&block_id).unwrap();
+                for var in live_out {
+                    if !defs[&block_id].contains(&var) && entry.insert(var) {
+                        changed = true;
                     }
                 }
             }
         }
 
-        vars.into_iter().collect()
+        live_in
     }
 
     /// 変数の名前を付け直す（SSA形式）
+    ///
+    /// 支配木を前順に辿り、定義ごとに新しいバージョンを割り当てる。
+    /// 後続ブロックのPhi-nodeには、このブロックに対応する先行ブロック位置の
+    /// 入力だけを現在のバージョンで埋める
     fn rename_variables(&mut self, cfg: &mut ControlFlowGraph, block_id: BlockId) {
-        let block = match cfg.blocks.get(&block_id) {
+        let mut pushed: Vec<Varnode> = Vec::new();
+
+        let block = match cfg.blocks.get_mut(&block_id) {
             Some(b) => b,
             None => return,
         };
 
         // このブロックの命令を処理
-        let ops_len = block.ops.len();
-        for i in 0..ops_len {
-            let block = cfg.blocks.get_mut(&block_id).unwrap();
-            let op = &mut block.ops[i];
-
-            // 入力変数の名前を変更
-            for input in &mut op.inputs {
-                if input.space != AddressSpace::Const {
-                    if let Some(stack) = self.var_stacks.get(input) {
-                        if let Some(&version) = stack.last() {
-                            // 変数名にバージョンを追加
-                            input.offset = (input.offset & 0xFFFFFFFF) | ((version as u64) << 32);
-                        }
+        for op in &mut block.ops {
+            // 入力変数の名前を変更（Phi-nodeの入力は先行ブロック側で埋める）
+            if op.opcode != OpCode::MultiEqual {
+                for input in &mut op.inputs {
+                    if input.space != AddressSpace::Const {
+                        input.version = self.current_version(input);
                     }
                 }
             }
 
-            // 出力変数の名前を変更
+            // 出力変数に新しいバージョン番号を割り当て
             if let Some(ref mut output) = op.output {
                 if output.space != AddressSpace::Const {
-                    // 新しいバージョン番号を割り当て
-                    let counter = self.def_counters.entry(output.clone()).or_insert(0);
+                    let base = output.base();
+                    let counter = self.def_counters.entry(base.clone()).or_insert(0);
                     *counter += 1;
-                    let version = *counter;
-
-                    // スタックにプッシュ
-                    self.var_stacks
-                        .entry(output.clone())
-                        .or_insert_with(Vec::new)
-                        .push(version);
+                    output.version = *counter;
 
-                    // 変数名にバージョンを追加
-                    output.offset = (output.offset & 0xFFFFFFFF) | ((version as u64) << 32);
+                    self.var_stacks.entry(base.clone()).or_default().push(*counter);
+                    pushed.push(base);
                 }
             }
         }
 
         // 後続ブロックのPhi-nodeパラメータを更新
         let successors: Vec<BlockId> = cfg.blocks[&block_id].successors.clone();
-        for &succ in &successors {
-            let succ_block = cfg.blocks.get_mut(&succ).unwrap();
-
-            for op in &mut succ_block.ops {
-                if op.opcode == OpCode::MultiEqual {
-                    // Phi-nodeの対応する入力を更新
-                    // （この実装は簡略化されています）
-                    for input in &mut op.inputs {
-                        if input.space != AddressSpace::Const {
-                            if let Some(stack) = self.var_stacks.get(input) {
-                                if let Some(&version) = stack.last() {
-                                    input.offset = (input.offset & 0xFFFFFFFF) | ((version as u64) << 32);
-                                }
-                            }
-                        }
+        for succ in successors {
+            let succ_block = match cfg.blocks.get_mut(&succ) {
+                Some(b) => b,
+                None => continue,
+            };
+            let pred_indices: Vec<usize> = succ_block.predecessors
+                .iter()
+                .enumerate()
+                .filter(|(_, &p)| p == block_id)
+                .map(|(i, _)| i)
+                .collect();
+
+            for op in succ_block.ops.iter_mut().filter(|op| op.opcode == OpCode::MultiEqual) {
+                for &i in &pred_indices {
+                    if let Some(input) = op.inputs.get_mut(i) {
+                        input.version = self.current_version(input);
                     }
                 }
             }
@@ -422,12 +452,20 @@ impl SSATransform {
             }
         }
 
-        // スタックから変数をポップ（この実装では省略）
+        // このブロックで積んだバージョンをポップ
+        for base in pushed {
+            if let Some(stack) = self.var_stacks.get_mut(&base) {
+                stack.pop();
+            }
+        }
     }
 
-    /// 2つの変数が同じか判定（オフセットとサイズが同じ）
-    fn same_variable(v1: &Varnode, v2: &Varnode) -> bool {
-        v1.space == v2.space && (v1.offset & 0xFFFFFFFF) == (v2.offset & 0xFFFFFFFF) && v1.size == v2.size
+    /// 変数の現在のバージョン（未定義なら関数入力として0）
+    fn current_version(&self, vn: &Varnode) -> u32 {
+        self.var_stacks
+            .get(&vn.base())
+            .and_then(|stack| stack.last().copied())
+            .unwrap_or(0)
     }
 }
 
@@ -603,6 +641,79 @@ mod tests {
         println!("Dominance tree test passed!");
     }
 
+    /// Block 0 -> Block 1, Block 2 -> Block 3 のダイヤモンド型CFG
+    fn diamond_cfg() -> ControlFlowGraph {
+        let mut cfg = ControlFlowGraph::new();
+        cfg.entry_block = 0;
+        for (id, addr) in [(0, 0x1000), (1, 0x1010), (2, 0x1020), (3, 0x1030)] {
+            cfg.blocks.insert(id, BasicBlock::new(id, addr));
+        }
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            cfg.blocks.get_mut(&from).unwrap().successors.push(to);
+            cfg.blocks.get_mut(&to).unwrap().predecessors.push(from);
+        }
+        cfg.next_block_id = 4;
+        cfg
+    }
+
+    #[test]
+    fn test_pruned_phi_placement() {
+        let mut cfg = diamond_cfg();
+        let x = Varnode::register(0, 8);
+        let tmp = Varnode::unique(0x10000, 8);
+
+        // 両方の分岐で x と tmp を定義し、合流後は x だけを使う
+        for (id, value) in [(1, 1), (2, 2)] {
+            let block = cfg.blocks.get_mut(&id).unwrap();
+            block.ops.push(PcodeOp::unary(OpCode::Copy, tmp.clone(), Varnode::constant(value, 8), 0));
+            block.ops.push(PcodeOp::unary(OpCode::Copy, x.clone(), tmp.clone(), 0));
+        }
+        cfg.blocks.get_mut(&3).unwrap().ops.push(PcodeOp::no_output(OpCode::Return, vec![x.clone()], 0x1030));
+
+        let mut ssa = SSATransform::new();
+        ssa.transform(&mut cfg);
+
+        // tmp は合流点で生存していないのでPhi-nodeは x の1つだけ
+        assert_eq!(ssa.phi_count(), 1);
+        let merge = &cfg.blocks[&3];
+        let phi = &merge.ops[0];
+        assert_eq!(phi.opcode, OpCode::MultiEqual);
+        assert_eq!(phi.output.as_ref().unwrap().base(), x);
+
+        // 入力は先行ブロックの順番（Block 1, Block 2）に対応する
+        let def_version = |id: BlockId| cfg.blocks[&id].ops[1].output.as_ref().unwrap().version;
+        assert_eq!(phi.inputs[0].version, def_version(1));
+        assert_eq!(phi.inputs[1].version, def_version(2));
+        assert_ne!(phi.inputs[0].version, phi.inputs[1].version);
+
+        // returnはPhi-nodeの結果を参照する
+        assert_eq!(merge.ops[1].inputs[0], *phi.output.as_ref().unwrap());
+    }
+
+    #[test]
+    fn test_memory_versioning() {
+        let mut cfg = diamond_cfg();
+        let global = Varnode::ram(0x140001000, 4);
+
+        for (id, value) in [(1, 1), (2, 2)] {
+            cfg.blocks.get_mut(&id).unwrap().ops.push(
+                PcodeOp::unary(OpCode::Copy, global.clone(), Varnode::constant(value, 4), 0),
+            );
+        }
+        cfg.blocks.get_mut(&3).unwrap().ops.push(PcodeOp::no_output(OpCode::Return, vec![global.clone()], 0x1030));
+
+        let mut ssa = SSATransform::new();
+        ssa.transform(&mut cfg);
+
+        assert_eq!(ssa.phi_count(), 1);
+        let v1 = cfg.blocks[&1].ops[0].output.clone().unwrap();
+        let v2 = cfg.blocks[&2].ops[0].output.clone().unwrap();
+        assert_ne!(v1.version, v2.version);
+        // バージョン付けでアドレスが壊れない
+        assert_eq!(v1.offset, 0x140001000);
+        assert_eq!(v2.base(), global);
+    }
+
     #[test]
     fn test_dataflow() {
         let mut cfg = ControlFlowGraph::new();
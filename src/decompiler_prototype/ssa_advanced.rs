@@ -83,6 +83,8 @@ pub struct SSARenameContext {
     input_counter: u64,
     /// 一時変数カウンタ
     unique_counter: u64,
+    /// アドレスごとのSSAバージョンカウンタ
+    version_counters: HashMap<VarnodeAddress, u32>,
 }
 
 impl SSARenameContext {
@@ -91,9 +93,17 @@ impl SSARenameContext {
             varstack: VariableStack::new(),
             input_counter: 0,
             unique_counter: 10000, // 一時変数は10000番から開始
+            version_counters: HashMap::new(),
         }
     }
 
+    /// 書き込みに新しいSSAバージョンを割り当てたVarnodeを作成
+    pub fn create_version(&mut self, vn: &Varnode) -> Varnode {
+        let counter = self.version_counters.entry(VarnodeAddress::from(vn)).or_insert(0);
+        *counter += 1;
+        vn.with_version(*counter)
+    }
+
     /// 新しいinput Varnodeを作成
     pub fn create_input_varnode(&mut self, addr: &VarnodeAddress, size: usize) -> Varnode {
         let vn = Varnode::new(addr.space, addr.offset, size);
@@ -135,8 +145,9 @@ impl AdvancedSSATransform {
     /// 1. ブロック内の各P-code操作を実行順に処理
     /// 2. 読み取り（入力）: スタックトップのVarnodeで置き換え
     /// 3. 書き込み（出力）: スタックにプッシュ
-    /// 4. 支配子ブロックを再帰処理
-    /// 5. このブロックの書き込みをポップして状態を復元
+    /// 4. CFG後続ブロックのPhi-node入力（このブロックに対応する位置）を更新
+    /// 5. 支配子ブロックを再帰処理
+    /// 6. このブロックの書き込みをポップして状態を復元
     pub fn rename_recurse(
         &mut self,
         block_id: BlockId,
@@ -175,38 +186,42 @@ impl AdvancedSSATransform {
                 }
             }
 
-            // 書き込み（出力）: スタックにプッシュ
-            if let Some(output) = &op.output {
+            // 書き込み（出力）: 新しいバージョンを割り当ててスタックにプッシュ
+            if let Some(output) = &mut op.output {
                 if self.should_rename(output) {
-                    let addr = VarnodeAddress::from(output);
+                    let addr = VarnodeAddress::from(&*output);
+                    *output = self.rename_context.create_version(output);
                     self.rename_context.varstack.push(output.clone());
                     write_list.push(addr);
                 }
             }
         }
 
-        // 支配子ブロックのMultiEqual（Phi-node）の入力を更新
-        if let Some(children) = dom_tree.get_children(block_id) {
-            for &child_id in children {
-                if let Some(child_block) = cfg.blocks.get_mut(&child_id) {
-                    for child_op in &mut child_block.ops {
-                        if child_op.opcode == OpCode::MultiEqual {
-                            // このブロックから来るエッジに対応する入力を更新
-                            // （実際には前駆ブロックのインデックスに基づく）
-                            for input in &mut child_op.inputs {
-                                if self.should_rename(input) {
-                                    let addr = VarnodeAddress::from(&*input);
-                                    if let Some(new_vn) = self.rename_context.varstack.top(&addr) {
-                                        *input = new_vn.clone();
-                                    } else {
-                                        let new_input = self
-                                            .rename_context
-                                            .create_input_varnode(&addr, input.size);
-                                        self.rename_context.varstack.push(new_input.clone());
-                                        *input = new_input;
-                                    }
-                                }
-                            }
+        // CFG後続ブロックのMultiEqual（Phi-node）のうち、このブロックから来るエッジに対応する入力を更新
+        let successors = cfg.blocks[&block_id].successors.clone();
+        for succ_id in successors {
+            if let Some(succ_block) = cfg.blocks.get_mut(&succ_id) {
+                let pred_indices: Vec<usize> = succ_block.predecessors
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &p)| p == block_id)
+                    .map(|(i, _)| i)
+                    .collect();
+
+                for succ_op in &mut succ_block.ops {
+                    if succ_op.opcode != OpCode::MultiEqual {
+                        continue;
+                    }
+                    for &i in &pred_indices {
+                        let input = match succ_op.inputs.get_mut(i) {
+                            Some(input) if self.should_rename(input) => input,
+                            _ => continue,
+                        };
+                        let addr = VarnodeAddress::from(&*input);
+                        if let Some(new_vn) = self.rename_context.varstack.top(&addr) {
+                            *input = new_vn.clone();
+                        } else {
+                            *input = self.rename_context.create_input_varnode(&addr, input.size);
                         }
                     }
                 }
@@ -2,10 +2,11 @@
 /// P-code命令から変数の型を推論し、C言語風の型情報を生成する
 
 use super::pcode::*;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 /// 推論される型
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Type {
     /// 未知の型
     Unknown,
@@ -23,10 +24,12 @@ pub enum Type {
     Struct(Vec<(String, Type)>),
     /// 関数型 (引数型リスト, 戻り値型)
     Function(Vec<Type>, Box<Type>),
+    /// 共用体型（互換性のない型が同じVarnodeに要求された場合の結び）
+    Union(Vec<Type>),
 }
 
 /// 整数型の種類
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum IntType {
     /// 符号付き8ビット
     I8,
@@ -47,7 +50,7 @@ pub enum IntType {
 }
 
 /// 浮動小数点型の種類
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FloatType {
     /// 32ビット浮動小数点
     F32,
@@ -101,6 +104,9 @@ impl Type {
                 fields.iter().map(|(_, ty)| ty.size()).sum()
             }
             Type::Function(_, _) => 8, // 関数ポインタ
+            Type::Union(members) => {
+                members.iter().map(|ty| ty.size()).max().unwrap_or(0)
+            }
         }
     }
 
@@ -136,6 +142,14 @@ impl Type {
                 let arg_strs: Vec<String> = args.iter().map(|t| t.to_c_string()).collect();
                 format!("{} (*)({})", ret.to_c_string(), arg_strs.join(", "))
             }
+            Type::Union(members) => {
+                let member_strs: Vec<String> = members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| format!("{} u{}", ty.to_c_string(), i))
+                    .collect();
+                format!("union {{ {} }}", member_strs.join("; "))
+            }
         }
     }
 
@@ -148,9 +162,76 @@ impl Type {
             (Type::Float(_), Type::Float(_)) => true,
             (Type::Pointer(a), Type::Pointer(b)) => a.is_compatible_with(b),
             (Type::Array(a, _), Type::Array(b, _)) => a.is_compatible_with(b),
+            (Type::Union(members), other) | (other, Type::Union(members)) => {
+                members.iter().any(|m| m.is_compatible_with(other))
+            }
             _ => self == other,
         }
     }
+
+    /// 型束の結び（join）を計算
+    ///
+    /// 互換性のある型同士はより一般的な型に、互換性のない型は共用体にまとめる
+    pub fn join(&self, other: &Type) -> Type {
+        match (self, other) {
+            (Type::Unknown, t) | (t, Type::Unknown) => t.clone(),
+            _ if self == other => self.clone(),
+            (Type::Int(a), Type::Int(b)) => {
+                // 大きい方のサイズ、符号が食い違えば符号なし
+                let size = std::cmp::max(self.size(), other.size());
+                Type::int_from_size(size, a.is_signed() && b.is_signed())
+            }
+            (Type::Float(_), Type::Float(_)) => {
+                Type::float_from_size(std::cmp::max(self.size(), other.size()))
+            }
+            (Type::Pointer(a), Type::Pointer(b)) => {
+                if a.is_compatible_with(b) {
+                    Type::Pointer(Box::new(a.join(b)))
+                } else {
+                    // 指す先が食い違うポインタは void* に丸める
+                    Type::Pointer(Box::new(Type::Void))
+                }
+            }
+            (Type::Pointer(_), Type::Int(_)) if self.size() == other.size() => self.clone(),
+            (Type::Int(_), Type::Pointer(_)) if self.size() == other.size() => other.clone(),
+            (Type::Array(a, n), Type::Array(b, m)) if a.is_compatible_with(b) => {
+                Type::Array(Box::new(a.join(b)), std::cmp::max(*n, *m))
+            }
+            (Type::Union(members), t) | (t, Type::Union(members)) => {
+                Self::union_with(members.clone(), t)
+            }
+            _ => Self::union_with(vec![self.clone()], other),
+        }
+    }
+
+    /// 共用体のメンバーに型を追加（互換性のあるメンバーとは結びを取る）
+    fn union_with(mut members: Vec<Type>, ty: &Type) -> Type {
+        let incoming = match ty {
+            Type::Union(others) => others.clone(),
+            _ => vec![ty.clone()],
+        };
+
+        for t in incoming {
+            if let Some(member) = members.iter_mut().find(|m| m.is_compatible_with(&t)) {
+                *member = member.join(&t);
+            } else {
+                members.push(t);
+            }
+        }
+
+        if members.len() == 1 {
+            members.pop().unwrap()
+        } else {
+            Type::Union(members)
+        }
+    }
+}
+
+impl IntType {
+    /// 符号付き整数型か
+    pub fn is_signed(&self) -> bool {
+        matches!(self, IntType::I8 | IntType::I16 | IntType::I32 | IntType::I64)
+    }
 }
 
 /// 型制約
@@ -162,6 +243,39 @@ pub struct TypeConstraint {
     pub type_: Type,
     /// 制約の理由（デバッグ用）
     pub reason: String,
+    /// 制約を生んだ命令のアドレス
+    pub address: u64,
+    /// 制約を生んだ命令の種類
+    pub opcode: OpCode,
+}
+
+/// 型衝突の発生元（どの命令がどの型を要求したか）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictOrigin {
+    /// 命令のアドレス
+    pub address: u64,
+    /// 命令の種類
+    pub opcode: OpCode,
+    /// 要求された型
+    pub type_: Type,
+    /// 制約の理由
+    pub reason: String,
+}
+
+/// 型衝突の診断情報
+///
+/// 同じVarnodeに互換性のない型が要求された箇所を記録する。
+/// デコンパイラが型を「推測」した場所を解析者が確認するために使う
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeConflict {
+    /// 衝突したVarnode
+    pub varnode: Varnode,
+    /// 競合した型（出現順、重複なし）
+    pub competing_types: Vec<Type>,
+    /// 各型を要求した命令
+    pub origins: Vec<ConflictOrigin>,
+    /// 型束の結びで決定した型
+    pub resolved: Type,
 }
 
 /// 型推論エンジン
@@ -172,6 +286,12 @@ pub struct TypeInference {
     inferred_types: HashMap<Varnode, Type>,
     /// 型の候補（複数の制約がある場合）
     type_candidates: HashMap<Varnode, Vec<Type>>,
+    /// Copy命令による型伝播の辺 (出力, 入力, アドレス)
+    copy_edges: Vec<(Varnode, Varnode, u64)>,
+    /// Varnodeごとに適用済みの制約インデックス
+    applied: HashMap<Varnode, Vec<usize>>,
+    /// 検出された型衝突
+    conflicts: Vec<TypeConflict>,
 }
 
 impl TypeInference {
@@ -180,6 +300,9 @@ impl TypeInference {
             constraints: Vec::new(),
             inferred_types: HashMap::new(),
             type_candidates: HashMap::new(),
+            copy_edges: Vec::new(),
+            applied: HashMap::new(),
+            conflicts: Vec::new(),
         }
     }
 
@@ -200,6 +323,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::int_from_size(output.size, true),
+                        op,
                         format!("整数演算 {:?} の出力", op.opcode),
                     );
                 }
@@ -207,6 +331,7 @@ impl TypeInference {
                     self.add_constraint(
                         input.clone(),
                         Type::int_from_size(input.size, true),
+                        op,
                         format!("整数演算 {:?} の入力", op.opcode),
                     );
                 }
@@ -218,6 +343,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::float_from_size(output.size),
+                        op,
                         format!("浮動小数点演算 {:?} の出力", op.opcode),
                     );
                 }
@@ -225,6 +351,7 @@ impl TypeInference {
                     self.add_constraint(
                         input.clone(),
                         Type::float_from_size(input.size),
+                        op,
                         format!("浮動小数点演算 {:?} の入力", op.opcode),
                     );
                 }
@@ -238,6 +365,7 @@ impl TypeInference {
                         self.add_constraint(
                             ptr.clone(),
                             Type::Pointer(Box::new(Type::int_from_size(output.size, true))),
+                            op,
                             "Load命令のアドレス引数".to_string(),
                         );
                     }
@@ -252,23 +380,25 @@ impl TypeInference {
                     self.add_constraint(
                         ptr.clone(),
                         Type::Pointer(Box::new(Type::int_from_size(value.size, true))),
+                        op,
                         "Store命令のアドレス引数".to_string(),
                     );
                 }
             }
 
-            // コピー → 型を伝播
+            // コピー → 型を伝播（入力の型は伝播フェーズで確定する）
             OpCode::Copy => {
                 if let Some(ref output) = op.output {
                     if !op.inputs.is_empty() {
                         let input = &op.inputs[0];
-                        // 入力と出力の型は同じ
-                        if let Some(input_type) = self.inferred_types.get(input).cloned() {
-                            self.add_constraint(
-                                output.clone(),
-                                input_type,
-                                "Copy命令による型伝播".to_string(),
-                            );
+                        self.add_constraint(
+                            output.clone(),
+                            Type::Unknown,
+                            op,
+                            "Copy命令の出力".to_string(),
+                        );
+                        if input.space != AddressSpace::Const {
+                            self.copy_edges.push((output.clone(), input.clone(), op.address));
                         }
                     }
                 }
@@ -281,6 +411,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::Int(IntType::I8), // bool として 1バイト
+                        op,
                         format!("比較演算 {:?} の出力", op.opcode),
                     );
                 }
@@ -288,6 +419,7 @@ impl TypeInference {
                     self.add_constraint(
                         input.clone(),
                         Type::int_from_size(input.size, true),
+                        op,
                         format!("比較演算 {:?} の入力", op.opcode),
                     );
                 }
@@ -300,6 +432,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::int_from_size(output.size, false), // 符号なしとして扱う
+                        op,
                         format!("ビット演算 {:?} の出力", op.opcode),
                     );
                 }
@@ -311,6 +444,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::int_from_size(output.size, true),
+                        op,
                         "符号拡張の出力".to_string(),
                     );
                 }
@@ -322,6 +456,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::int_from_size(output.size, false),
+                        op,
                         "ゼロ拡張の出力".to_string(),
                     );
                 }
@@ -334,6 +469,7 @@ impl TypeInference {
                     self.add_constraint(
                         output.clone(),
                         Type::int_from_size(output.size, true),
+                        op,
                         "関数呼び出しの戻り値".to_string(),
                     );
                 }
@@ -344,7 +480,7 @@ impl TypeInference {
     }
 
     /// 型制約を追加
-    fn add_constraint(&mut self, varnode: Varnode, type_: Type, reason: String) {
+    fn add_constraint(&mut self, varnode: Varnode, type_: Type, op: &PcodeOp, reason: String) {
         // 定数は型推論しない
         if varnode.space == AddressSpace::Const {
             return;
@@ -354,6 +490,8 @@ impl TypeInference {
             varnode: varnode.clone(),
             type_: type_.clone(),
             reason,
+            address: op.address,
+            opcode: op.opcode,
         });
 
         // 候補リストに追加
@@ -364,26 +502,116 @@ impl TypeInference {
     }
 
     /// 型を伝播させる
+    ///
+    /// 同じVarnodeへの制約は型束の結びで統合し、互換性のない組み合わせは
+    /// 型衝突として記録する
     pub fn propagate_types(&mut self) {
         // 制約から型を決定
-        for constraint in &self.constraints {
-            let varnode = &constraint.varnode;
-            let type_ = &constraint.type_;
-
-            // 既存の型と互換性をチェック
-            if let Some(existing_type) = self.inferred_types.get(varnode) {
-                if !existing_type.is_compatible_with(type_) {
-                    // 互換性がない場合は警告（今は無視）
+        for idx in 0..self.constraints.len() {
+            self.apply_constraint(idx);
+        }
+
+        // Copy命令に沿って型を伝播（不動点まで）
+        let max_rounds = self.copy_edges.len() + 1;
+        for _ in 0..max_rounds {
+            let mut changed = false;
+
+            for i in 0..self.copy_edges.len() {
+                let (output, input, address) = self.copy_edges[i].clone();
+                let input_type = match self.inferred_types.get(&input) {
+                    Some(t) if !matches!(t, Type::Unknown) => t.clone(),
+                    _ => continue,
+                };
+                let current = self.inferred_types.get(&output).cloned().unwrap_or(Type::Unknown);
+                if current.join(&input_type) == current {
                     continue;
                 }
-                // より具体的な型を選択
-                if matches!(existing_type, Type::Unknown) {
-                    self.inferred_types.insert(varnode.clone(), type_.clone());
+
+                self.constraints.push(TypeConstraint {
+                    varnode: output,
+                    type_: input_type,
+                    reason: "Copy命令による型伝播".to_string(),
+                    address,
+                    opcode: OpCode::Copy,
+                });
+                self.apply_constraint(self.constraints.len() - 1);
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // 衝突の最終的な解決型を反映
+        for conflict in &mut self.conflicts {
+            if let Some(ty) = self.inferred_types.get(&conflict.varnode) {
+                conflict.resolved = ty.clone();
+            }
+        }
+    }
+
+    /// 1つの制約を推論結果に適用
+    fn apply_constraint(&mut self, idx: usize) {
+        let varnode = self.constraints[idx].varnode.clone();
+        let type_ = self.constraints[idx].type_.clone();
+
+        let joined = match self.inferred_types.get(&varnode).cloned() {
+            Some(existing) => {
+                if !existing.is_compatible_with(&type_) {
+                    self.record_conflict(&varnode, idx);
+                } else if let Some(conflict) = self.conflicts.iter_mut().find(|c| c.varnode == varnode) {
+                    // 既に衝突しているVarnodeへの追加要求も発生元として残す
+                    Self::push_origin(conflict, &self.constraints[idx]);
                 }
-            } else {
-                self.inferred_types.insert(varnode.clone(), type_.clone());
+                existing.join(&type_)
             }
+            None => type_,
+        };
+
+        self.inferred_types.insert(varnode.clone(), joined);
+        self.applied.entry(varnode).or_default().push(idx);
+    }
+
+    /// 型衝突を記録
+    fn record_conflict(&mut self, varnode: &Varnode, idx: usize) {
+        let pos = match self.conflicts.iter().position(|c| &c.varnode == varnode) {
+            Some(pos) => pos,
+            None => {
+                // 初回の衝突: それまでにこのVarnodeへ適用された制約も発生元に含める
+                let mut conflict = TypeConflict {
+                    varnode: varnode.clone(),
+                    competing_types: Vec::new(),
+                    origins: Vec::new(),
+                    resolved: Type::Unknown,
+                };
+                if let Some(prior) = self.applied.get(varnode) {
+                    for &i in prior {
+                        Self::push_origin(&mut conflict, &self.constraints[i]);
+                    }
+                }
+                self.conflicts.push(conflict);
+                self.conflicts.len() - 1
+            }
+        };
+
+        Self::push_origin(&mut self.conflicts[pos], &self.constraints[idx]);
+    }
+
+    /// 衝突に発生元を追加
+    fn push_origin(conflict: &mut TypeConflict, constraint: &TypeConstraint) {
+        if matches!(constraint.type_, Type::Unknown) {
+            return;
+        }
+        if !conflict.competing_types.contains(&constraint.type_) {
+            conflict.competing_types.push(constraint.type_.clone());
         }
+        conflict.origins.push(ConflictOrigin {
+            address: constraint.address,
+            opcode: constraint.opcode,
+            type_: constraint.type_.clone(),
+            reason: constraint.reason.clone(),
+        });
     }
 
     /// 型制約を解決
@@ -454,6 +682,11 @@ impl TypeInference {
         &self.inferred_types
     }
 
+    /// 検出された型衝突を取得
+    pub fn get_conflicts(&self) -> &[TypeConflict] {
+        &self.conflicts
+    }
+
     /// 型推論を実行（収集→伝播→解決）
     pub fn run(&mut self, ops: &[PcodeOp]) {
         self.infer_from_pcode(ops);
@@ -471,8 +704,8 @@ mod tests {
         let mut inference = TypeInference::new();
 
         // mov rax, rbx (8バイトコピー)
-        let rax = Varnode { space: AddressSpace::Register, offset: 0, size: 8 };
-        let rbx = Varnode { space: AddressSpace::Register, offset: 24, size: 8 };
+        let rax = Varnode::new(AddressSpace::Register, 0, 8);
+        let rbx = Varnode::new(AddressSpace::Register, 24, 8);
 
         let op = PcodeOp {
             opcode: OpCode::Copy,
@@ -491,8 +724,8 @@ mod tests {
     fn test_float_type_inference() {
         let mut inference = TypeInference::new();
 
-        let xmm0 = Varnode { space: AddressSpace::Register, offset: 200, size: 8 };
-        let xmm1 = Varnode { space: AddressSpace::Register, offset: 208, size: 8 };
+        let xmm0 = Varnode::new(AddressSpace::Register, 200, 8);
+        let xmm1 = Varnode::new(AddressSpace::Register, 208, 8);
 
         let op = PcodeOp {
             opcode: OpCode::FloatAdd,
@@ -512,9 +745,9 @@ mod tests {
     fn test_pointer_type_inference() {
         let mut inference = TypeInference::new();
 
-        let rax = Varnode { space: AddressSpace::Register, offset: 0, size: 8 };
-        let value = Varnode { space: AddressSpace::Register, offset: 8, size: 4 };
-        let space_id = Varnode { space: AddressSpace::Const, offset: 0, size: 8 };
+        let rax = Varnode::new(AddressSpace::Register, 0, 8);
+        let value = Varnode::new(AddressSpace::Register, 8, 4);
+        let space_id = Varnode::new(AddressSpace::Const, 0, 8);
 
         // *rax = value (4バイトストア)
         let op = PcodeOp {
@@ -531,6 +764,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_type_conflict_reporting() {
+        let mut inference = TypeInference::new();
+
+        let rax = Varnode::new(AddressSpace::Register, 0, 8);
+        let rbx = Varnode::new(AddressSpace::Register, 24, 8);
+        let xmm0 = Varnode::new(AddressSpace::Register, 200, 8);
+
+        // rax = rax + rbx（整数）の後に rax = rax f+ xmm0（浮動小数点）
+        let ops = vec![
+            PcodeOp::binary(OpCode::IntAdd, rax.clone(), rax.clone(), rbx, 0x1000),
+            PcodeOp::binary(OpCode::FloatAdd, rax.clone(), rax.clone(), xmm0, 0x1004),
+        ];
+
+        inference.run(&ops);
+
+        let conflicts = inference.get_conflicts();
+        assert_eq!(conflicts.len(), 1);
+
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.varnode, rax);
+        assert!(conflict.competing_types.contains(&Type::Int(IntType::I64)));
+        assert!(conflict.competing_types.contains(&Type::Float(FloatType::F64)));
+        assert!(conflict.origins.iter().any(|o| o.address == 0x1000 && o.opcode == OpCode::IntAdd));
+        assert!(conflict.origins.iter().any(|o| o.address == 0x1004 && o.opcode == OpCode::FloatAdd));
+        assert!(matches!(conflict.resolved, Type::Union(_)));
+        assert_eq!(inference.get_type(&rax), Some(&conflict.resolved));
+    }
+
+    #[test]
+    fn test_type_join() {
+        let i32_ = Type::Int(IntType::I32);
+        let u64_ = Type::Int(IntType::U64);
+        let f32_ = Type::Float(FloatType::F32);
+
+        assert_eq!(Type::Unknown.join(&i32_), i32_);
+        assert_eq!(i32_.join(&u64_), u64_);
+
+        let union = i32_.join(&f32_);
+        assert_eq!(union, Type::Union(vec![i32_.clone(), f32_.clone()]));
+        assert_eq!(union.size(), 4);
+        // 既存メンバーと互換な型はメンバー側で結びを取る
+        assert_eq!(
+            union.join(&u64_),
+            Type::Union(vec![u64_.clone(), f32_.clone()])
+        );
+        assert_eq!(union.to_c_string(), "union { int32_t u0; float u1 }");
+    }
+
     #[test]
     fn test_type_to_c_string() {
         assert_eq!(Type::Int(IntType::I32).to_c_string(), "int32_t");
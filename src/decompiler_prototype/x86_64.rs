@@ -1312,7 +1312,7 @@ impl X86Decoder {
         let dest = X86Register::RAX.to_varnode(size);
         let src_addr = X86Register::RSI.to_varnode(8);
         ops.push(PcodeOp::unary(OpCode::Load, dest, src_addr.clone(), address));
-        let size_const = Varnode { space: AddressSpace::Const, offset: size as u64, size: 8 };
+        let size_const = Varnode::new(AddressSpace::Const, size as u64, 8);
         let new_rsi = X86Register::RSI.to_varnode(8);
         ops.push(PcodeOp::binary(OpCode::IntAdd, new_rsi, src_addr, size_const, address));
         ops
@@ -1323,14 +1323,14 @@ impl X86Decoder {
         let mut ops = Vec::new();
         let src = X86Register::RAX.to_varnode(size);
         let dest_addr = X86Register::RDI.to_varnode(8);
-        let space_id = Varnode { space: AddressSpace::Const, offset: 0, size: 8 };
+        let space_id = Varnode::new(AddressSpace::Const, 0, 8);
         ops.push(PcodeOp {
             opcode: OpCode::Store,
             output: None,
             inputs: vec![space_id, dest_addr.clone(), src],
             address,
         });
-        let size_const = Varnode { space: AddressSpace::Const, offset: size as u64, size: 8 };
+        let size_const = Varnode::new(AddressSpace::Const, size as u64, 8);
         let new_rdi = X86Register::RDI.to_varnode(8);
         ops.push(PcodeOp::binary(OpCode::IntAdd, new_rdi, dest_addr, size_const, address));
         ops
@@ -1343,14 +1343,14 @@ impl X86Decoder {
         let src_addr = X86Register::RSI.to_varnode(8);
         ops.push(PcodeOp::unary(OpCode::Load, temp.clone(), src_addr.clone(), address));
         let dest_addr = X86Register::RDI.to_varnode(8);
-        let space_id = Varnode { space: AddressSpace::Const, offset: 0, size: 8 };
+        let space_id = Varnode::new(AddressSpace::Const, 0, 8);
         ops.push(PcodeOp {
             opcode: OpCode::Store,
             output: None,
             inputs: vec![space_id, dest_addr.clone(), temp],
             address,
         });
-        let size_const = Varnode { space: AddressSpace::Const, offset: size as u64, size: 8 };
+        let size_const = Varnode::new(AddressSpace::Const, size as u64, 8);
         let new_rsi = X86Register::RSI.to_varnode(8);
         ops.push(PcodeOp::binary(OpCode::IntAdd, new_rsi, src_addr, size_const.clone(), address));
         let new_rdi = X86Register::RDI.to_varnode(8);
@@ -1365,10 +1365,10 @@ impl X86Decoder {
         let mut ops = Vec::new();
         let temp = self.next_unique(size);
         ops.push(PcodeOp::unary(OpCode::Load, temp.clone(), mem_addr.clone(), address));
-        let count_vn = Varnode { space: AddressSpace::Const, offset: count as u64, size: 1 };
+        let count_vn = Varnode::new(AddressSpace::Const, count as u64, 1);
         let result = self.next_unique(size);
         ops.push(PcodeOp::binary(opcode, result.clone(), temp, count_vn, address));
-        let space_id = Varnode { space: AddressSpace::Const, offset: 0, size: 8 };
+        let space_id = Varnode::new(AddressSpace::Const, 0, 8);
         ops.push(PcodeOp {
             opcode: OpCode::Store,
             output: None,
@@ -2,55 +2,420 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Output, Stdio};
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::decompiler_prototype::function_hash::fingerprint_at;
+
+/// 関数のハッシュを求めるときに読む最大命令数
+const FINGERPRINT_MAX_INSTRUCTIONS: usize = 1000;
+
+/// これより長く使われていないGhidraプロジェクトは起動時に削除する
+const PROJECT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// プロジェクトのメタデータファイル名
+const PROJECT_METADATA_FILE: &str = "project.json";
+
+/// スクリプト実行（デコンパイル）1回のタイムアウトの既定値
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// インポートと自動解析のタイムアウトの既定値
+const DEFAULT_IMPORT_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Ghidra Headlessの設定
+#[derive(Debug, Clone)]
+pub struct GhidraConfig {
+    /// Ghidraのインストールディレクトリ
+    pub ghidra_path: PathBuf,
+    /// analyzeHeadlessのパス（Noneならインストールディレクトリから探す）
+    pub launcher: Option<PathBuf>,
+    /// デコンパイル結果のキャッシュの保存先
+    pub cache_dir: PathBuf,
+    /// Ghidraプロジェクトの保存先（Noneならcache_dir/projects）
+    pub project_dir: Option<PathBuf>,
+    /// JVMの最大ヒープ（例: "4G"。analyzeHeadlessのMAXMEM）
+    pub max_memory: Option<String>,
+    /// JVMへの追加オプション（JAVA_TOOL_OPTIONSで渡す）
+    pub vm_args: Vec<String>,
+    /// スクリプト実行1回のタイムアウト（超えたらプロセスを終了させる）
+    pub timeout: Duration,
+    /// インポートと自動解析のタイムアウト
+    pub import_timeout: Duration,
+}
+
+impl GhidraConfig {
+    /// 既定の設定（キャッシュは一時ディレクトリ）
+    pub fn new(ghidra_path: impl AsRef<Path>) -> Self {
+        Self {
+            ghidra_path: ghidra_path.as_ref().to_path_buf(),
+            launcher: None,
+            cache_dir: std::env::temp_dir().join("ghidra_mcp_cache").join("ghidra"),
+            project_dir: None,
+            max_memory: None,
+            vm_args: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            import_timeout: DEFAULT_IMPORT_TIMEOUT,
+        }
+    }
+
+    /// 環境変数から設定を作る（GHIDRA_PATHがなければNone）
+    ///
+    /// GHIDRA_LAUNCHER, GHIDRA_CACHE_DIR, GHIDRA_PROJECT_DIR, GHIDRA_MAXMEM, GHIDRA_VMARGS,
+    /// GHIDRA_TIMEOUT_SECS, GHIDRA_IMPORT_TIMEOUT_SECS で既定値を変えられる
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let seconds = |name: &str| {
+            let value = var(name)?;
+            match value.parse::<u64>() {
+                Ok(seconds) => Some(Duration::from_secs(seconds)),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid {}: {}", name, value);
+                    None
+                }
+            }
+        };
+
+        let mut config = Self::new(var("GHIDRA_PATH")?);
+        config.launcher = var("GHIDRA_LAUNCHER").map(PathBuf::from);
+        if let Some(cache_dir) = var("GHIDRA_CACHE_DIR") {
+            config.cache_dir = PathBuf::from(cache_dir);
+        }
+        config.project_dir = var("GHIDRA_PROJECT_DIR").map(PathBuf::from);
+        config.max_memory = var("GHIDRA_MAXMEM");
+        config.vm_args = var("GHIDRA_VMARGS")
+            .map(|args| args.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        config.timeout = seconds("GHIDRA_TIMEOUT_SECS").unwrap_or(config.timeout);
+        config.import_timeout = seconds("GHIDRA_IMPORT_TIMEOUT_SECS").unwrap_or(config.import_timeout);
+        Some(config)
+    }
+}
+
+/// analyzeHeadlessを探す
+///
+/// インストールディレクトリのsupport/、その1階層下（バージョン名のディレクトリを挟む
+/// macOSのHomebrewなどの配置）、最後にPATHの順に探す
+fn find_launcher(ghidra_path: &Path) -> Result<PathBuf> {
+    let launcher = if cfg!(windows) { "analyzeHeadless.bat" } else { "analyzeHeadless" };
+    if ghidra_path.is_file() {
+        return Ok(ghidra_path.to_path_buf());
+    }
+
+    let mut candidates = vec![ghidra_path.join("support").join(launcher)];
+    if let Ok(entries) = fs::read_dir(ghidra_path) {
+        let mut nested: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path().join("support").join(launcher))
+            .collect();
+        nested.sort();
+        candidates.extend(nested);
+    }
+    if let Some(paths) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&paths).map(|dir| dir.join(launcher)));
+    }
+
+    candidates.into_iter().find(|candidate| candidate.is_file()).ok_or_else(|| {
+        anyhow::anyhow!(
+            "analyzeHeadless not found. Expected at: {}",
+            ghidra_path.join("support").join(launcher).display()
+        )
+    })
+}
+
+/// 子プロセスを孫プロセス（analyzeHeadlessが起動したJVM）ごと終了させる
+fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // 起動時に新しいプロセスグループにしているので、グループごと終了させる
+        let _ = Command::new("kill")
+            .arg("-KILL")
+            .arg(format!("-{}", child.id()))
+            .status();
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .status();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// 子プロセスの終了を待つ（timeoutを超えたら終了させてエラー）
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<Output> {
+    // パイプが詰まらないよう出力は別スレッドで読む
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            buffer
+        })
+    };
+    let stdout = read_pipe(child.stdout.take().map(|pipe| Box::new(pipe) as Box<dyn Read + Send>));
+    let stderr = read_pipe(child.stderr.take().map(|pipe| Box::new(pipe) as Box<dyn Read + Send>));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            kill_process_tree(&mut child);
+            return Err(anyhow::anyhow!(
+                "Ghidra analyzeHeadless timed out after {}s and was killed",
+                timeout.as_secs_f64()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
 
 /// Ghidra Headless連携モジュール
 ///
 /// Ghidraの高品質デコンパイラをサブプロセスで呼び出す
 /// キャッシュ機構により2回目以降は即座に結果を返す
+/// キャッシュは関数本体の位置に依存しないハッシュで引くため、
+/// パッチ後のバイナリでも本体が変わっていない関数は再解析しない
+/// バイナリごとにGhidraプロジェクトを残し、インポートと自動解析は最初の1回だけ行う
 pub struct GhidraHeadless {
-    ghidra_path: PathBuf,
+    config: GhidraConfig,
+    /// 見つけたanalyzeHeadlessのパス
+    launcher: PathBuf,
     cache_dir: PathBuf,
     cache: Mutex<HashMap<String, CachedDecompilation>>,
+    /// プロジェクトごとのロック（同じプロジェクトを同時に開かない）
+    project_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+/// バイナリごとのGhidraプロジェクト
+///
+/// プロジェクト名はバイナリの内容のハッシュから作るため、バイナリが変わると別のプロジェクトになる
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhidraProject {
+    pub name: String,
+    pub binary_path: String,
+    pub binary_hash: u64,
+    /// プロジェクト内のプログラム名（インポートしたファイル名）
+    pub program_name: String,
+    pub created: u64,
+    pub last_used: u64,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// バイナリの内容からプロジェクト名を作る
+fn project_name(binary_path: &str, binary_hash: u64) -> String {
+    let stem: String = Path::new(binary_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{:016x}", stem, binary_hash)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CachedDecompilation {
     binary_path: String,
     function_address: u64,
+    /// 関数本体のハッシュ（求められなかった場合はパスとアドレスで引く）
+    #[serde(default)]
+    function_hash: Option<u64>,
     decompiled_code: String,
+    /// 一括デコンパイルで得た関数のシグネチャとローカル変数
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    local_variables: Vec<GhidraVariable>,
     timestamp: u64,
 }
 
+/// Ghidraが復元したローカル変数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GhidraVariable {
+    pub name: String,
+    pub data_type: String,
+    /// 格納場所（スタック・レジスタ）
+    pub storage: String,
+}
+
+/// 一括デコンパイルの関数ごとの結果（失敗した関数はerrorだけを持つ）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhidraFunctionResult {
+    pub address: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub c_code: Option<String>,
+    #[serde(default)]
+    pub local_variables: Vec<GhidraVariable>,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// キャッシュから返した結果か
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// 一括デコンパイルスクリプトが関数ごとに出力する行の先頭
+const FUNCTION_JSON_MARKER: &str = "===FUNCTION_JSON===";
+
+/// 一括デコンパイル用のGhidraスクリプト（関数ごとに1行のJSONを出力する）
+fn batch_decompile_script(addresses: &[u64]) -> String {
+    let addresses: Vec<String> = addresses.iter().map(|address| format!("0x{:x}L", address)).collect();
+    format!(r#"
+# Ghidra Headless Batch Decompilation Script
+import json
+from ghidra.app.decompiler import DecompInterface
+from ghidra.util.task import ConsoleTaskMonitor
+
+addresses = [{addresses}]
+
+decompiler = DecompInterface()
+decompiler.openProgram(currentProgram)
+monitor = ConsoleTaskMonitor()
+
+for address in addresses:
+    entry = {{"address": address}}
+    try:
+        func = getFunctionAt(toAddr(address))
+        if func is None:
+            entry["error"] = "Function not found at address 0x%x" % address
+        else:
+            entry["name"] = func.getName()
+            entry["signature"] = func.getPrototypeString(False, False)
+
+            # デコンパイル実行（関数ごとにタイムアウト30秒）
+            result = decompiler.decompileFunction(func, 30, monitor)
+            decomp_func = result.getDecompiledFunction()
+            if result.decompileCompleted() and decomp_func is not None:
+                entry["c_code"] = decomp_func.getC()
+                entry["signature"] = decomp_func.getSignature()
+                high = result.getHighFunction()
+                if high is not None:
+                    entry["local_variables"] = [
+                        {{"name": sym.getName(), "data_type": sym.getDataType().getDisplayName(), "storage": str(sym.getStorage())}}
+                        for sym in high.getLocalSymbolMap().getSymbols()
+                        if not sym.isParameter()
+                    ]
+            else:
+                entry["error"] = result.getErrorMessage() or "Decompilation failed or timed out"
+    except Exception as e:
+        entry["error"] = str(e)
+    print("{marker}" + json.dumps(entry))
+"#, addresses = addresses.join(", "), marker = FUNCTION_JSON_MARKER)
+}
+
+/// 一括デコンパイルの出力から関数ごとの結果を取り出す
+fn parse_batch_output(stdout: &str) -> HashMap<u64, GhidraFunctionResult> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(FUNCTION_JSON_MARKER))
+        .filter_map(|json| match serde_json::from_str::<GhidraFunctionResult>(json) {
+            Ok(result) => Some((result.address, result)),
+            Err(e) => {
+                tracing::warn!("Failed to parse Ghidra batch result: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+impl CachedDecompilation {
+    fn cache_key(&self) -> String {
+        match self.function_hash {
+            Some(hash) => format!("fn_{:016x}", hash),
+            None => legacy_cache_key(&self.binary_path, self.function_address),
+        }
+    }
+}
+
+/// 関数ハッシュを求められない場合のキャッシュキー
+fn legacy_cache_key(binary_path: &str, function_address: u64) -> String {
+    let file_name = Path::new(binary_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let path_hash = xxhash_rust::xxh3::xxh3_64(binary_path.as_bytes());
+    format!("{}_{:016x}_{:x}", file_name, path_hash, function_address)
+}
+
+/// 別のアドレスにある同じ本体の関数の結果として、Ghidraが付けた名前（FUN_xxxxxxxx）を付け替える
+fn rebase_decompiled_code(code: &str, from: u64, to: u64) -> String {
+    if from == to {
+        return code.to_string();
+    }
+    code.replace(&format!("{:08x}", from), &format!("{:08x}", to))
+}
+
 impl GhidraHeadless {
     /// 新しいGhidraHeadlessインスタンスを作成
     ///
     /// # Arguments
-    /// * `ghidra_path` - Ghidraのインストールディレクトリ（例: C:/ghidra_11.0）
+    /// * `ghidra_path` - Ghidraのインストールディレクトリ（例: C:/ghidra_11.0, /opt/ghidra_11.0_PUBLIC）
     pub fn new(ghidra_path: impl AsRef<Path>) -> Result<Self> {
-        let ghidra_path = ghidra_path.as_ref().to_path_buf();
+        Self::with_config(GhidraConfig::new(ghidra_path))
+    }
 
+    /// 設定を指定して作成
+    pub fn with_config(config: GhidraConfig) -> Result<Self> {
         // Ghidraの存在確認
-        if !ghidra_path.exists() {
+        if !config.ghidra_path.exists() {
             return Err(anyhow::anyhow!(
                 "Ghidra not found at: {}. Please install Ghidra or set correct path",
-                ghidra_path.display()
+                config.ghidra_path.display()
             ));
         }
 
+        let launcher = match &config.launcher {
+            Some(launcher) if launcher.is_file() => launcher.clone(),
+            Some(launcher) => {
+                return Err(anyhow::anyhow!("analyzeHeadless not found at: {}", launcher.display()));
+            }
+            None => find_launcher(&config.ghidra_path)?,
+        };
+        tracing::info!("Using Ghidra launcher: {}", launcher.display());
+
         // キャッシュディレクトリ作成
-        let cache_dir = PathBuf::from(".ghidra_cache");
+        let cache_dir = config.cache_dir.clone();
         if !cache_dir.exists() {
             fs::create_dir_all(&cache_dir)?;
         }
 
-        Ok(Self {
-            ghidra_path,
+        let headless = Self {
+            config,
+            launcher,
             cache_dir,
             cache: Mutex::new(HashMap::new()),
-        })
+            project_locks: Mutex::new(HashMap::new()),
+        };
+
+        // 長く使われていないプロジェクトを片付ける
+        match headless.prune_projects(PROJECT_MAX_AGE) {
+            Ok(0) => {}
+            Ok(removed) => tracing::info!("Removed {} stale Ghidra projects", removed),
+            Err(e) => tracing::warn!("Failed to prune Ghidra projects: {}", e),
+        }
+
+        Ok(headless)
     }
 
     /// 関数をデコンパイル（キャッシュ優先）
@@ -62,15 +427,12 @@ impl GhidraHeadless {
     /// # Returns
     /// デコンパイルされたC疑似コード
     pub fn decompile(&self, binary_path: &str, function_address: u64) -> Result<String> {
-        let cache_key = format!("{}_{:x}", binary_path, function_address);
+        let (function_hash, cache_key) = Self::cache_key_for(binary_path, function_address);
 
-        // キャッシュチェック
-        {
-            let cache = self.cache.lock().unwrap();
-            if let Some(cached) = cache.get(&cache_key) {
-                tracing::info!("Cache hit for {}@0x{:x}", binary_path, function_address);
-                return Ok(cached.decompiled_code.clone());
-            }
+        // キャッシュチェック（別のビルドの同じ関数も対象）
+        if let Some(cached) = self.cached(&cache_key, function_address) {
+            tracing::info!("Cache hit for {}@0x{:x}", binary_path, function_address);
+            return Ok(cached.decompiled_code);
         }
 
         tracing::info!("Cache miss, calling Ghidra Headless...");
@@ -78,36 +440,155 @@ impl GhidraHeadless {
         // Ghidra Headlessで実際にデコンパイル
         let decompiled = self.decompile_with_ghidra(binary_path, function_address)?;
 
-        // キャッシュに保存
-        {
-            let mut cache = self.cache.lock().unwrap();
-            cache.insert(cache_key.clone(), CachedDecompilation {
-                binary_path: binary_path.to_string(),
-                function_address,
-                decompiled_code: decompiled.clone(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            });
+        // キャッシュに保存（メモリとディスク）
+        self.store(&cache_key, CachedDecompilation {
+            binary_path: binary_path.to_string(),
+            function_address,
+            function_hash,
+            decompiled_code: decompiled.clone(),
+            signature: None,
+            local_variables: Vec::new(),
+            timestamp: unix_time(),
+        })?;
+
+        Ok(decompiled)
+    }
+
+    /// 複数の関数を1回のGhidra実行でまとめてデコンパイル（キャッシュ優先）
+    ///
+    /// 結果はaddressesの順で返す。失敗した関数はerrorを持つ結果になり、成功した関数はすべてキャッシュする
+    pub fn decompile_batch(&self, binary_path: &str, addresses: &[u64]) -> Result<Vec<GhidraFunctionResult>> {
+        let keys: Vec<(Option<u64>, String)> = addresses
+            .iter()
+            .map(|&address| Self::cache_key_for(binary_path, address))
+            .collect();
+
+        let mut results: Vec<Option<GhidraFunctionResult>> = addresses
+            .iter()
+            .zip(&keys)
+            .map(|(&address, (_, key))| {
+                self.cached(key, address).map(|cached| GhidraFunctionResult {
+                    address,
+                    name: None,
+                    signature: cached.signature,
+                    c_code: Some(cached.decompiled_code),
+                    local_variables: cached.local_variables,
+                    error: None,
+                    cached: true,
+                })
+            })
+            .collect();
+
+        let mut missing: Vec<u64> = addresses
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.is_none())
+            .map(|(&address, _)| address)
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        if !missing.is_empty() {
+            tracing::info!("Batch decompiling {} functions with Ghidra Headless...", missing.len());
+
+            let script_name = format!("decompile_batch_{:016x}.py", xxhash_rust::xxh3::xxh3_64(format!("{:?}", missing).as_bytes()));
+            let output = self.run_project_script(binary_path, &script_name, &batch_decompile_script(&missing))?;
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut decompiled = parse_batch_output(&stdout);
+
+            if decompiled.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Ghidra batch decompilation produced no results: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            for (index, (&address, (function_hash, key))) in addresses.iter().zip(&keys).enumerate() {
+                if results[index].is_some() {
+                    continue;
+                }
+                // 同じアドレスが複数回指定されていれば2回目以降はキャッシュから返す
+                let result = match decompiled.remove(&address) {
+                    Some(result) => result,
+                    None => match self.cached(key, address) {
+                        Some(cached) => GhidraFunctionResult {
+                            address,
+                            name: None,
+                            signature: cached.signature,
+                            c_code: Some(cached.decompiled_code),
+                            local_variables: cached.local_variables,
+                            error: None,
+                            cached: true,
+                        },
+                        None => GhidraFunctionResult {
+                            address,
+                            name: None,
+                            signature: None,
+                            c_code: None,
+                            local_variables: Vec::new(),
+                            error: Some("No result from Ghidra".to_string()),
+                            cached: false,
+                        },
+                    },
+                };
+
+                if let Some(code) = &result.c_code {
+                    self.store(key, CachedDecompilation {
+                        binary_path: binary_path.to_string(),
+                        function_address: address,
+                        function_hash: *function_hash,
+                        decompiled_code: code.clone(),
+                        signature: result.signature.clone(),
+                        local_variables: result.local_variables.clone(),
+                        timestamp: unix_time(),
+                    })?;
+                }
+                results[index] = Some(result);
+            }
         }
 
-        // ディスクにもキャッシュ
-        self.save_cache_to_disk(&cache_key, binary_path, function_address, &decompiled)?;
+        Ok(results.into_iter().flatten().collect())
+    }
 
-        Ok(decompiled)
+    /// 関数のキャッシュキー（関数本体のハッシュを求められなければパスとアドレス）
+    fn cache_key_for(binary_path: &str, function_address: u64) -> (Option<u64>, String) {
+        let function_hash = Self::function_hash(binary_path, function_address);
+        let cache_key = match function_hash {
+            Some(hash) => format!("fn_{:016x}", hash),
+            None => legacy_cache_key(binary_path, function_address),
+        };
+        (function_hash, cache_key)
     }
 
-    /// Ghidra Headlessで実際にデコンパイル実行
-    fn decompile_with_ghidra(&self, binary_path: &str, function_address: u64) -> Result<String> {
-        // 一時プロジェクトディレクトリ
-        let temp_project_dir = self.cache_dir.join("temp_projects");
-        fs::create_dir_all(&temp_project_dir)?;
+    /// キャッシュ済みの結果（別のアドレスの同じ関数なら名前を付け替える）
+    fn cached(&self, cache_key: &str, function_address: u64) -> Option<CachedDecompilation> {
+        let cache = self.cache.lock().unwrap();
+        let cached = cache.get(cache_key)?;
+        let mut rebased = cached.clone();
+        rebased.decompiled_code = rebase_decompiled_code(&cached.decompiled_code, cached.function_address, function_address);
+        rebased.function_address = function_address;
+        Some(rebased)
+    }
 
-        let project_name = format!("temp_{}", std::process::id());
+    /// 結果をメモリとディスクのキャッシュに保存
+    fn store(&self, cache_key: &str, entry: CachedDecompilation) -> Result<()> {
+        self.save_cache_to_disk(cache_key, &entry)?;
+        self.cache.lock().unwrap().insert(cache_key.to_string(), entry);
+        Ok(())
+    }
 
-        // Ghidra解析スクリプト作成
-        let script_path = self.cache_dir.join("decompile.py");
+    /// 関数本体のハッシュを求める（バイナリを読めない・逆アセンブルできない場合はNone）
+    fn function_hash(binary_path: &str, function_address: u64) -> Option<u64> {
+        let data = fs::read(binary_path).ok()?;
+        fingerprint_at(&data, function_address, FINGERPRINT_MAX_INSTRUCTIONS)
+            .ok()
+            .map(|fingerprint| fingerprint.hash)
+    }
+
+    /// Ghidra Headlessで実際にデコンパイル実行
+    ///
+    /// バイナリのプロジェクトを（なければインポートして）開き、自動解析なしでスクリプトだけ実行する
+    fn decompile_with_ghidra(&self, binary_path: &str, function_address: u64) -> Result<String> {
         let script_content = format!(r#"
 # Ghidra Headless Decompilation Script
 from ghidra.app.decompiler import DecompInterface
@@ -143,30 +624,8 @@ else:
     exit(1)
 "#, function_address, function_address);
 
-        fs::write(&script_path, script_content)?;
-
-        // analyzeHeadless実行
-        let analyze_headless = self.ghidra_path.join("support").join("analyzeHeadless.bat");
-
-        if !analyze_headless.exists() {
-            return Err(anyhow::anyhow!(
-                "analyzeHeadless not found. Expected at: {}",
-                analyze_headless.display()
-            ));
-        }
-
-        tracing::info!("Running Ghidra Headless analysis...");
-
-        let output = Command::new(&analyze_headless)
-            .arg(&temp_project_dir)
-            .arg(&project_name)
-            .arg("-import")
-            .arg(binary_path)
-            .arg("-postScript")
-            .arg(&script_path)
-            .arg("-deleteProject") // 解析後にプロジェクト削除
-            .output()
-            .context("Failed to execute Ghidra analyzeHeadless")?;
+        let script_name = format!("decompile_{:x}.py", function_address);
+        let output = self.run_project_script(binary_path, &script_name, &script_content)?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -193,27 +652,270 @@ else:
         Err(anyhow::anyhow!("Failed to extract decompiled code from Ghidra output"))
     }
 
-    /// キャッシュをディスクに保存
-    fn save_cache_to_disk(
-        &self,
-        cache_key: &str,
-        binary_path: &str,
-        function_address: u64,
-        decompiled_code: &str,
-    ) -> Result<()> {
-        let cache_file = self.cache_dir.join(format!("{}.json", cache_key));
+    /// バイナリのプロジェクトを（なければインポートして）開き、自動解析なしでスクリプトを実行する
+    fn run_project_script(&self, binary_path: &str, script_name: &str, script_content: &str) -> Result<std::process::Output> {
+        let binary_hash = Self::hash_binary(binary_path)?;
+        let name = project_name(binary_path, binary_hash);
+        let lock = self.project_lock(&name);
+        let _guard = lock.lock().unwrap();
+
+        let project = self.ensure_project(binary_path, binary_hash, &name)?;
+
+        // スクリプトはプロジェクトのロック中に書くので他の呼び出しと衝突しない
+        let script_path = self.project_location(&name).join(script_name);
+        fs::write(&script_path, script_content)?;
+
+        tracing::info!("Running Ghidra Headless on existing project {}...", name);
+
+        let output = self.run_headless(&name, &[
+            "-process".as_ref(),
+            project.program_name.as_ref(),
+            "-noanalysis".as_ref(), // 解析済みのプロジェクトを使う
+            "-readOnly".as_ref(),   // スクリプトの失敗でプロジェクトを壊さない
+            "-postScript".as_ref(),
+            script_path.as_os_str(),
+        ], self.config.timeout);
+        let _ = fs::remove_file(&script_path);
+        output
+    }
+
+    /// analyzeHeadlessを起動するコマンド
+    fn launcher_command(&self) -> Command {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            // zipを展開しただけだと実行権限がないことがあるのでshで起動する
+            let executable = fs::metadata(&self.launcher)
+                .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false);
+            if !executable {
+                let mut command = Command::new("sh");
+                command.arg(&self.launcher);
+                return command;
+            }
+        }
+        Command::new(&self.launcher)
+    }
+
+    /// プロジェクトに対してanalyzeHeadlessを実行（timeoutを超えたらプロセスごと終了させる）
+    fn run_headless(&self, name: &str, args: &[&std::ffi::OsStr], timeout: Duration) -> Result<Output> {
+        let mut command = self.launcher_command();
+        command
+            .arg(self.project_location(name))
+            .arg(name)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if let Some(max_memory) = &self.config.max_memory {
+            command.env("MAXMEM", max_memory);
+        }
+        if !self.config.vm_args.is_empty() {
+            let mut options = std::env::var("JAVA_TOOL_OPTIONS").unwrap_or_default();
+            for arg in &self.config.vm_args {
+                if !options.is_empty() {
+                    options.push(' ');
+                }
+                options.push_str(arg);
+            }
+            command.env("JAVA_TOOL_OPTIONS", options);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            // タイムアウト時にJVMごと終了させられるよう新しいプロセスグループで起動する
+            command.process_group(0);
+        }
+
+        let child = command.spawn().context("Failed to execute Ghidra analyzeHeadless")?;
+        wait_with_timeout(child, timeout)
+    }
+
+    /// バイナリの内容のハッシュ
+    fn hash_binary(binary_path: &str) -> Result<u64> {
+        let data = fs::read(binary_path).with_context(|| format!("Failed to read binary: {}", binary_path))?;
+        Ok(xxhash_rust::xxh3::xxh3_64(&data))
+    }
+
+    fn projects_dir(&self) -> PathBuf {
+        self.config
+            .project_dir
+            .clone()
+            .unwrap_or_else(|| self.cache_dir.join("projects"))
+    }
+
+    /// プロジェクトの置き場所（analyzeHeadlessに渡すプロジェクトディレクトリ）
+    fn project_location(&self, name: &str) -> PathBuf {
+        self.projects_dir().join(name)
+    }
+
+    fn project_lock(&self, name: &str) -> Arc<Mutex<()>> {
+        self.project_locks
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn read_project(&self, name: &str) -> Option<GhidraProject> {
+        let location = self.project_location(name);
+        let metadata = fs::read_to_string(location.join(PROJECT_METADATA_FILE)).ok()?;
+        let project: GhidraProject = serde_json::from_str(&metadata).ok()?;
+        location.join(format!("{}.gpr", name)).exists().then_some(project)
+    }
+
+    fn write_project(&self, project: &GhidraProject) -> Result<()> {
+        let path = self.project_location(&project.name).join(PROJECT_METADATA_FILE);
+        fs::write(path, serde_json::to_string_pretty(project)?)?;
+        Ok(())
+    }
+
+    /// バイナリのプロジェクトを開く（なければインポートして自動解析する）
+    pub fn open_project(&self, binary_path: &str) -> Result<GhidraProject> {
+        let binary_hash = Self::hash_binary(binary_path)?;
+        let name = project_name(binary_path, binary_hash);
+        let lock = self.project_lock(&name);
+        let _guard = lock.lock().unwrap();
+        self.ensure_project(binary_path, binary_hash, &name)
+    }
+
+    /// プロジェクトのロックを持った状態で呼ぶ
+    fn ensure_project(&self, binary_path: &str, binary_hash: u64, name: &str) -> Result<GhidraProject> {
+        if let Some(mut project) = self.read_project(name) {
+            project.last_used = unix_time();
+            self.write_project(&project)?;
+            return Ok(project);
+        }
+
+        // 同じパスの古い内容のプロジェクトは使われなくなるので消す
+        for old in self.list_projects()? {
+            if old.binary_path == binary_path && old.name != name {
+                self.remove_project(&old.name)?;
+            }
+        }
+
+        // 途中で失敗したプロジェクトが残っていれば作り直す
+        let location = self.project_location(name);
+        if location.exists() {
+            fs::remove_dir_all(&location)?;
+        }
+        fs::create_dir_all(&location)?;
+
+        tracing::info!("Importing {} into Ghidra project {} (one-time analysis)...", binary_path, name);
+
+        let output = match self.run_headless(name, &["-import".as_ref(), binary_path.as_ref()], self.config.import_timeout) {
+            Ok(output) => output,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&location);
+                return Err(e);
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        tracing::debug!("Ghidra import stdout: {}", stdout);
 
-        let cached_data = CachedDecompilation {
+        if !output.status.success() || stdout.contains("Import failed") || !location.join(format!("{}.gpr", name)).exists() {
+            let _ = fs::remove_dir_all(&location);
+            return Err(anyhow::anyhow!(
+                "Ghidra import of {} failed: {}",
+                binary_path,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let now = unix_time();
+        let project = GhidraProject {
+            name: name.to_string(),
             binary_path: binary_path.to_string(),
-            function_address,
-            decompiled_code: decompiled_code.to_string(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            binary_hash,
+            program_name: Path::new(binary_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            created: now,
+            last_used: now,
         };
+        self.write_project(&project)?;
+        Ok(project)
+    }
+
+    /// 保存されているプロジェクトの一覧
+    pub fn list_projects(&self) -> Result<Vec<GhidraProject>> {
+        let mut projects = Vec::new();
+        if !self.projects_dir().exists() {
+            return Ok(projects);
+        }
+
+        for entry in fs::read_dir(self.projects_dir())? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(project) = self.read_project(&name) {
+                projects.push(project);
+            }
+        }
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    /// バイナリのプロジェクトを削除（次のデコンパイルで再インポートする）
+    pub fn delete_project(&self, binary_path: &str) -> Result<bool> {
+        let mut deleted = false;
+        for project in self.list_projects()? {
+            if project.binary_path == binary_path {
+                let lock = self.project_lock(&project.name);
+                let _guard = lock.lock().unwrap();
+                self.remove_project(&project.name)?;
+                deleted = true;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// 使われていない・バイナリが変わった・壊れたプロジェクトを削除（使用中のものは残す）
+    pub fn prune_projects(&self, max_age: Duration) -> Result<usize> {
+        if !self.projects_dir().exists() {
+            return Ok(0);
+        }
+
+        let now = unix_time();
+        let mut removed = 0;
+        for entry in fs::read_dir(self.projects_dir())? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            let lock = self.project_lock(&name);
+            let Ok(_guard) = lock.try_lock() else {
+                continue;
+            };
 
-        let json = serde_json::to_string_pretty(&cached_data)?;
+            let stale = match self.read_project(&name) {
+                Some(project) => {
+                    now.saturating_sub(project.last_used) > max_age.as_secs()
+                        || Self::hash_binary(&project.binary_path).ok() != Some(project.binary_hash)
+                }
+                None => true,
+            };
+            if stale {
+                self.remove_project(&name)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn remove_project(&self, name: &str) -> Result<()> {
+        let location = self.project_location(name);
+        if location.exists() {
+            fs::remove_dir_all(&location)
+                .with_context(|| format!("Failed to remove Ghidra project: {}", location.display()))?;
+        }
+        Ok(())
+    }
+
+    /// キャッシュをディスクに保存
+    fn save_cache_to_disk(&self, cache_key: &str, entry: &CachedDecompilation) -> Result<()> {
+        let cache_file = self.cache_dir.join(format!("{}.json", cache_key));
+        let json = serde_json::to_string_pretty(entry)?;
         fs::write(cache_file, json)?;
 
         Ok(())
@@ -234,11 +936,7 @@ else:
             if path.extension().and_then(|s| s.to_str()) == Some("json") {
                 if let Ok(content) = fs::read_to_string(&path) {
                     if let Ok(cached_data) = serde_json::from_str::<CachedDecompilation>(&content) {
-                        let cache_key = format!("{}_{:x}",
-                            cached_data.binary_path,
-                            cached_data.function_address
-                        );
-                        cache.insert(cache_key, cached_data);
+                        cache.insert(cached_data.cache_key(), cached_data);
                     }
                 }
             }
@@ -273,6 +971,7 @@ else:
         let cache = self.cache.lock().unwrap();
         let mut stats = HashMap::new();
         stats.insert("total_entries".to_string(), cache.len());
+        stats.insert("projects".to_string(), self.list_projects().map(|p| p.len()).unwrap_or(0));
         stats
     }
 }
@@ -287,4 +986,149 @@ mod tests {
         let result = GhidraHeadless::new("C:/nonexistent_ghidra");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_rebase_decompiled_code() {
+        let code = "void FUN_00401000(void)\n{\n  FUN_00402000();\n}";
+        let rebased = rebase_decompiled_code(code, 0x401000, 0x401230);
+        assert_eq!(rebased, "void FUN_00401230(void)\n{\n  FUN_00402000();\n}");
+    }
+
+    /// 呼び出しを記録し、-importでプロジェクトを作り、-processで固定のコードを返すanalyzeHeadless
+    /// 一括デコンパイルのスクリプトには関数ごとのJSONを返す（0x403000は関数なし、0xdeadは応答しない）
+    #[cfg(unix)]
+    const FAKE_ANALYZE_HEADLESS: &str = r#"#!/bin/sh
+echo "$*" >> "$(dirname "$0")/calls.log"
+echo "MAXMEM=$MAXMEM JAVA_TOOL_OPTIONS=$JAVA_TOOL_OPTIONS" >> "$(dirname "$0")/env.log"
+eval script=\${$#}
+case "$*" in
+  *-import*) touch "$1/$2.gpr"; mkdir -p "$1/$2.rep" ;;
+  *decompile_batch*)
+    for a in $(sed -n 's/^addresses = \[\(.*\)\]/\1/p' "$script" | tr -d 'L,'); do
+      if [ "$a" = "0x403000" ]; then
+        echo "===FUNCTION_JSON==="'{"address": 4206592, "error": "Function not found at address 0x403000"}'
+      else
+        printf '===FUNCTION_JSON==={"address": %d, "name": "FUN_%08x", "signature": "int FUN_%08x(void)", "c_code": "int FUN_%08x(void) {}", "local_variables": [{"name": "local_8", "data_type": "int", "storage": "Stack[-0x8]:4"}]}\n' $a $a $a $a
+      fi
+    done ;;
+  *-process*)
+    [ -f "$1/$2.gpr" ] || { echo "ERROR: no project"; exit 1; }
+    grep -q 0xdead "$script" && sleep 30
+    echo "===DECOMPILED_START==="
+    echo "void fake(void) {}"
+    echo "===DECOMPILED_END===" ;;
+esac
+"#;
+
+    /// 偽のGhidraインストールと解析対象のバイナリを作る
+    #[cfg(unix)]
+    fn fake_ghidra(name: &str) -> Result<(PathBuf, String)> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let support = root.join("ghidra").join("support");
+        fs::create_dir_all(&support)?;
+        let launcher = support.join("analyzeHeadless");
+        fs::write(&launcher, FAKE_ANALYZE_HEADLESS)?;
+        fs::set_permissions(&launcher, fs::Permissions::from_mode(0o755))?;
+        let binary = root.join("game.exe");
+        fs::write(&binary, b"MZ not really a program")?;
+        Ok((root, binary.to_string_lossy().to_string()))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_project_is_imported_once_and_reused() -> Result<()> {
+        let (root, binary) = fake_ghidra("ghidra_project_test")?;
+        let support = root.join("ghidra").join("support");
+
+        let ghidra = GhidraHeadless::with_config(GhidraConfig {
+            cache_dir: root.join("cache"),
+            ..GhidraConfig::new(root.join("ghidra"))
+        })?;
+        assert_eq!(ghidra.decompile(&binary, 0x401000)?, "void fake(void) {}");
+        ghidra.decompile(&binary, 0x402000)?;
+
+        let calls = fs::read_to_string(support.join("calls.log"))?;
+        assert_eq!(calls.matches("-import").count(), 1);
+        assert_eq!(calls.matches("-process game.exe -noanalysis").count(), 2);
+        assert_eq!(ghidra.list_projects()?.len(), 1);
+
+        // バイナリが変わったら古いプロジェクトを捨てて再インポートする
+        fs::write(&binary, b"MZ patched")?;
+        ghidra.decompile(&binary, 0x403000)?;
+        let projects = ghidra.list_projects()?;
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].binary_hash, GhidraHeadless::hash_binary(&binary)?);
+        assert_eq!(fs::read_to_string(support.join("calls.log"))?.matches("-import").count(), 2);
+
+        assert!(ghidra.delete_project(&binary)?);
+        assert!(ghidra.list_projects()?.is_empty());
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_decompile_batch_runs_ghidra_once_and_fills_cache() -> Result<()> {
+        let (root, binary) = fake_ghidra("ghidra_batch_test")?;
+        let calls = root.join("ghidra").join("support").join("calls.log");
+
+        let ghidra = GhidraHeadless::with_config(GhidraConfig {
+            cache_dir: root.join("cache"),
+            ..GhidraConfig::new(root.join("ghidra"))
+        })?;
+        let results = ghidra.decompile_batch(&binary, &[0x401000, 0x403000, 0x402000])?;
+        assert_eq!(results.iter().map(|r| r.address).collect::<Vec<_>>(), vec![0x401000, 0x403000, 0x402000]);
+        assert_eq!(results[0].c_code.as_deref(), Some("int FUN_00401000(void) {}"));
+        assert_eq!(results[0].local_variables[0].storage, "Stack[-0x8]:4");
+        assert!(results[1].c_code.is_none() && results[1].error.is_some());
+        assert_eq!(fs::read_to_string(&calls)?.matches("-process").count(), 1);
+
+        // 2回目はGhidraを起動せずキャッシュから返す（単体のデコンパイルも同じキャッシュを使う）
+        let again = ghidra.decompile_batch(&binary, &[0x402000, 0x401000])?;
+        assert!(again.iter().all(|r| r.cached));
+        assert_eq!(again[0].signature.as_deref(), Some("int FUN_00402000(void)"));
+        assert_eq!(ghidra.decompile(&binary, 0x401000)?, "int FUN_00401000(void) {}");
+        assert_eq!(fs::read_to_string(&calls)?.matches("-process").count(), 1);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_launcher_config_and_timeout() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (root, binary) = fake_ghidra("ghidra_launcher_test")?;
+        // インストールディレクトリの1階層下にあり、実行権限のないランチャーも使える
+        let install = root.join("ghidra_11.0_PUBLIC");
+        fs::rename(root.join("ghidra"), &install)?;
+        fs::create_dir(root.join("ghidra"))?;
+        fs::rename(&install, root.join("ghidra").join("ghidra_11.0_PUBLIC"))?;
+        let support = root.join("ghidra").join("ghidra_11.0_PUBLIC").join("support");
+        fs::set_permissions(support.join("analyzeHeadless"), fs::Permissions::from_mode(0o644))?;
+
+        let ghidra = GhidraHeadless::with_config(GhidraConfig {
+            cache_dir: root.join("cache"),
+            project_dir: Some(root.join("projects")),
+            max_memory: Some("4G".to_string()),
+            vm_args: vec!["-Dfake=1".to_string()],
+            timeout: Duration::from_secs(1),
+            ..GhidraConfig::new(root.join("ghidra"))
+        })?;
+        assert_eq!(ghidra.decompile(&binary, 0x401000)?, "void fake(void) {}");
+        assert!(fs::read_to_string(support.join("env.log"))?.contains("MAXMEM=4G JAVA_TOOL_OPTIONS=-Dfake=1"));
+        assert!(root.join("projects").join(&ghidra.list_projects()?[0].name).exists());
+
+        // 応答しないランチャーはタイムアウトで終了させる
+        let started = Instant::now();
+        let error = ghidra.decompile(&binary, 0xdead).unwrap_err();
+        assert!(error.to_string().contains("timed out"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
 }
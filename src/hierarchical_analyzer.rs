@@ -329,6 +329,53 @@ impl HierarchicalAnalyzer {
         })
     }
 
+    /// 一括デコンパイル用: 検出した関数の仮想アドレスとファイルオフセット
+    ///
+    /// ファイル上に実体のないアドレスの関数は除外する
+    pub fn function_entry_points(&mut self, path: &str) -> Result<Vec<(u64, usize)>> {
+        let functions = self.get_or_cache_functions(path)?;
+        let buffer = fs::read(path)?;
+        let object = Object::parse(&buffer)?;
+        let mut entries = Vec::new();
+
+        match object {
+            Object::Elf(elf) => {
+                for func in &functions {
+                    let section = elf.section_headers.iter().find(|sh| {
+                        sh.sh_type != goblin::elf::section_header::SHT_NOBITS
+                            && sh.sh_addr != 0
+                            && func.address >= sh.sh_addr
+                            && func.address < sh.sh_addr + sh.sh_size
+                    });
+                    if let Some(sh) = section {
+                        entries.push((func.address, (func.address - sh.sh_addr + sh.sh_offset) as usize));
+                    }
+                }
+            }
+            Object::PE(pe) => {
+                // PEの関数アドレスはRVAなのでイメージベースを加える
+                let image_base = pe.image_base as u64;
+                for func in &functions {
+                    let section = pe.sections.iter().find(|s| {
+                        let start = s.virtual_address as u64;
+                        let size = s.virtual_size.max(s.size_of_raw_data) as u64;
+                        func.address >= start && func.address < start + size
+                    });
+                    if let Some(s) = section {
+                        let offset = func.address - s.virtual_address as u64 + s.pointer_to_raw_data as u64;
+                        entries.push((image_base + func.address, offset as usize));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        entries.retain(|&(_, offset)| offset < buffer.len());
+        entries.sort_unstable();
+        entries.dedup();
+        Ok(entries)
+    }
+
     // === キャッシュ系ヘルパー ===
 
     fn get_or_cache_functions(&mut self, path: &str) -> Result<Vec<FunctionInfo>> {
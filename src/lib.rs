@@ -12,3 +12,8 @@ pub mod decompiler_prototype;
 
 // 動的解析（メモリスキャン）
 pub mod memory_scanner;
+pub mod pointer_scanner;
+pub mod memory_watch;
+
+// バイトシグネチャ（ライブプロセスと静的イメージで共通）
+pub mod signature;
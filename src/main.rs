@@ -3,7 +3,10 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tracing::{info, error};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedSender, WeakUnboundedSender};
 use tokio::sync::Mutex;
 
 mod hierarchical_analyzer;
@@ -13,9 +16,31 @@ mod ghidra_headless;
 
 // Ghidraデコンパイラコアのプロトタイプ実装（新規）
 mod decompiler_prototype;
+mod signature;
 
 use hierarchical_analyzer::HierarchicalAnalyzer;
 use ghidra_headless::GhidraHeadless;
+use decompiler_prototype::{BatchProgress, BatchReport, BinaryDiff};
+
+/// 一括デコンパイルジョブの状態
+#[derive(Debug, Clone)]
+enum BatchJobState {
+    Running(BatchProgress),
+    Completed(BatchReport),
+    Failed(String),
+}
+
+/// ジョブID → 状態
+type BatchJobs = Arc<std::sync::Mutex<HashMap<u64, BatchJobState>>>;
+
+/// 次に割り当てるジョブID
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+/// バイナリ差分のキー（旧パス, 新パス, 最大命令数, 各ファイルの更新日時）
+type DiffKey = (String, String, usize, Option<std::time::SystemTime>, Option<std::time::SystemTime>);
+
+/// 直近のバイナリ差分（ページ送りのたびに再計算しない）
+static LAST_DIFF: std::sync::Mutex<Option<(DiffKey, Arc<BinaryDiff>)>> = std::sync::Mutex::new(None);
 
 #[derive(Debug, Deserialize)]
 struct McpRequest {
@@ -74,8 +99,21 @@ async fn main() -> Result<()> {
         None
     };
 
+    let jobs: BatchJobs = Arc::new(std::sync::Mutex::new(HashMap::new()));
+
+    // 応答と通知は1つのタスクから標準出力へ書き出す
+    let (outbound, mut outbound_rx) = mpsc::unbounded_channel::<String>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = outbound_rx.recv().await {
+            stdout.write_all(message.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+        Ok::<(), std::io::Error>(())
+    });
+
     let stdin = tokio::io::stdin();
-    let mut stdout = tokio::io::stdout();
     let mut reader = BufReader::new(stdin);
     let mut line = String::new();
 
@@ -86,7 +124,7 @@ async fn main() -> Result<()> {
         match reader.read_line(&mut line).await {
             Ok(0) => break,
             Ok(_) => {
-                let response = match process_request(&line, Arc::clone(&analyzer), ghidra.clone()).await {
+                let response = match process_request(&line, Arc::clone(&analyzer), ghidra.clone(), Arc::clone(&jobs), &outbound).await {
                     Ok(resp) => resp,
                     Err(e) => {
                         error!("Request processing error: {}", e);
@@ -103,9 +141,9 @@ async fn main() -> Result<()> {
                 };
 
                 let response_str = serde_json::to_string(&response)?;
-                stdout.write_all(response_str.as_bytes()).await?;
-                stdout.write_all(b"\n").await?;
-                stdout.flush().await?;
+                if outbound.send(response_str).is_err() {
+                    break;
+                }
             }
             Err(e) => {
                 error!("Read error: {}", e);
@@ -114,6 +152,10 @@ async fn main() -> Result<()> {
         }
     }
 
+    // 実行中のジョブは弱参照しか持たないので、ここで送信口が閉じる
+    drop(outbound);
+    writer.await??;
+
     info!("Server shutting down");
     Ok(())
 }
@@ -122,6 +164,8 @@ async fn process_request(
     request_str: &str,
     analyzer: Arc<Mutex<HierarchicalAnalyzer>>,
     ghidra: Option<Arc<Mutex<GhidraHeadless>>>,
+    jobs: BatchJobs,
+    outbound: &UnboundedSender<String>,
 ) -> Result<McpResponse> {
     let request: McpRequest = serde_json::from_str(request_str)?;
     
@@ -130,7 +174,7 @@ async fn process_request(
     let result = match request.method.as_str() {
         "initialize" => handle_initialize().await?,
         "tools/list" => handle_list_tools(ghidra.is_some()).await?,
-        "tools/call" => handle_tool_call(request.params, analyzer, ghidra).await?,
+        "tools/call" => handle_tool_call(request.params, analyzer, ghidra, jobs, outbound).await?,
         _ => {
             return Ok(McpResponse {
                 jsonrpc: "2.0".to_string(),
@@ -352,6 +396,193 @@ async fn handle_list_tools(ghidra_enabled: bool) -> Result<Value> {
                     },
                     "required": ["path", "function_address", "file_offset"]
                 }
+            }),
+
+            // 関数の名前・注釈（関数本体のハッシュで別ビルドにも引き継ぐ）
+            json!({
+                "name": "annotate_function",
+                "description": "関数に名前とコメントを付ける。関数本体のハッシュに紐付けるため、パッチ後のバイナリでも本体が同じ関数に引き継がれる。decompile_function_cachedの結果に含まれる",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "バイナリファイルパス"
+                        },
+                        "function_address": {
+                            "type": "string",
+                            "description": "関数のアドレス（16進数: 0x140001000）"
+                        },
+                        "file_offset": {
+                            "type": "string",
+                            "description": "ファイルオフセット（16進数: 0x600）"
+                        },
+                        "max_instructions": {
+                            "type": "integer",
+                            "description": "最大命令数（decompile_function_cachedと同じ値）",
+                            "default": 1000
+                        },
+                        "name": {
+                            "type": "string",
+                            "description": "関数名（省略すると削除）"
+                        },
+                        "comment": {
+                            "type": "string",
+                            "description": "コメント（省略すると削除）"
+                        }
+                    },
+                    "required": ["path", "function_address", "file_offset"]
+                }
+            }),
+
+            // バイナリ差分
+            json!({
+                "name": "diff_binaries",
+                "description": "2つのビルドの関数を対応付け（関数ハッシュ → CFG形状 → 呼び出し関係 → 参照文字列）、追加・削除・変更・移動した関数とアドレスの対応を返す（ページネーション対応）",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "old_path": {
+                            "type": "string",
+                            "description": "古いビルドのバイナリファイルパス"
+                        },
+                        "new_path": {
+                            "type": "string",
+                            "description": "新しいビルドのバイナリファイルパス"
+                        },
+                        "status": {
+                            "type": "string",
+                            "enum": ["unchanged", "moved", "changed", "added", "removed"],
+                            "description": "この種類の差分のみ返す（オプション）"
+                        },
+                        "page": {
+                            "type": "integer",
+                            "description": "ページ番号（0始まり）",
+                            "default": 0
+                        },
+                        "page_size": {
+                            "type": "integer",
+                            "description": "1ページあたりの件数",
+                            "default": 50
+                        },
+                        "max_instructions": {
+                            "type": "integer",
+                            "description": "1関数あたりの最大命令数",
+                            "default": 1000
+                        }
+                    },
+                    "required": ["old_path", "new_path"]
+                }
+            }),
+
+            // 静的イメージのシグネチャスキャン
+            json!({
+                "name": "scan_signature",
+                "description": "IDA形式のワイルドカード付きバイトパターン（例: 48 8B 05 ?? ?? ?? ?? E8）でバイナリファイルをスキャンし、一致位置を仮想アドレスで返す。patternsで複数パターンを一度に検索できる",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "バイナリファイルパス"
+                        },
+                        "pattern": {
+                            "type": "string",
+                            "description": "バイトパターン（??または?がワイルドカード）"
+                        },
+                        "patterns": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "複数のバイトパターン（ファイルを1回だけ走査してまとめて検索）"
+                        },
+                        "section": {
+                            "type": "string",
+                            "description": "スキャンするセクション名（例: .text、省略時は全セクション）"
+                        },
+                        "max_results": {
+                            "type": "integer",
+                            "description": "パターンごとに返す一致位置の最大数",
+                            "default": 100
+                        }
+                    },
+                    "required": ["path"]
+                }
+            }),
+
+            // シグネチャ生成
+            json!({
+                "name": "make_signature",
+                "description": "関数または命令を一意に特定する最短のバイトシグネチャを生成。RIP相対の変位・相対分岐先・アドレスの即値はワイルドカードになるため、アップデート後も使える",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "バイナリファイルパス"
+                        },
+                        "address": {
+                            "type": "string",
+                            "description": "関数・命令の仮想アドレス（16進数: 0x140001000）"
+                        },
+                        "section": {
+                            "type": "string",
+                            "description": "一意性を確認するセクション名（省略時は全セクション）"
+                        },
+                        "max_length": {
+                            "type": "integer",
+                            "description": "シグネチャの最大長（バイト）",
+                            "default": 128
+                        }
+                    },
+                    "required": ["path", "address"]
+                }
+            }),
+
+            // バイナリ全体の一括デコンパイル（バックグラウンドジョブ）
+            json!({
+                "name": "decompile_all",
+                "description": "検出した全関数をバックグラウンドで並列デコンパイルしてキャッシュに格納。ジョブIDを即座に返し、進捗はnotifications/progressで通知。結果はdecompile_function_cachedで取得",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "バイナリファイルパス"
+                        },
+                        "max_instructions": {
+                            "type": "integer",
+                            "description": "1関数あたりの最大命令数",
+                            "default": 1000
+                        },
+                        "function_timeout_ms": {
+                            "type": "integer",
+                            "description": "1関数あたりの制限時間（ミリ秒）",
+                            "default": 10000
+                        },
+                        "max_pcode_ops": {
+                            "type": "integer",
+                            "description": "1関数あたりのP-code命令数の上限",
+                            "default": 200000
+                        }
+                    },
+                    "required": ["path"]
+                }
+            }),
+
+            // 一括デコンパイルジョブの状態
+            json!({
+                "name": "get_decompile_all_status",
+                "description": "decompile_allジョブの進捗と結果（失敗した関数の一覧を含む）を取得",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "job_id": {
+                            "type": "integer",
+                            "description": "decompile_allが返したジョブID"
+                        }
+                    },
+                    "required": ["job_id"]
+                }
             })
     ];
 
@@ -375,6 +606,25 @@ async fn handle_list_tools(ghidra_enabled: bool) -> Result<Value> {
                 "required": ["path", "function_address"]
             }
         }));
+        tools.push(json!({
+            "name": "decompile_batch_with_ghidra",
+            "description": "Ghidra Headlessで複数の関数を1回の起動でまとめてデコンパイル（C・シグネチャ・ローカル変数・エラーを関数ごとに返す）",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "バイナリファイルパス"
+                    },
+                    "function_addresses": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "関数のアドレスの配列（16進数: [\"0x140001000\", \"0x140001200\"]）"
+                    }
+                },
+                "required": ["path", "function_addresses"]
+            }
+        }));
     }
 
     Ok(json!({
@@ -386,6 +636,8 @@ async fn handle_tool_call(
     params: Option<Value>,
     analyzer: Arc<Mutex<HierarchicalAnalyzer>>,
     ghidra: Option<Arc<Mutex<GhidraHeadless>>>,
+    jobs: BatchJobs,
+    outbound: &UnboundedSender<String>,
 ) -> Result<Value> {
     let params = params.ok_or_else(|| anyhow::anyhow!("Missing params"))?;
     let tool_name = params["name"]
@@ -523,6 +775,7 @@ async fn handle_tool_call(
                 "instruction_count": pcodes.len(),
                 "control_structure": structure_str,
                 "type_inference": type_info,
+                "type_conflicts": type_conflicts_to_json(type_inference.get_conflicts()),
                 "loops_detected": analyzer.get_loops().len(),
                 "backend": "Native Decompiler (P-code + SSA + Type Inference)"
             })
@@ -600,26 +853,294 @@ async fn handle_tool_call(
                 file_offset,
                 max_instructions,
             )?;
+            let annotation = decompiler.function_annotation(&binary_data, address, file_offset, max_instructions);
 
             let cache_stats = decompiler.get_cache_stats();
 
             json!({
                 "function_address": format!("0x{:X}", result.address),
+                "function_hash": format!("{:016x}", result.function_hash),
+                "annotation": annotation,
                 "pcode_count": result.pcode_count,
                 "block_count": result.block_count,
                 "type_count": result.type_count,
                 "loop_count": result.loop_count,
                 "control_structure": result.control_structure,
+                "type_conflicts": type_conflicts_to_json(&result.type_conflicts),
                 "cached_at": result.cached_at,
                 "cache_stats": {
                     "memory_cached_binaries": cache_stats.memory_cached_binaries,
                     "disk_cached_binaries": cache_stats.disk_cached_binaries,
+                    "disk_cached_functions": cache_stats.disk_cached_functions,
+                    "disk_bytes": cache_stats.disk_bytes,
                     "cache_directory": cache_stats.cache_directory
                 },
                 "backend": "Native Decompiler with Cache"
             })
         }
 
+        "annotate_function" => {
+            use decompiler_prototype::{FunctionAnnotation, ParallelDecompiler};
+
+            let path = arguments["path"].as_str().unwrap();
+            let addr_str = arguments["function_address"].as_str().unwrap();
+            let offset_str = arguments["file_offset"].as_str().unwrap();
+            let max_instructions = arguments["max_instructions"].as_u64().unwrap_or(1000) as usize;
+
+            let address = if addr_str.starts_with("0x") {
+                u64::from_str_radix(&addr_str[2..], 16)?
+            } else {
+                addr_str.parse()?
+            };
+
+            let file_offset = if offset_str.starts_with("0x") {
+                usize::from_str_radix(&offset_str[2..], 16)?
+            } else {
+                offset_str.parse()?
+            };
+
+            let annotation = FunctionAnnotation {
+                name: arguments["name"].as_str().map(str::to_string),
+                comment: arguments["comment"].as_str().map(str::to_string),
+            };
+
+            let cache_dir = std::env::temp_dir().join("ghidra_mcp_cache");
+            let decompiler = ParallelDecompiler::new(&cache_dir)?;
+            let binary_data = std::fs::read(path)?;
+            let function_hash = decompiler.annotate_function(&binary_data, address, file_offset, max_instructions, &annotation)?;
+
+            json!({
+                "function_address": format!("0x{:X}", address),
+                "function_hash": format!("{:016x}", function_hash),
+                "annotation": annotation
+            })
+        }
+
+        "diff_binaries" => {
+            let old_path = arguments["old_path"].as_str().unwrap().to_string();
+            let new_path = arguments["new_path"].as_str().unwrap().to_string();
+            let page = arguments["page"].as_u64().unwrap_or(0) as usize;
+            let page_size = arguments["page_size"].as_u64().unwrap_or(50) as usize;
+            let max_instructions = arguments["max_instructions"].as_u64().unwrap_or(1000) as usize;
+            let status = match arguments["status"].as_str() {
+                Some(name) => Some(
+                    decompiler_prototype::DiffStatus::parse(name)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown status: {}", name))?,
+                ),
+                None => None,
+            };
+
+            let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            let key: DiffKey = (old_path.clone(), new_path.clone(), max_instructions, modified(&old_path), modified(&new_path));
+            let cached = LAST_DIFF
+                .lock()
+                .ok()
+                .and_then(|last| last.as_ref().filter(|(k, _)| *k == key).map(|(_, diff)| Arc::clone(diff)));
+
+            let diff = match cached {
+                Some(diff) => diff,
+                None => {
+                    let (old_functions, new_functions) = {
+                        let mut analyzer = analyzer.lock().await;
+                        (analyzer.function_entry_points(&old_path)?, analyzer.function_entry_points(&new_path)?)
+                    };
+                    let diff = tokio::task::spawn_blocking(move || -> Result<BinaryDiff> {
+                        let old_data = std::fs::read(&old_path)?;
+                        let new_data = std::fs::read(&new_path)?;
+                        decompiler_prototype::diff_binaries(&old_data, &old_functions, &new_data, &new_functions, max_instructions)
+                    })
+                    .await??;
+                    let diff = Arc::new(diff);
+                    if let Ok(mut last) = LAST_DIFF.lock() {
+                        *last = Some((key, Arc::clone(&diff)));
+                    }
+                    diff
+                }
+            };
+
+            serde_json::to_value(diff.page(page, page_size, status))?
+        }
+
+        "scan_signature" => {
+            let path = arguments["path"].as_str().unwrap();
+            let section = arguments["section"].as_str();
+            let max_results = arguments["max_results"].as_u64().unwrap_or(100) as usize;
+
+            let patterns: Vec<&str> = match (arguments["patterns"].as_array(), arguments["pattern"].as_str()) {
+                (Some(patterns), _) => patterns.iter().filter_map(|p| p.as_str()).collect(),
+                (None, Some(pattern)) => vec![pattern],
+                (None, None) => return Err(anyhow::anyhow!("Missing pattern or patterns")),
+            };
+            let signatures = patterns
+                .iter()
+                .map(|pattern| signature::Signature::parse(pattern))
+                .collect::<Result<Vec<_>>>()?;
+
+            let binary_data = std::fs::read(path)?;
+            let matches = signature::scan_image_multi(&binary_data, &signatures, section)?;
+
+            let results: Vec<Value> = signatures
+                .iter()
+                .zip(&matches)
+                .map(|(signature, matches)| json!({
+                    "pattern": signature.to_string(),
+                    "total_count": matches.len(),
+                    "matches": matches.iter().take(max_results).map(|m| json!({
+                        "address": format!("0x{:X}", m.address),
+                        "file_offset": format!("0x{:X}", m.file_offset),
+                        "section": m.section
+                    })).collect::<Vec<_>>()
+                }))
+                .collect();
+
+            if arguments["patterns"].is_array() {
+                json!({ "results": results })
+            } else {
+                results.into_iter().next().unwrap_or(Value::Null)
+            }
+        }
+
+        "make_signature" => {
+            let path = arguments["path"].as_str().unwrap();
+            let addr_str = arguments["address"].as_str().unwrap();
+            let section = arguments["section"].as_str();
+            let max_length = arguments["max_length"]
+                .as_u64()
+                .map(|n| n as usize)
+                .unwrap_or(signature::DEFAULT_MAX_SIGNATURE_LENGTH);
+
+            let address = if addr_str.starts_with("0x") {
+                u64::from_str_radix(&addr_str[2..], 16)?
+            } else {
+                addr_str.parse()?
+            };
+
+            let binary_data = std::fs::read(path)?;
+            let generated = signature::make_signature(&binary_data, address, section, max_length)?;
+
+            json!({
+                "address": format!("0x{:X}", generated.address),
+                "signature": generated.signature,
+                "length": generated.length,
+                "wildcards": generated.wildcards,
+                "instruction_count": generated.instruction_count
+            })
+        }
+
+        "decompile_all" => {
+            use decompiler_prototype::{BatchOptions, ParallelDecompiler};
+            use std::path::Path;
+
+            let path = arguments["path"].as_str().unwrap().to_string();
+            let defaults = BatchOptions::default();
+            let options = BatchOptions {
+                max_instructions: arguments["max_instructions"].as_u64().map(|n| n as usize).unwrap_or(defaults.max_instructions),
+                function_timeout: arguments["function_timeout_ms"].as_u64().map(std::time::Duration::from_millis).unwrap_or(defaults.function_timeout),
+                max_pcode_ops: arguments["max_pcode_ops"].as_u64().map(|n| n as usize).unwrap_or(defaults.max_pcode_ops),
+            };
+
+            // 階層解析器が検出した関数一覧を対象にする
+            let functions = {
+                let mut analyzer = analyzer.lock().await;
+                analyzer.function_entry_points(&path)?
+            };
+            let total = functions.len();
+
+            let job_id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+            let progress_token = match &params["_meta"]["progressToken"] {
+                Value::Null => json!(format!("decompile_all-{}", job_id)),
+                token => token.clone(),
+            };
+
+            if let Ok(mut jobs) = jobs.lock() {
+                jobs.insert(job_id, BatchJobState::Running(BatchProgress {
+                    completed: 0,
+                    total,
+                    cached: 0,
+                    failed: 0,
+                    last_address: 0,
+                }));
+            }
+
+            let job_jobs = Arc::clone(&jobs);
+            let notifier = outbound.downgrade();
+            let token = progress_token.clone();
+            tokio::task::spawn_blocking(move || {
+                let cache_dir = std::env::temp_dir().join("ghidra_mcp_cache");
+                // 通知は全体の約1%ごとに送る
+                let step = (total / 100).max(1);
+
+                let outcome = ParallelDecompiler::new(&cache_dir).and_then(|decompiler| {
+                    let binary_data = std::fs::read(&path)?;
+                    decompiler.decompile_all(Some(Path::new(&path)), &binary_data, &functions, &options, &|progress| {
+                        if let Ok(mut jobs) = job_jobs.lock() {
+                            jobs.insert(job_id, BatchJobState::Running(progress.clone()));
+                        }
+                        if progress.completed % step == 0 || progress.completed == progress.total {
+                            send_notification(&notifier, "notifications/progress", json!({
+                                "progressToken": token,
+                                "progress": progress.completed,
+                                "total": progress.total,
+                                "message": format!(
+                                    "decompiled {}/{} (cached {}, failed {})",
+                                    progress.completed, progress.total, progress.cached, progress.failed
+                                )
+                            }));
+                        }
+                    })
+                });
+
+                let state = match outcome {
+                    Ok(report) => BatchJobState::Completed(report),
+                    Err(e) => {
+                        error!("decompile_all job {} failed: {}", job_id, e);
+                        BatchJobState::Failed(e.to_string())
+                    }
+                };
+                if let Ok(mut jobs) = job_jobs.lock() {
+                    jobs.insert(job_id, state);
+                }
+            });
+
+            json!({
+                "job_id": job_id,
+                "status": "running",
+                "function_count": total,
+                "progress_token": progress_token
+            })
+        }
+
+        "get_decompile_all_status" => {
+            let job_id = arguments["job_id"]
+                .as_u64()
+                .ok_or_else(|| anyhow::anyhow!("Missing job_id"))?;
+
+            let state = jobs
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Job table lock poisoned"))?
+                .get(&job_id)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Unknown job: {}", job_id))?;
+
+            match state {
+                BatchJobState::Running(progress) => json!({
+                    "job_id": job_id,
+                    "status": "running",
+                    "progress": progress
+                }),
+                BatchJobState::Completed(report) => json!({
+                    "job_id": job_id,
+                    "status": "completed",
+                    "report": report
+                }),
+                BatchJobState::Failed(message) => json!({
+                    "job_id": job_id,
+                    "status": "failed",
+                    "error": message
+                }),
+            }
+        }
+
         "decompile_with_ghidra" => {
             if let Some(ref ghidra) = ghidra {
                 let path = arguments["path"].as_str().unwrap();
@@ -646,6 +1167,45 @@ async fn handle_tool_call(
             }
         }
 
+        "decompile_batch_with_ghidra" => {
+            if let Some(ref ghidra) = ghidra {
+                let path = arguments["path"].as_str().unwrap();
+                let mut addresses = Vec::new();
+                for addr in arguments["function_addresses"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+                    let addr_str = addr.as_str().ok_or_else(|| anyhow::anyhow!("function_addresses must be strings"))?;
+                    addresses.push(if addr_str.starts_with("0x") {
+                        u64::from_str_radix(&addr_str[2..], 16)?
+                    } else {
+                        addr_str.parse()?
+                    });
+                }
+
+                let ghidra = ghidra.lock().await;
+                let results = ghidra.decompile_batch(path, &addresses)?;
+                let decompiled = results.iter().filter(|r| r.c_code.is_some()).count();
+
+                json!({
+                    "requested": addresses.len(),
+                    "decompiled": decompiled,
+                    "failed": results.len() - decompiled,
+                    "functions": results.iter().map(|r| json!({
+                        "function_address": format!("0x{:x}", r.address),
+                        "name": r.name,
+                        "signature": r.signature,
+                        "decompiled_code": r.c_code,
+                        "local_variables": r.local_variables,
+                        "error": r.error,
+                        "cached": r.cached
+                    })).collect::<Vec<_>>(),
+                    "backend": "Ghidra Headless"
+                })
+            } else {
+                json!({
+                    "error": "Ghidra Headless not enabled. Set GHIDRA_PATH environment variable."
+                })
+            }
+        }
+
         _ => {
             return Err(anyhow::anyhow!("Unknown tool: {}", tool_name));
         }
@@ -658,3 +1218,47 @@ async fn handle_tool_call(
         }]
     }))
 }
+
+/// JSON-RPC通知を送信（サーバーが終了していれば何もしない）
+fn send_notification(outbound: &WeakUnboundedSender<String>, method: &str, params: Value) {
+    if let Some(sender) = outbound.upgrade() {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+        let _ = sender.send(notification.to_string());
+    }
+}
+
+/// 型衝突をMCP出力用のJSONに整形
+fn type_conflicts_to_json(conflicts: &[decompiler_prototype::TypeConflict]) -> Value {
+    let entries: Vec<Value> = conflicts
+        .iter()
+        .map(|conflict| {
+            let origins: Vec<Value> = conflict.origins
+                .iter()
+                .map(|origin| {
+                    json!({
+                        "address": format!("0x{:x}", origin.address),
+                        "opcode": origin.opcode.to_string(),
+                        "type": origin.type_.to_c_string(),
+                        "reason": origin.reason
+                    })
+                })
+                .collect();
+
+            json!({
+                "varnode": conflict.varnode.to_string(),
+                "competing_types": conflict.competing_types
+                    .iter()
+                    .map(|ty| ty.to_c_string())
+                    .collect::<Vec<_>>(),
+                "resolved_type": conflict.resolved.to_c_string(),
+                "origins": origins
+            })
+        })
+        .collect();
+
+    Value::Array(entries)
+}
@@ -11,7 +11,12 @@ use windows::{
 };
 
 use anyhow::{Result, Context, bail};
+use crate::signature::{find_masked, Signature};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
 use std::mem;
+use std::path::Path;
 
 /// プロセス情報
 #[derive(Debug, Clone)]
@@ -29,6 +34,21 @@ pub struct MemoryRegion {
     pub protection: u32,
 }
 
+/// ロード済みモジュール（ポインタチェーンの起点になる静的領域）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub base_address: usize,
+    pub size: usize,
+}
+
+impl ModuleInfo {
+    /// アドレスがモジュールの範囲内か
+    pub fn contains(&self, address: usize) -> bool {
+        address >= self.base_address && address - self.base_address < self.size
+    }
+}
+
 /// メモリスキャナー
 pub struct MemoryScanner {
     #[cfg(windows)]
@@ -47,12 +67,15 @@ impl MemoryScanner {
     /// PIDからスキャナーを作成
     #[cfg(windows)]
     pub fn from_pid(pid: u32) -> Result<Self> {
+        // 書き込み権限で開けなければ読み取り専用で開く
         let process_handle = unsafe {
             OpenProcess(
-                PROCESS_VM_READ | PROCESS_QUERY_INFORMATION,
+                PROCESS_VM_READ | PROCESS_VM_WRITE | PROCESS_VM_OPERATION | PROCESS_QUERY_INFORMATION,
                 false,
                 pid,
-            ).context("Failed to open process")?
+            )
+            .or_else(|_| OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, false, pid))
+            .context("Failed to open process")?
         };
 
         let base_address = Self::get_module_base_address(process_handle)?;
@@ -126,6 +149,44 @@ impl MemoryScanner {
         }
     }
 
+    /// ロード済みモジュールを列挙
+    #[cfg(windows)]
+    pub fn enumerate_modules(&self) -> Result<Vec<ModuleInfo>> {
+        let mut modules = Vec::new();
+
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(
+                TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32,
+                self.process_info.pid,
+            )?;
+
+            let mut entry = MODULEENTRY32W {
+                dwSize: mem::size_of::<MODULEENTRY32W>() as u32,
+                ..Default::default()
+            };
+
+            if Module32FirstW(snapshot, &mut entry).is_ok() {
+                loop {
+                    modules.push(ModuleInfo {
+                        name: String::from_utf16_lossy(&entry.szModule)
+                            .trim_end_matches('\0')
+                            .to_string(),
+                        base_address: entry.modBaseAddr as usize,
+                        size: entry.modBaseSize as usize,
+                    });
+
+                    if Module32NextW(snapshot, &mut entry).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            let _ = CloseHandle(snapshot);
+        }
+
+        Ok(modules)
+    }
+
     /// メモリリージョンを列挙
     #[cfg(windows)]
     pub fn enumerate_regions(&self) -> Result<Vec<MemoryRegion>> {
@@ -185,15 +246,35 @@ impl MemoryScanner {
         Ok(buffer)
     }
 
-    /// パターンマッチング（バイトシーケンス検索）
+    /// メモリに書き込み
     #[cfg(windows)]
+    pub fn write_memory(&self, address: usize, data: &[u8]) -> Result<()> {
+        let mut bytes_written = 0;
+
+        unsafe {
+            WriteProcessMemory(
+                self.process_handle,
+                address as *const _,
+                data.as_ptr() as *const _,
+                data.len(),
+                Some(&mut bytes_written),
+            )?;
+        }
+
+        if bytes_written != data.len() {
+            bail!("Partial write at 0x{:X}: {} of {} bytes", address, bytes_written, data.len());
+        }
+        Ok(())
+    }
+
+    /// パターンマッチング（バイトシーケンス検索）
     pub fn scan_pattern(&self, pattern: &[u8], mask: Option<&[bool]>) -> Result<Vec<usize>> {
         let regions = self.enumerate_regions()?;
         let mut results = Vec::new();
 
         for region in regions {
             // 大きすぎるリージョンはスキップ（100MB以上）
-            if region.size > 100 * 1024 * 1024 {
+            if region.size > MAX_SCAN_REGION_SIZE {
                 continue;
             }
 
@@ -208,53 +289,59 @@ impl MemoryScanner {
         Ok(results)
     }
 
-    /// データ内でパターンを検索
-    fn find_pattern(data: &[u8], pattern: &[u8], mask: &[bool]) -> Vec<usize> {
-        let mut results = Vec::new();
+    /// IDA形式のシグネチャでスキャン
+    pub fn scan_signature(&self, signature: &Signature) -> Result<Vec<usize>> {
+        self.scan_pattern(&signature.bytes, Some(&signature.mask))
+    }
 
-        if pattern.len() > data.len() {
-            return results;
-        }
+    /// 複数のシグネチャでまとめてスキャン（各リージョンは1回だけ読み込む）
+    pub fn scan_signatures(&self, signatures: &[Signature]) -> Result<Vec<Vec<usize>>> {
+        use crate::signature::MultiPatternScanner;
 
-        for i in 0..=(data.len() - pattern.len()) {
-            let mut matched = true;
-            for j in 0..pattern.len() {
-                if mask[j] && data[i + j] != pattern[j] {
-                    matched = false;
-                    break;
-                }
+        let scanner = MultiPatternScanner::new(signatures);
+        let regions = self.enumerate_regions()?;
+        let mut results = vec![Vec::new(); signatures.len()];
+
+        for region in regions {
+            // 大きすぎるリージョンはスキップ（100MB以上）
+            if region.size > MAX_SCAN_REGION_SIZE {
+                continue;
             }
-            if matched {
-                results.push(i);
+
+            if let Ok(data) = self.read_memory(region.base_address, region.size) {
+                for (index, offset) in scanner.scan(&data) {
+                    results[index].push(region.base_address + offset);
+                }
             }
         }
 
-        results
+        Ok(results)
+    }
+
+    /// データ内でパターンを検索
+    fn find_pattern(data: &[u8], pattern: &[u8], mask: &[bool]) -> Vec<usize> {
+        find_masked(data, pattern, mask)
     }
 
     /// 4バイト整数値でスキャン
-    #[cfg(windows)]
     pub fn scan_int32(&self, value: i32) -> Result<Vec<usize>> {
         let pattern = value.to_le_bytes();
         self.scan_pattern(&pattern, None)
     }
 
     /// 8バイト整数値でスキャン
-    #[cfg(windows)]
     pub fn scan_int64(&self, value: i64) -> Result<Vec<usize>> {
         let pattern = value.to_le_bytes();
         self.scan_pattern(&pattern, None)
     }
 
     /// 浮動小数点数でスキャン
-    #[cfg(windows)]
     pub fn scan_float(&self, value: f32) -> Result<Vec<usize>> {
         let pattern = value.to_le_bytes();
         self.scan_pattern(&pattern, None)
     }
 
     /// 文字列でスキャン
-    #[cfg(windows)]
     pub fn scan_string(&self, text: &str) -> Result<Vec<usize>> {
         self.scan_pattern(text.as_bytes(), None)
     }
@@ -269,46 +356,776 @@ impl Drop for MemoryScanner {
     }
 }
 
-// Windows以外のプラットフォーム用のスタブ実装
-#[cfg(not(windows))]
+/// /proc/<pid>/maps の1行
+#[cfg(target_os = "linux")]
+struct MapsEntry {
+    start: usize,
+    end: usize,
+    perms: String,
+    path: String,
+}
+
+// Linux: /proc/<pid>/maps と /proc/<pid>/mem で読み書きする
+#[cfg(target_os = "linux")]
+impl MemoryScanner {
+    /// プロセス名からスキャナーを作成
+    pub fn from_process_name(name: &str) -> Result<Self> {
+        let pid = Self::find_process_by_name(name)?;
+        Self::from_pid(pid)
+    }
+
+    /// PIDからスキャナーを作成
+    pub fn from_pid(pid: u32) -> Result<Self> {
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid))
+            .with_context(|| format!("Process not found: {}", pid))?
+            .trim()
+            .to_string();
+
+        let mut scanner = Self {
+            process_info: ProcessInfo {
+                pid,
+                name,
+                base_address: 0,
+            },
+        };
+
+        // 実行ファイルの最初のマッピングをベースアドレスとする
+        let exe = fs::read_link(format!("/proc/{}/exe", pid)).ok();
+        scanner.process_info.base_address = scanner
+            .read_maps()?
+            .iter()
+            .find(|entry| exe.as_deref().is_some_and(|exe| Path::new(&entry.path) == exe))
+            .map(|entry| entry.start)
+            .unwrap_or(0);
+
+        Ok(scanner)
+    }
+
+    /// プロセス名からPIDを検索
+    fn find_process_by_name(name: &str) -> Result<u32> {
+        let name = name.to_lowercase();
+        for entry in fs::read_dir("/proc")?.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+                continue;
+            };
+            if let Ok(comm) = fs::read_to_string(entry.path().join("comm")) {
+                if comm.trim().to_lowercase().contains(&name) {
+                    return Ok(pid);
+                }
+            }
+        }
+        bail!("Process not found: {}", name);
+    }
+
+    /// /proc/<pid>/maps を読む
+    fn read_maps(&self) -> Result<Vec<MapsEntry>> {
+        let maps = fs::read_to_string(format!("/proc/{}/maps", self.process_info.pid))
+            .context("Failed to read process memory map")?;
+
+        let mut entries = Vec::new();
+        for line in maps.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(range), Some(perms)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let Some((start, end)) = range.split_once('-') else {
+                continue;
+            };
+            // offset, dev, inode の後がパス
+            let path = fields.nth(3).unwrap_or("").to_string();
+            entries.push(MapsEntry {
+                start: usize::from_str_radix(start, 16)?,
+                end: usize::from_str_radix(end, 16)?,
+                perms: perms.to_string(),
+                path,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// ロード済みモジュール（ファイルをマップした領域）を列挙
+    pub fn enumerate_modules(&self) -> Result<Vec<ModuleInfo>> {
+        let mut modules: Vec<(String, ModuleInfo)> = Vec::new();
+
+        for entry in self.read_maps()? {
+            if !entry.path.starts_with('/') {
+                continue;
+            }
+            match modules.iter_mut().find(|(path, _)| *path == entry.path) {
+                Some((_, module)) => {
+                    module.size = entry.end.max(module.base_address + module.size) - module.base_address;
+                },
+                None => {
+                    let name = Path::new(&entry.path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    modules.push((
+                        entry.path.clone(),
+                        ModuleInfo {
+                            name,
+                            base_address: entry.start,
+                            size: entry.end - entry.start,
+                        },
+                    ));
+                },
+            }
+        }
+
+        Ok(modules.into_iter().map(|(_, module)| module).collect())
+    }
+
+    /// メモリリージョンを列挙（保護属性はWindowsのPAGE_*に合わせる）
+    pub fn enumerate_regions(&self) -> Result<Vec<MemoryRegion>> {
+        Ok(self
+            .read_maps()?
+            .into_iter()
+            .filter(|entry| entry.perms.starts_with('r'))
+            .map(|entry| {
+                let protection = match (entry.perms.as_bytes()[1] == b'w', entry.perms.as_bytes()[2] == b'x') {
+                    (false, false) => 0x02, // PAGE_READONLY
+                    (true, false) => 0x04,  // PAGE_READWRITE
+                    (false, true) => 0x20,  // PAGE_EXECUTE_READ
+                    (true, true) => 0x40,   // PAGE_EXECUTE_READWRITE
+                };
+                MemoryRegion {
+                    base_address: entry.start,
+                    size: entry.end - entry.start,
+                    protection,
+                }
+            })
+            .collect())
+    }
+
+    /// メモリを読み取り
+    pub fn read_memory(&self, address: usize, size: usize) -> Result<Vec<u8>> {
+        use std::os::unix::fs::FileExt;
+
+        let file = fs::File::open(format!("/proc/{}/mem", self.process_info.pid))
+            .context("Failed to open process memory")?;
+        let mut buffer = vec![0u8; size];
+        let mut bytes_read = 0;
+
+        while bytes_read < size {
+            match file.read_at(&mut buffer[bytes_read..], (address + bytes_read) as u64) {
+                Ok(0) => break,
+                Ok(n) => bytes_read += n,
+                Err(e) if bytes_read == 0 => return Err(e.into()),
+                Err(_) => break,
+            }
+        }
+
+        buffer.truncate(bytes_read);
+        Ok(buffer)
+    }
+
+    /// メモリに書き込み
+    pub fn write_memory(&self, address: usize, data: &[u8]) -> Result<()> {
+        use std::os::unix::fs::FileExt;
+
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .open(format!("/proc/{}/mem", self.process_info.pid))
+            .context("Failed to open process memory for writing")?;
+        file.write_all_at(data, address as u64)
+            .with_context(|| format!("Failed to write {} bytes at 0x{:X}", data.len(), address))?;
+        Ok(())
+    }
+}
+
+// WindowsとLinux以外のプラットフォーム用のスタブ実装
+#[cfg(not(any(windows, target_os = "linux")))]
 impl MemoryScanner {
     pub fn from_process_name(_name: &str) -> Result<Self> {
-        bail!("Memory scanning is only supported on Windows");
+        bail!("Memory scanning is only supported on Windows and Linux");
     }
 
     pub fn from_pid(_pid: u32) -> Result<Self> {
-        bail!("Memory scanning is only supported on Windows");
+        bail!("Memory scanning is only supported on Windows and Linux");
+    }
+
+    pub fn enumerate_modules(&self) -> Result<Vec<ModuleInfo>> {
+        bail!("Memory scanning is only supported on Windows and Linux");
     }
 
     pub fn enumerate_regions(&self) -> Result<Vec<MemoryRegion>> {
-        bail!("Memory scanning is only supported on Windows");
+        bail!("Memory scanning is only supported on Windows and Linux");
     }
 
     pub fn read_memory(&self, _address: usize, _size: usize) -> Result<Vec<u8>> {
-        bail!("Memory scanning is only supported on Windows");
+        bail!("Memory scanning is only supported on Windows and Linux");
+    }
+
+    pub fn write_memory(&self, _address: usize, _data: &[u8]) -> Result<()> {
+        bail!("Memory scanning is only supported on Windows and Linux");
+    }
+}
+
+/// スキャンで読み込むリージョンの上限（これより大きいリージョンはスキップ）
+pub(crate) const MAX_SCAN_REGION_SIZE: usize = 100 * 1024 * 1024;
+
+/// 候補の値をまとめて読むときの1回の読み込みサイズの上限
+const MAX_READ_SPAN: usize = 64 * 1024;
+
+/// スキャンセッションファイルの識別子
+const SESSION_MAGIC: &[u8; 4] = b"MSCN";
+
+/// スキャンセッションファイルの形式バージョン
+const SESSION_FORMAT_VERSION: u32 = 1;
+
+/// スキャン対象のメモリ（テストでは偽のメモリに差し替える）
+pub trait MemorySource {
+    fn regions(&self) -> Result<Vec<MemoryRegion>>;
+    fn read(&self, address: usize, size: usize) -> Result<Vec<u8>>;
+
+    /// ロード済みモジュール（列挙できないソースでは空）
+    fn modules(&self) -> Result<Vec<ModuleInfo>> {
+        Ok(Vec::new())
+    }
+
+    /// メモリに書き込む（読み取り専用のソースではエラー）
+    fn write(&self, address: usize, _data: &[u8]) -> Result<()> {
+        bail!("Memory at 0x{:X} is read-only", address);
+    }
+}
+
+impl MemorySource for MemoryScanner {
+    fn regions(&self) -> Result<Vec<MemoryRegion>> {
+        self.enumerate_regions()
     }
 
-    pub fn scan_pattern(&self, _pattern: &[u8], _mask: Option<&[bool]>) -> Result<Vec<usize>> {
-        bail!("Memory scanning is only supported on Windows");
+    fn modules(&self) -> Result<Vec<ModuleInfo>> {
+        self.enumerate_modules()
     }
 
-    pub fn scan_int32(&self, _value: i32) -> Result<Vec<usize>> {
-        bail!("Memory scanning is only supported on Windows");
+    fn read(&self, address: usize, size: usize) -> Result<Vec<u8>> {
+        self.read_memory(address, size)
     }
 
-    pub fn scan_int64(&self, _value: i64) -> Result<Vec<usize>> {
-        bail!("Memory scanning is only supported on Windows");
+    fn write(&self, address: usize, data: &[u8]) -> Result<()> {
+        self.write_memory(address, data)
     }
+}
+
+/// スキャンする値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanValueType {
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+/// スキャンで比較する値
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScanValue {
+    Int(i64),
+    Float(f64),
+}
 
-    pub fn scan_float(&self, _value: f32) -> Result<Vec<usize>> {
-        bail!("Memory scanning is only supported on Windows");
+impl ScanValue {
+    fn as_f64(self) -> f64 {
+        match self {
+            ScanValue::Int(v) => v as f64,
+            ScanValue::Float(v) => v,
+        }
     }
 
-    pub fn scan_string(&self, _text: &str) -> Result<Vec<usize>> {
-        bail!("Memory scanning is only supported on Windows");
+    fn compare(self, other: ScanValue) -> Option<Ordering> {
+        match (self, other) {
+            (ScanValue::Int(a), ScanValue::Int(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
     }
 }
 
+impl std::fmt::Display for ScanValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanValue::Int(v) => write!(f, "{}", v),
+            ScanValue::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl ScanValueType {
+    /// 型名から変換（i8/i16/i32/i64/f32/f64、int/floatも可）
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "i8" | "byte" => ScanValueType::I8,
+            "i16" | "short" => ScanValueType::I16,
+            "i32" | "int" => ScanValueType::I32,
+            "i64" | "long" => ScanValueType::I64,
+            "f32" | "float" => ScanValueType::F32,
+            "f64" | "double" => ScanValueType::F64,
+            _ => bail!("Unknown value type: {}", name),
+        })
+    }
+
+    /// 値のバイト数
+    pub fn size(self) -> usize {
+        match self {
+            ScanValueType::I8 => 1,
+            ScanValueType::I16 => 2,
+            ScanValueType::I32 | ScanValueType::F32 => 4,
+            ScanValueType::I64 | ScanValueType::F64 => 8,
+        }
+    }
+
+    /// スキャンのアライメント（8バイト型も4バイト境界で探す）
+    pub fn alignment(self) -> usize {
+        self.size().min(4)
+    }
+
+    /// メモリ上のバイト列を値に変換
+    pub fn decode(self, bytes: &[u8]) -> ScanValue {
+        let mut buffer = [0u8; 8];
+        buffer[..self.size()].copy_from_slice(&bytes[..self.size()]);
+        match self {
+            ScanValueType::I8 => ScanValue::Int(buffer[0] as i8 as i64),
+            ScanValueType::I16 => ScanValue::Int(i16::from_le_bytes([buffer[0], buffer[1]]) as i64),
+            ScanValueType::I32 => ScanValue::Int(i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as i64),
+            ScanValueType::I64 => ScanValue::Int(i64::from_le_bytes(buffer)),
+            ScanValueType::F32 => ScanValue::Float(f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64),
+            ScanValueType::F64 => ScanValue::Float(f64::from_le_bytes(buffer)),
+        }
+    }
+
+    /// 値をメモリ上のバイト列に変換（整数型は型の幅に切り詰める）
+    pub fn encode(self, value: ScanValue) -> Vec<u8> {
+        let (int, float) = match value {
+            ScanValue::Int(v) => (v, v as f64),
+            ScanValue::Float(v) => (v as i64, v),
+        };
+        match self {
+            ScanValueType::F32 => (float as f32).to_le_bytes().to_vec(),
+            ScanValueType::F64 => float.to_le_bytes().to_vec(),
+            _ => int.to_le_bytes()[..self.size()].to_vec(),
+        }
+    }
+
+    /// 文字列を値に変換（浮動小数点型は型の精度に丸める）
+    pub fn parse_value(self, text: &str) -> Result<ScanValue> {
+        Ok(match self {
+            ScanValueType::F32 => ScanValue::Float(text.parse::<f32>().context("Invalid float value")? as f64),
+            ScanValueType::F64 => ScanValue::Float(text.parse::<f64>().context("Invalid float value")?),
+            _ => ScanValue::Int(text.parse::<i64>().context("Invalid integer value")?),
+        })
+    }
+}
+
+/// スキャンの条件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanFilter {
+    /// 値が一致
+    Exact(ScanValue),
+    /// 値が範囲内（両端を含む）
+    Range(ScanValue, ScanValue),
+    /// 初期値が不明（最初のスキャンのみ。全位置を候補にする）
+    Unknown,
+    /// 前回から変化した
+    Changed,
+    /// 前回から変化していない
+    Unchanged,
+    /// 前回より増えた
+    Increased,
+    /// 前回より減った
+    Decreased,
+}
+
+impl ScanFilter {
+    /// コマンドの引数から変換（例: ["exact", "100"], ["range", "1", "10"], ["decreased"]）
+    pub fn parse(args: &[&str], value_type: ScanValueType) -> Result<Self> {
+        Ok(match args {
+            ["exact", value] => ScanFilter::Exact(value_type.parse_value(value)?),
+            [value] if value.parse::<f64>().is_ok() => ScanFilter::Exact(value_type.parse_value(value)?),
+            ["range", min, max] => ScanFilter::Range(value_type.parse_value(min)?, value_type.parse_value(max)?),
+            ["unknown"] => ScanFilter::Unknown,
+            ["changed"] => ScanFilter::Changed,
+            ["unchanged"] => ScanFilter::Unchanged,
+            ["increased"] => ScanFilter::Increased,
+            ["decreased"] => ScanFilter::Decreased,
+            _ => bail!(
+                "Invalid scan filter: {} (exact <v>, range <min> <max>, unknown, changed, unchanged, increased, decreased)",
+                args.join(" ")
+            ),
+        })
+    }
+
+    /// 前回の値と比較する条件か
+    fn needs_previous(self) -> bool {
+        matches!(self, ScanFilter::Changed | ScanFilter::Unchanged | ScanFilter::Increased | ScanFilter::Decreased)
+    }
+
+    /// 現在の値（と前回の値）が条件を満たすか
+    fn accepts(self, current: ScanValue, previous: Option<ScanValue>) -> bool {
+        let against_previous = |expected: &[Ordering]| {
+            previous
+                .and_then(|previous| current.compare(previous))
+                .is_some_and(|ordering| expected.contains(&ordering))
+        };
+
+        match self {
+            ScanFilter::Exact(value) => current.compare(value) == Some(Ordering::Equal),
+            ScanFilter::Range(min, max) => {
+                current.compare(min).is_some_and(|o| o != Ordering::Less)
+                    && current.compare(max).is_some_and(|o| o != Ordering::Greater)
+            }
+            ScanFilter::Unknown => true,
+            ScanFilter::Changed => against_previous(&[Ordering::Less, Ordering::Greater]),
+            ScanFilter::Unchanged => against_previous(&[Ordering::Equal]),
+            ScanFilter::Increased => against_previous(&[Ordering::Greater]),
+            ScanFilter::Decreased => against_previous(&[Ordering::Less]),
+        }
+    }
+}
+
+/// 初期値不明スキャンで保存するリージョンの内容
+#[derive(Debug, Clone)]
+struct RegionSnapshot {
+    base_address: usize,
+    data: Vec<u8>,
+}
+
+/// セッションの候補
+#[derive(Debug, Clone)]
+enum ScanCandidates {
+    /// リージョン全体（初期値不明スキャンの直後）
+    Snapshots(Vec<RegionSnapshot>),
+    /// 候補アドレスと前回の値（値の型のバイト数ずつ）
+    Addresses { addresses: Vec<usize>, values: Vec<u8> },
+}
+
+/// セッションファイルのヘッダー
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionHeader {
+    pid: u32,
+    process_name: String,
+    value_type: ScanValueType,
+    scan_count: usize,
+    snapshots: bool,
+}
+
+/// Cheat Engine方式の絞り込みスキャンのセッション
+///
+/// 最初のスキャンで候補を集め、次のスキャンで前回の値と比較して絞り込む
+/// ディスクに保存して別のプロセス起動（memscanの再実行）から再開できる
+#[derive(Debug, Clone)]
+pub struct ScanSession {
+    pub pid: u32,
+    pub process_name: String,
+    pub value_type: ScanValueType,
+    /// 実行したスキャンの回数
+    pub scan_count: usize,
+    candidates: ScanCandidates,
+}
+
+impl ScanSession {
+    /// 最初のスキャン（exact / range / unknown）
+    pub fn first_scan<M: MemorySource>(
+        memory: &M,
+        process: &ProcessInfo,
+        value_type: ScanValueType,
+        filter: ScanFilter,
+    ) -> Result<Self> {
+        if filter.needs_previous() {
+            bail!("The first scan needs an exact value, a range or an unknown initial value");
+        }
+
+        let size = value_type.size();
+        let alignment = value_type.alignment();
+        let mut snapshots = Vec::new();
+        let mut addresses = Vec::new();
+        let mut values = Vec::new();
+
+        for region in memory.regions()? {
+            if region.size > MAX_SCAN_REGION_SIZE {
+                continue;
+            }
+            let Ok(data) = memory.read(region.base_address, region.size) else {
+                continue;
+            };
+
+            if filter == ScanFilter::Unknown {
+                snapshots.push(RegionSnapshot {
+                    base_address: region.base_address,
+                    data,
+                });
+                continue;
+            }
+
+            let mut offset = (alignment - region.base_address % alignment) % alignment;
+            while offset + size <= data.len() {
+                let bytes = &data[offset..offset + size];
+                if filter.accepts(value_type.decode(bytes), None) {
+                    addresses.push(region.base_address + offset);
+                    values.extend_from_slice(bytes);
+                }
+                offset += alignment;
+            }
+        }
+
+        let candidates = if filter == ScanFilter::Unknown {
+            ScanCandidates::Snapshots(snapshots)
+        } else {
+            ScanCandidates::Addresses { addresses, values }
+        };
+
+        Ok(Self {
+            pid: process.pid,
+            process_name: process.name.clone(),
+            value_type,
+            scan_count: 1,
+            candidates,
+        })
+    }
+
+    /// 次のスキャン（候補を絞り込み、残った候補数を返す）
+    pub fn next_scan<M: MemorySource>(&mut self, memory: &M, filter: ScanFilter) -> Result<usize> {
+        if filter == ScanFilter::Unknown {
+            bail!("An unknown initial value scan can only be the first scan");
+        }
+
+        let value_type = self.value_type;
+        let size = value_type.size();
+        let mut addresses = Vec::new();
+        let mut values = Vec::new();
+
+        match &self.candidates {
+            ScanCandidates::Snapshots(snapshots) => {
+                let alignment = value_type.alignment();
+                for snapshot in snapshots {
+                    let Ok(current) = memory.read(snapshot.base_address, snapshot.data.len()) else {
+                        continue;
+                    };
+                    let length = current.len().min(snapshot.data.len());
+                    let mut offset = (alignment - snapshot.base_address % alignment) % alignment;
+                    while offset + size <= length {
+                        let bytes = &current[offset..offset + size];
+                        let previous = value_type.decode(&snapshot.data[offset..offset + size]);
+                        if filter.accepts(value_type.decode(bytes), Some(previous)) {
+                            addresses.push(snapshot.base_address + offset);
+                            values.extend_from_slice(bytes);
+                        }
+                        offset += alignment;
+                    }
+                }
+            }
+            ScanCandidates::Addresses { addresses: previous_addresses, values: previous_values } => {
+                let current = read_values(memory, previous_addresses, size);
+                for (index, (&address, bytes)) in previous_addresses.iter().zip(&current).enumerate() {
+                    let Some(bytes) = bytes else {
+                        continue;
+                    };
+                    let previous = value_type.decode(&previous_values[index * size..(index + 1) * size]);
+                    if filter.accepts(value_type.decode(bytes), Some(previous)) {
+                        addresses.push(address);
+                        values.extend_from_slice(bytes);
+                    }
+                }
+            }
+        }
+
+        let count = addresses.len();
+        self.candidates = ScanCandidates::Addresses { addresses, values };
+        self.scan_count += 1;
+        Ok(count)
+    }
+
+    /// 候補数（初期値不明スキャンの直後は全位置の数）
+    pub fn candidate_count(&self) -> usize {
+        match &self.candidates {
+            ScanCandidates::Snapshots(snapshots) => {
+                let size = self.value_type.size();
+                let alignment = self.value_type.alignment();
+                snapshots
+                    .iter()
+                    .map(|s| if s.data.len() >= size { (s.data.len() - size) / alignment + 1 } else { 0 })
+                    .sum()
+            }
+            ScanCandidates::Addresses { addresses, .. } => addresses.len(),
+        }
+    }
+
+    /// 候補のアドレスと前回スキャン時の値（初期値不明スキャンの直後は空）
+    pub fn results(&self, limit: usize) -> Vec<(usize, ScanValue)> {
+        let ScanCandidates::Addresses { addresses, values } = &self.candidates else {
+            return Vec::new();
+        };
+        let size = self.value_type.size();
+        addresses
+            .iter()
+            .enumerate()
+            .take(limit)
+            .map(|(index, &address)| (address, self.value_type.decode(&values[index * size..(index + 1) * size])))
+            .collect()
+    }
+
+    /// セッションをファイルに保存
+    ///
+    /// 形式: 識別子 + 形式バージョン + ヘッダー（JSON）の長さとヘッダー + 候補のバイナリ
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let header = SessionHeader {
+            pid: self.pid,
+            process_name: self.process_name.clone(),
+            value_type: self.value_type,
+            scan_count: self.scan_count,
+            snapshots: matches!(self.candidates, ScanCandidates::Snapshots(_)),
+        };
+        let header = serde_json::to_vec(&header)?;
+
+        let mut out = Vec::new();
+        out.extend_from_slice(SESSION_MAGIC);
+        out.extend_from_slice(&SESSION_FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        out.extend_from_slice(&header);
+
+        match &self.candidates {
+            ScanCandidates::Snapshots(snapshots) => {
+                out.extend_from_slice(&(snapshots.len() as u64).to_le_bytes());
+                for snapshot in snapshots {
+                    out.extend_from_slice(&(snapshot.base_address as u64).to_le_bytes());
+                    out.extend_from_slice(&(snapshot.data.len() as u64).to_le_bytes());
+                    out.extend_from_slice(&snapshot.data);
+                }
+            }
+            ScanCandidates::Addresses { addresses, values } => {
+                out.extend_from_slice(&(addresses.len() as u64).to_le_bytes());
+                for &address in addresses {
+                    out.extend_from_slice(&(address as u64).to_le_bytes());
+                }
+                out.extend_from_slice(values);
+            }
+        }
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, out)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// 保存したセッションを読み込む
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read scan session: {}", path.as_ref().display()))?;
+        let mut reader = SessionReader::new(&data);
+
+        if reader.take(4)? != SESSION_MAGIC {
+            bail!("Not a scan session file");
+        }
+        let version = u32::from_le_bytes(reader.take(4)?.try_into()?);
+        if version != SESSION_FORMAT_VERSION {
+            bail!("Unsupported scan session format version: {}", version);
+        }
+        let header_length = reader.u64()? as usize;
+        let header: SessionHeader = serde_json::from_slice(reader.take(header_length)?)?;
+        let size = header.value_type.size();
+
+        let count = reader.u64()? as usize;
+        let candidates = if header.snapshots {
+            let mut snapshots = Vec::new();
+            for _ in 0..count {
+                let base_address = reader.u64()? as usize;
+                let length = reader.u64()? as usize;
+                snapshots.push(RegionSnapshot {
+                    base_address,
+                    data: reader.take(length)?.to_vec(),
+                });
+            }
+            ScanCandidates::Snapshots(snapshots)
+        } else {
+            let mut addresses = Vec::with_capacity(count);
+            for _ in 0..count {
+                addresses.push(reader.u64()? as usize);
+            }
+            let values = reader.take(count * size)?.to_vec();
+            ScanCandidates::Addresses { addresses, values }
+        };
+
+        Ok(Self {
+            pid: header.pid,
+            process_name: header.process_name,
+            value_type: header.value_type,
+            scan_count: header.scan_count,
+            candidates,
+        })
+    }
+}
+
+/// セッション・ポインタマップファイルの読み取り位置
+pub(crate) struct SessionReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SessionReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub(crate) fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.data.len())
+            .context("Truncated scan file")?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+/// 候補アドレスの現在の値をまとめて読む
+///
+/// 近い候補は1回の読み込み（最大64KB）にまとめる。読めなかった候補はNone
+fn read_values<M: MemorySource>(memory: &M, addresses: &[usize], size: usize) -> Vec<Option<Vec<u8>>> {
+    let mut values = vec![None; addresses.len()];
+    let mut order: Vec<usize> = (0..addresses.len()).collect();
+    order.sort_unstable_by_key(|&index| addresses[index]);
+
+    let mut start = 0;
+    while start < order.len() {
+        let span_start = addresses[order[start]];
+        let mut end = start + 1;
+        while end < order.len() && addresses[order[end]] + size - span_start <= MAX_READ_SPAN {
+            end += 1;
+        }
+        let span_end = addresses[order[end - 1]] + size;
+
+        match memory.read(span_start, span_end - span_start) {
+            Ok(data) => {
+                for &index in &order[start..end] {
+                    let offset = addresses[index] - span_start;
+                    values[index] = data.get(offset..offset + size).map(|bytes| bytes.to_vec());
+                }
+            }
+            Err(_) => {
+                // まとめて読めなければ1つずつ読む
+                for &index in &order[start..end] {
+                    values[index] = memory
+                        .read(addresses[index], size)
+                        .ok()
+                        .filter(|bytes| bytes.len() == size);
+                }
+            }
+        }
+        start = end;
+    }
+
+    values
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -324,4 +1141,87 @@ mod tests {
         assert_eq!(results[0], 0);
         assert_eq!(results[1], 12);
     }
+
+    /// テスト用のメモリ（0x10000から1ページ）
+    struct FakeMemory {
+        data: std::cell::RefCell<Vec<u8>>,
+    }
+
+    impl FakeMemory {
+        const BASE: usize = 0x10000;
+
+        fn write_i32(&self, address: usize, value: i32) {
+            let offset = address - Self::BASE;
+            self.data.borrow_mut()[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    impl MemorySource for FakeMemory {
+        fn regions(&self) -> Result<Vec<MemoryRegion>> {
+            Ok(vec![MemoryRegion {
+                base_address: Self::BASE,
+                size: self.data.borrow().len(),
+                protection: 0x04,
+            }])
+        }
+
+        fn read(&self, address: usize, size: usize) -> Result<Vec<u8>> {
+            let data = self.data.borrow();
+            let offset = address.checked_sub(Self::BASE).context("unmapped")?;
+            data.get(offset..offset + size).map(|bytes| bytes.to_vec()).context("unmapped")
+        }
+    }
+
+    fn process() -> ProcessInfo {
+        ProcessInfo {
+            pid: 42,
+            name: "game.exe".to_string(),
+            base_address: 0x400000,
+        }
+    }
+
+    #[test]
+    fn test_scan_session_refinement_and_resume() -> Result<()> {
+        let memory = FakeMemory { data: std::cell::RefCell::new(vec![0; 0x1000]) };
+        memory.write_i32(0x10010, 100);
+        memory.write_i32(0x10200, 100);
+        memory.write_i32(0x10400, 100);
+
+        let mut session = ScanSession::first_scan(&memory, &process(), ScanValueType::I32, ScanFilter::Exact(ScanValue::Int(100)))?;
+        assert_eq!(session.candidate_count(), 3);
+        assert!(session.next_scan(&memory, ScanFilter::Unknown).is_err());
+
+        // 体力が減った
+        memory.write_i32(0x10010, 90);
+        memory.write_i32(0x10400, 120);
+        assert_eq!(session.next_scan(&memory, ScanFilter::Decreased)?, 1);
+
+        // 保存して別の実行から再開
+        let path = std::env::temp_dir().join(format!("memscan_session_test_{}.scan", std::process::id()));
+        session.save(&path)?;
+        let mut resumed = ScanSession::load(&path)?;
+        assert_eq!((resumed.pid, resumed.scan_count), (42, 2));
+        assert_eq!(resumed.results(10), vec![(0x10010, ScanValue::Int(90))]);
+
+        assert_eq!(resumed.next_scan(&memory, ScanFilter::Unchanged)?, 1);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_initial_value_scan() -> Result<()> {
+        let memory = FakeMemory { data: std::cell::RefCell::new(vec![0; 0x100]) };
+        let mut session = ScanSession::first_scan(&memory, &process(), ScanValueType::F32, ScanFilter::Unknown)?;
+        assert_eq!(session.candidate_count(), 0x100 / 4);
+        assert!(ScanSession::first_scan(&memory, &process(), ScanValueType::F32, ScanFilter::Changed).is_err());
+
+        memory.data.borrow_mut()[0x20..0x24].copy_from_slice(&1.5f32.to_le_bytes());
+        memory.data.borrow_mut()[0x80..0x84].copy_from_slice(&(-2.0f32).to_le_bytes());
+        assert_eq!(session.next_scan(&memory, ScanFilter::Changed)?, 2);
+
+        let filter = ScanFilter::parse(&["range", "1", "2"], ScanValueType::F32)?;
+        assert_eq!(session.next_scan(&memory, filter)?, 1);
+        assert_eq!(session.results(10), vec![(0x10020, ScanValue::Float(1.5))]);
+        Ok(())
+    }
 }
@@ -0,0 +1,217 @@
+/// 値の固定（フリーズ）と監視（ウォッチ）
+///
+/// フリーズは指定した値を一定間隔で書き戻し、ウォッチは一定間隔で読み取って
+/// 値が変わったアドレスを時刻付きで報告する
+
+use anyhow::Result;
+use crate::memory_scanner::{MemorySource, ScanValue, ScanValueType};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 固定する値
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrozenValue {
+    pub address: usize,
+    pub value_type: ScanValueType,
+    pub value: ScanValue,
+    bytes: Vec<u8>,
+}
+
+/// 値を一定間隔で書き戻すフリーザー
+#[derive(Debug, Default)]
+pub struct ValueFreezer {
+    values: Vec<FrozenValue>,
+}
+
+impl ValueFreezer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 値を固定する（同じアドレスは値を置き換える）
+    pub fn freeze(&mut self, address: usize, value_type: ScanValueType, value: ScanValue) {
+        self.unfreeze(address);
+        self.values.push(FrozenValue {
+            address,
+            value_type,
+            value,
+            bytes: value_type.encode(value),
+        });
+    }
+
+    /// 固定を解除
+    pub fn unfreeze(&mut self, address: usize) -> bool {
+        let before = self.values.len();
+        self.values.retain(|frozen| frozen.address != address);
+        self.values.len() != before
+    }
+
+    pub fn values(&self) -> &[FrozenValue] {
+        &self.values
+    }
+
+    /// 値が変わっていたアドレスに書き戻す（書き戻した数を返す）
+    pub fn apply<M: MemorySource>(&self, memory: &M) -> Result<usize> {
+        let mut rewritten = 0;
+        for frozen in &self.values {
+            let current = memory.read(frozen.address, frozen.bytes.len()).ok();
+            if current.as_deref() != Some(frozen.bytes.as_slice()) {
+                memory.write(frozen.address, &frozen.bytes)?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// 一定間隔で書き戻し続ける（durationがNoneなら終了しない）
+    pub fn run<M: MemorySource>(&self, memory: &M, interval: Duration, duration: Option<Duration>) -> Result<usize> {
+        let started = Instant::now();
+        let mut rewritten = 0;
+        loop {
+            rewritten += self.apply(memory)?;
+            if duration.is_some_and(|duration| started.elapsed() >= duration) {
+                return Ok(rewritten);
+            }
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+/// 値の変化（previousがNoneなら初回の読み取り、currentがNoneなら読み取り失敗）
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchEvent {
+    pub timestamp: SystemTime,
+    pub address: usize,
+    pub previous: Option<ScanValue>,
+    pub current: Option<ScanValue>,
+}
+
+/// 監視するアドレス
+struct WatchEntry {
+    address: usize,
+    value_type: ScanValueType,
+    /// 前回読み取ったバイト列（未読ならNone、読めなかったらSome(None)）
+    last: Option<Option<Vec<u8>>>,
+}
+
+/// アドレスを一定間隔で読み取り、値の変化を記録するウォッチリスト
+#[derive(Default)]
+pub struct WatchList {
+    entries: Vec<WatchEntry>,
+}
+
+impl WatchList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, address: usize, value_type: ScanValueType) {
+        self.entries.push(WatchEntry {
+            address,
+            value_type,
+            last: None,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 全アドレスを読み取り、前回から変わったものを返す（初回は全アドレスを返す）
+    ///
+    /// 浮動小数点のNaNでも毎回変化扱いにならないようバイト列で比較する
+    pub fn poll<M: MemorySource>(&mut self, memory: &M) -> Vec<WatchEvent> {
+        let timestamp = SystemTime::now();
+        let mut events = Vec::new();
+
+        for entry in &mut self.entries {
+            let size = entry.value_type.size();
+            let current = memory.read(entry.address, size).ok().filter(|bytes| bytes.len() == size);
+            if entry.last.as_ref() == Some(&current) {
+                continue;
+            }
+
+            let decode = |bytes: &Option<Vec<u8>>| bytes.as_ref().map(|bytes| entry.value_type.decode(bytes));
+            events.push(WatchEvent {
+                timestamp,
+                address: entry.address,
+                previous: entry.last.as_ref().and_then(decode),
+                current: decode(&current),
+            });
+            entry.last = Some(current);
+        }
+
+        events
+    }
+
+    /// 一定間隔で読み取り、変化があるたびにon_changeを呼ぶ（durationがNoneなら終了しない）
+    pub fn run<M, F>(&mut self, memory: &M, interval: Duration, duration: Option<Duration>, mut on_change: F)
+    where
+        M: MemorySource,
+        F: FnMut(&WatchEvent),
+    {
+        let started = Instant::now();
+        loop {
+            for event in self.poll(memory) {
+                on_change(&event);
+            }
+            if duration.is_some_and(|duration| started.elapsed() >= duration) {
+                return;
+            }
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::memory_scanner::MemoryScanner;
+    use std::process::{Child, Command};
+
+    /// 子プロセスを起動し、スタック領域の末端（使われていない部分）のアドレスを返す
+    fn spawn_child() -> Result<(Child, MemoryScanner, usize)> {
+        let child = Command::new("sleep").arg("30").spawn()?;
+        let scanner = MemoryScanner::from_pid(child.id())?;
+        let stack = scanner
+            .enumerate_regions()?
+            .into_iter()
+            .filter(|region| region.protection == 0x04)
+            .max_by_key(|region| region.base_address)
+            .expect("no writable region in child");
+        Ok((child, scanner, stack.base_address))
+    }
+
+    #[test]
+    fn test_write_freeze_and_watch_child_process() -> Result<()> {
+        let (mut child, scanner, address) = spawn_child()?;
+
+        scanner.write_memory(address, &1234i32.to_le_bytes())?;
+        assert_eq!(scanner.read_memory(address, 4)?, 1234i32.to_le_bytes());
+
+        let mut watch = WatchList::new();
+        watch.add(address, ScanValueType::I32);
+        let initial = watch.poll(&scanner);
+        assert_eq!((initial[0].previous, initial[0].current), (None, Some(ScanValue::Int(1234))));
+        assert!(watch.poll(&scanner).is_empty());
+
+        // 固定した値を書き換えられても書き戻す
+        let mut freezer = ValueFreezer::new();
+        freezer.freeze(address, ScanValueType::I32, ScanValue::Int(999));
+        assert_eq!(freezer.apply(&scanner)?, 1);
+        assert_eq!(freezer.apply(&scanner)?, 0);
+        scanner.write_memory(address, &5i32.to_le_bytes())?;
+        assert_eq!(freezer.run(&scanner, Duration::from_millis(1), Some(Duration::ZERO))?, 1);
+
+        let changed = watch.poll(&scanner);
+        assert_eq!(changed.len(), 1);
+        assert_eq!((changed[0].previous, changed[0].current), (Some(ScanValue::Int(1234)), Some(ScanValue::Int(999))));
+
+        child.kill()?;
+        child.wait()?;
+        Ok(())
+    }
+}
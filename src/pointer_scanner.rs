@@ -0,0 +1,450 @@
+/// ポインタスキャナー
+///
+/// ヒープ上の値は実行ごとにアドレスが変わるため、モジュール（静的領域）から
+/// 目的のアドレスへたどれる多段ポインタ（ポインタチェーン）を逆向きに探索する
+/// ポインタマップはファイルに保存でき、別の実行で得たマップや実プロセスで
+/// チェーンを再検証して、実行をまたいで安定したチェーンだけを残せる
+
+use anyhow::{bail, Context, Result};
+use crate::memory_scanner::{MemoryRegion, MemorySource, ModuleInfo, ProcessInfo, SessionReader, MAX_SCAN_REGION_SIZE};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// ポインタのサイズ（x64プロセスのみ対応）
+pub const POINTER_SIZE: usize = 8;
+
+/// ポインタマップファイルの識別子
+const POINTER_MAP_MAGIC: &[u8; 4] = b"MPTR";
+
+/// ポインタマップファイルの形式バージョン
+const POINTER_MAP_FORMAT_VERSION: u32 = 1;
+
+/// ポインタスキャンの設定
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PointerScanOptions {
+    /// チェーンの最大段数
+    pub max_level: usize,
+    /// 各段でポインタの指す先から足せるオフセットの上限
+    pub max_offset: usize,
+    /// 見つけるチェーン数の上限
+    pub max_results: usize,
+}
+
+impl Default for PointerScanOptions {
+    fn default() -> Self {
+        Self {
+            max_level: 5,
+            max_offset: 0x1000,
+            max_results: 10_000,
+        }
+    }
+}
+
+/// ポインタチェーン: `[[module+module_offset]+offsets[0]]+offsets[1]...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PointerChain {
+    pub module: String,
+    pub module_offset: usize,
+    pub offsets: Vec<usize>,
+}
+
+impl PointerChain {
+    /// チェーンをたどって最終的なアドレスを求める（途中で読めなければNone）
+    pub fn resolve<F>(&self, modules: &[ModuleInfo], read_pointer: F) -> Option<usize>
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let module = modules.iter().find(|m| m.name.eq_ignore_ascii_case(&self.module))?;
+        let mut address = module.base_address.checked_add(self.module_offset)?;
+        for &offset in &self.offsets {
+            address = read_pointer(address)?.checked_add(offset)?;
+        }
+        Some(address)
+    }
+}
+
+impl std::fmt::Display for PointerChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = format!("{}+0x{:X}", self.module, self.module_offset);
+        for offset in &self.offsets {
+            text = format!("[{}]+0x{:X}", text, offset);
+        }
+        write!(f, "{}", text)
+    }
+}
+
+/// ポインタスキャンの結果（ファイルに保存して次の実行で絞り込む）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PointerScanResults {
+    pub process_name: String,
+    pub target: usize,
+    pub options: PointerScanOptions,
+    pub chains: Vec<PointerChain>,
+}
+
+impl PointerScanResults {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read pointer scan results: {}", path.as_ref().display()))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+/// ポインタマップファイルのヘッダー
+#[derive(Serialize, Deserialize)]
+struct PointerMapHeader {
+    pid: u32,
+    process_name: String,
+    modules: Vec<ModuleInfo>,
+}
+
+/// プロセス内のポインタ（読み取り可能なメモリを指す値）の一覧
+pub struct PointerMap {
+    pub pid: u32,
+    pub process_name: String,
+    pub modules: Vec<ModuleInfo>,
+    /// (ポインタのアドレス, 値)。アドレス順
+    entries: Vec<(usize, usize)>,
+    /// 値の順に並べたentriesの添字
+    by_value: Vec<usize>,
+}
+
+impl PointerMap {
+    /// プロセスのメモリからポインタマップを作る
+    pub fn build<M: MemorySource>(memory: &M, process: &ProcessInfo) -> Result<Self> {
+        let mut regions = memory.regions()?;
+        regions.sort_unstable_by_key(|r| r.base_address);
+
+        let mut entries = Vec::new();
+        for region in &regions {
+            if region.size > MAX_SCAN_REGION_SIZE {
+                continue;
+            }
+            let Ok(data) = memory.read(region.base_address, region.size) else {
+                continue;
+            };
+
+            // リージョンの先頭はページ境界なのでオフセットの整列でよい
+            for (index, bytes) in data.chunks_exact(POINTER_SIZE).enumerate() {
+                let value = u64::from_le_bytes(bytes.try_into()?) as usize;
+                if value != 0 && points_into(&regions, value) {
+                    entries.push((region.base_address + index * POINTER_SIZE, value));
+                }
+            }
+        }
+
+        Ok(Self::from_entries(
+            process.pid,
+            process.name.clone(),
+            memory.modules()?,
+            entries,
+        ))
+    }
+
+    fn from_entries(pid: u32, process_name: String, modules: Vec<ModuleInfo>, mut entries: Vec<(usize, usize)>) -> Self {
+        entries.sort_unstable();
+        entries.dedup_by_key(|entry| entry.0);
+        let mut by_value: Vec<usize> = (0..entries.len()).collect();
+        by_value.sort_unstable_by_key(|&index| entries[index].1);
+
+        Self {
+            pid,
+            process_name,
+            modules,
+            entries,
+            by_value,
+        }
+    }
+
+    /// ポインタの数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// アドレスにあるポインタの値
+    pub fn pointer_at(&self, address: usize) -> Option<usize> {
+        self.entries
+            .binary_search_by_key(&address, |entry| entry.0)
+            .ok()
+            .map(|index| self.entries[index].1)
+    }
+
+    /// 静的領域（モジュール内）のアドレスならモジュールを返す
+    fn module_for(&self, address: usize) -> Option<&ModuleInfo> {
+        self.modules.iter().find(|m| m.contains(address))
+    }
+
+    /// 値が [low, high] のポインタのアドレス
+    fn pointers_into(&self, low: usize, high: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let start = self.by_value.partition_point(|&index| self.entries[index].1 < low);
+        self.by_value[start..]
+            .iter()
+            .map(move |&index| self.entries[index])
+            .take_while(move |&(_, value)| value <= high)
+    }
+
+    /// 目的のアドレスへたどれるポインタチェーンを探す
+    ///
+    /// 段数の少ないチェーンから順に見つける（反復深化）
+    pub fn scan(&self, target: usize, options: &PointerScanOptions) -> Vec<PointerChain> {
+        let mut chains = Vec::new();
+        let mut path = Vec::new();
+        let mut offsets = Vec::new();
+
+        for level in 1..=options.max_level {
+            self.scan_level(target, level, options, &mut path, &mut offsets, &mut chains);
+            if chains.len() >= options.max_results {
+                break;
+            }
+        }
+
+        chains.truncate(options.max_results);
+        chains
+    }
+
+    /// 残りlevel段でtargetに届くチェーンを探す（offsetsは目的側からの逆順）
+    fn scan_level(
+        &self,
+        target: usize,
+        level: usize,
+        options: &PointerScanOptions,
+        path: &mut Vec<usize>,
+        offsets: &mut Vec<usize>,
+        chains: &mut Vec<PointerChain>,
+    ) {
+        let low = target.saturating_sub(options.max_offset);
+        for (address, value) in self.pointers_into(low, target) {
+            if chains.len() >= options.max_results {
+                return;
+            }
+            offsets.push(target - value);
+
+            if let Some(module) = self.module_for(address) {
+                // 静的なポインタに着いたらそれ以上さかのぼらない
+                if level == 1 {
+                    chains.push(PointerChain {
+                        module: module.name.clone(),
+                        module_offset: address - module.base_address,
+                        offsets: offsets.iter().rev().copied().collect(),
+                    });
+                }
+            } else if level > 1 && !path.contains(&address) {
+                path.push(address);
+                self.scan_level(address, level - 1, options, path, offsets, chains);
+                path.pop();
+            }
+
+            offsets.pop();
+        }
+    }
+
+    /// このマップ（別の実行で作ったもの）でも目的のアドレスに届くチェーンだけを残す
+    pub fn rescan(&self, chains: &[PointerChain], target: usize) -> Vec<PointerChain> {
+        chains
+            .iter()
+            .filter(|chain| chain.resolve(&self.modules, |address| self.pointer_at(address)) == Some(target))
+            .cloned()
+            .collect()
+    }
+
+    /// ポインタマップをファイルに保存
+    ///
+    /// 形式: 識別子 + 形式バージョン + ヘッダー（JSON）の長さとヘッダー + (アドレス, 値)の列
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let header = PointerMapHeader {
+            pid: self.pid,
+            process_name: self.process_name.clone(),
+            modules: self.modules.clone(),
+        };
+        let header = serde_json::to_vec(&header)?;
+
+        let mut out = Vec::with_capacity(header.len() + 24 + self.entries.len() * 16);
+        out.extend_from_slice(POINTER_MAP_MAGIC);
+        out.extend_from_slice(&POINTER_MAP_FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+        for &(address, value) in &self.entries {
+            out.extend_from_slice(&(address as u64).to_le_bytes());
+            out.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, out)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// 保存したポインタマップを読み込む
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read(path.as_ref())
+            .with_context(|| format!("Failed to read pointer map: {}", path.as_ref().display()))?;
+        let mut reader = SessionReader::new(&data);
+
+        if reader.take(4)? != POINTER_MAP_MAGIC {
+            bail!("Not a pointer map file");
+        }
+        let version = u32::from_le_bytes(reader.take(4)?.try_into()?);
+        if version != POINTER_MAP_FORMAT_VERSION {
+            bail!("Unsupported pointer map format version: {}", version);
+        }
+        let header_length = reader.u64()? as usize;
+        let header: PointerMapHeader = serde_json::from_slice(reader.take(header_length)?)?;
+
+        let count = reader.u64()? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let address = reader.u64()? as usize;
+            let value = reader.u64()? as usize;
+            entries.push((address, value));
+        }
+
+        Ok(Self::from_entries(header.pid, header.process_name, header.modules, entries))
+    }
+}
+
+/// 実プロセスでも目的のアドレスに届くチェーンだけを残す
+pub fn rescan_live<M: MemorySource>(memory: &M, chains: &[PointerChain], target: usize) -> Result<Vec<PointerChain>> {
+    let modules = memory.modules()?;
+    let read_pointer = |address: usize| {
+        let bytes = memory.read(address, POINTER_SIZE).ok()?;
+        Some(u64::from_le_bytes(bytes.as_slice().try_into().ok()?) as usize)
+    };
+
+    Ok(chains
+        .iter()
+        .filter(|chain| chain.resolve(&modules, read_pointer) == Some(target))
+        .cloned()
+        .collect())
+}
+
+/// 値がいずれかのリージョン（アドレス順）を指しているか
+fn points_into(regions: &[MemoryRegion], value: usize) -> bool {
+    let index = regions.partition_point(|r| r.base_address <= value);
+    index > 0 && value - regions[index - 1].base_address < regions[index - 1].size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用のプロセス（モジュール1つとヒープ1つ）
+    struct FakeProcess {
+        module_base: usize,
+        heap_base: usize,
+        image: Vec<u8>,
+        heap: Vec<u8>,
+    }
+
+    impl FakeProcess {
+        fn new(module_base: usize, heap_base: usize) -> Self {
+            Self {
+                module_base,
+                heap_base,
+                image: vec![0; 0x1000],
+                heap: vec![0; 0x1000],
+            }
+        }
+
+        fn in_heap(&self, address: usize) -> bool {
+            address >= self.heap_base && address - self.heap_base < self.heap.len()
+        }
+
+        fn write_pointer(&mut self, address: usize, value: usize) {
+            let (data, offset) = if self.in_heap(address) {
+                (&mut self.heap, address - self.heap_base)
+            } else {
+                (&mut self.image, address - self.module_base)
+            };
+            data[offset..offset + 8].copy_from_slice(&(value as u64).to_le_bytes());
+        }
+    }
+
+    impl MemorySource for FakeProcess {
+        fn regions(&self) -> Result<Vec<MemoryRegion>> {
+            Ok(vec![
+                MemoryRegion { base_address: self.module_base, size: self.image.len(), protection: 0x04 },
+                MemoryRegion { base_address: self.heap_base, size: self.heap.len(), protection: 0x04 },
+            ])
+        }
+
+        fn read(&self, address: usize, size: usize) -> Result<Vec<u8>> {
+            let (data, offset) = if self.in_heap(address) {
+                (&self.heap, address - self.heap_base)
+            } else {
+                (&self.image, address.checked_sub(self.module_base).context("unmapped")?)
+            };
+            data.get(offset..offset + size).map(|bytes| bytes.to_vec()).context("unmapped")
+        }
+
+        fn modules(&self) -> Result<Vec<ModuleInfo>> {
+            Ok(vec![ModuleInfo {
+                name: "game.exe".to_string(),
+                base_address: self.module_base,
+                size: self.image.len(),
+            }])
+        }
+    }
+
+    /// game.exe+0x100 → プレイヤー（ヒープ）、プレイヤー+0x18 → 体力を持つ構造体、+0x40が体力
+    /// game.exe+0x300 にはたまたま1回目だけ体力の近くを指す値がある
+    fn run(module_base: usize, heap_base: usize, stray: usize) -> (FakeProcess, usize) {
+        let mut process = FakeProcess::new(module_base, heap_base);
+        process.write_pointer(module_base + 0x100, heap_base + 0x200);
+        process.write_pointer(heap_base + 0x218, heap_base + 0x800);
+        process.write_pointer(module_base + 0x300, stray);
+        (process, heap_base + 0x840)
+    }
+
+    fn info() -> ProcessInfo {
+        ProcessInfo {
+            pid: 42,
+            name: "game.exe".to_string(),
+            base_address: 0x400000,
+        }
+    }
+
+    #[test]
+    fn test_pointer_chain_scan_and_rescan() -> Result<()> {
+        let options = PointerScanOptions { max_level: 3, max_offset: 0x100, max_results: 100 };
+
+        let (first, target) = run(0x400000, 0x1000_0000, 0x1000_0838);
+        let map = PointerMap::build(&first, &info())?;
+        let chains = map.scan(target, &options);
+        let stable = PointerChain { module: "game.exe".to_string(), module_offset: 0x100, offsets: vec![0x18, 0x40] };
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].to_string(), "[game.exe+0x300]+0x8");
+        assert_eq!(chains[1], stable);
+        assert_eq!(stable.to_string(), "[[game.exe+0x100]+0x18]+0x40");
+
+        // 2回目の実行ではモジュールもヒープも別の場所にある
+        let (second, target) = run(0x7ff6_0000_0000, 0x2000_0000, 0x2000_0100);
+        assert_eq!(rescan_live(&second, &chains, target)?, vec![stable.clone()]);
+
+        let path = std::env::temp_dir().join(format!("pointer_map_test_{}.ptr", std::process::id()));
+        PointerMap::build(&second, &info())?.save(&path)?;
+        let saved = PointerMap::load(&path)?;
+        assert_eq!(saved.modules[0].base_address, 0x7ff6_0000_0000);
+        assert_eq!(saved.rescan(&chains, target), vec![stable]);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,632 @@
+/// バイトシグネチャ（IDA形式のワイルドカード付きパターン）
+///
+/// "48 8B 05 ?? ?? ?? ?? E8" のようなパターンの解析・検索と、
+/// 静的なバイナリイメージに対するセクション単位のスキャン、
+/// 関数や命令を一意に特定する最短シグネチャの生成を行う
+/// 生成するシグネチャはRIP相対の変位・相対分岐先・アドレスの即値（再配置対象）を
+/// ワイルドカードにするため、アップデートでコードの配置が変わっても使い続けられる
+
+use anyhow::{anyhow, bail, Result};
+use capstone::arch::x86::{ArchMode, X86OperandType, X86Reg};
+use capstone::prelude::*;
+use goblin::Object;
+use serde::Serialize;
+use std::fmt;
+
+/// 生成するシグネチャの最大長（バイト）
+pub const DEFAULT_MAX_SIGNATURE_LENGTH: usize = 128;
+
+/// ワイルドカード付きバイトパターン
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub bytes: Vec<u8>,
+    /// trueのバイトのみ比較する
+    pub mask: Vec<bool>,
+}
+
+impl Signature {
+    /// マスクなしのパターンを作成
+    pub fn exact(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            mask: vec![true; bytes.len()],
+        }
+    }
+
+    /// IDA形式の文字列から作成（"??"または"?"がワイルドカード）
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let mut bytes = Vec::new();
+        let mut mask = Vec::new();
+
+        for part in pattern.split_whitespace() {
+            if part == "??" || part == "?" {
+                bytes.push(0);
+                mask.push(false);
+            } else {
+                let byte = u8::from_str_radix(part, 16).map_err(|_| anyhow!("Invalid hex byte: {}", part))?;
+                bytes.push(byte);
+                mask.push(true);
+            }
+        }
+
+        if !mask.iter().any(|&m| m) {
+            bail!("Signature must contain at least one fixed byte");
+        }
+        Ok(Self { bytes, mask })
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// ワイルドカードのバイト数
+    pub fn wildcard_count(&self) -> usize {
+        self.mask.iter().filter(|&&m| !m).count()
+    }
+
+    /// データの指定位置で一致するか
+    pub fn matches_at(&self, data: &[u8], position: usize) -> bool {
+        data.get(position..position + self.len()).is_some_and(|window| {
+            window
+                .iter()
+                .zip(&self.bytes)
+                .zip(&self.mask)
+                .all(|((&actual, &expected), &fixed)| !fixed || actual == expected)
+        })
+    }
+
+    /// データ内の一致位置をすべて求める
+    pub fn find_all(&self, data: &[u8]) -> Vec<usize> {
+        find_masked(data, &self.bytes, &self.mask)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (byte, fixed)) in self.bytes.iter().zip(&self.mask).enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            if *fixed {
+                write!(f, "{:02X}", byte)?;
+            } else {
+                write!(f, "??")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// マスク付きパターンの検索
+pub fn find_masked(data: &[u8], pattern: &[u8], mask: &[bool]) -> Vec<usize> {
+    let signature = Signature {
+        bytes: pattern.to_vec(),
+        mask: mask.to_vec(),
+    };
+    MultiPatternScanner::new(std::slice::from_ref(&signature))
+        .scan(data)
+        .into_iter()
+        .map(|(_, position)| position)
+        .collect()
+}
+
+/// シグネチャ内で検索の手がかりにする固定バイト列
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    /// シグネチャのインデックス
+    signature: usize,
+    /// シグネチャ先頭からのオフセット
+    offset: usize,
+}
+
+/// 複数シグネチャの一括検索
+///
+/// 各シグネチャの最長の固定バイト列をAho-Corasickでまとめて検索し、
+/// 固定バイトが1バイトずつしか続かないシグネチャはmemchrでその1バイトを探す
+/// 候補位置はマスク付きで全体を照合する。データは1回だけ走査する
+pub struct MultiPatternScanner {
+    signatures: Vec<Signature>,
+    /// 固定バイト列のオートマトン（パターンID → そのバイト列を持つシグネチャ）
+    automaton: Option<aho_corasick::AhoCorasick>,
+    literal_anchors: Vec<Vec<Anchor>>,
+    /// 1バイトのアンカー（バイト値ごと）
+    byte_anchors: Vec<(u8, Vec<Anchor>)>,
+    /// 固定バイトのないシグネチャ（全位置で一致）
+    wildcard_only: Vec<usize>,
+}
+
+impl MultiPatternScanner {
+    pub fn new(signatures: &[Signature]) -> Self {
+        let mut literals: Vec<Vec<u8>> = Vec::new();
+        let mut literal_anchors: Vec<Vec<Anchor>> = Vec::new();
+        let mut byte_anchors: Vec<(u8, Vec<Anchor>)> = Vec::new();
+        let mut wildcard_only = Vec::new();
+
+        for (index, signature) in signatures.iter().enumerate() {
+            let Some((offset, length)) = longest_fixed_run(signature) else {
+                wildcard_only.push(index);
+                continue;
+            };
+            let anchor = Anchor { signature: index, offset };
+
+            if length >= 2 {
+                let literal = &signature.bytes[offset..offset + length];
+                match literals.iter().position(|existing| existing == literal) {
+                    Some(id) => literal_anchors[id].push(anchor),
+                    None => {
+                        literals.push(literal.to_vec());
+                        literal_anchors.push(vec![anchor]);
+                    }
+                }
+            } else {
+                let byte = signature.bytes[offset];
+                match byte_anchors.iter_mut().find(|(b, _)| *b == byte) {
+                    Some((_, anchors)) => anchors.push(anchor),
+                    None => byte_anchors.push((byte, vec![anchor])),
+                }
+            }
+        }
+
+        let automaton = if literals.is_empty() {
+            None
+        } else {
+            aho_corasick::AhoCorasick::builder()
+                .match_kind(aho_corasick::MatchKind::Standard)
+                .build(&literals)
+                .ok()
+        };
+
+        Self {
+            signatures: signatures.to_vec(),
+            automaton,
+            literal_anchors,
+            byte_anchors,
+            wildcard_only,
+        }
+    }
+
+    pub fn signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    /// データを走査し、（シグネチャのインデックス, 一致位置）を順に並べて返す
+    pub fn scan(&self, data: &[u8]) -> Vec<(usize, usize)> {
+        let mut results = Vec::new();
+        let mut verify = |anchor: &Anchor, found: usize| {
+            if let Some(start) = found.checked_sub(anchor.offset) {
+                if self.signatures[anchor.signature].matches_at(data, start) {
+                    results.push((anchor.signature, start));
+                }
+            }
+        };
+
+        if let Some(automaton) = &self.automaton {
+            for found in automaton.find_overlapping_iter(data) {
+                for anchor in &self.literal_anchors[found.pattern().as_usize()] {
+                    verify(anchor, found.start());
+                }
+            }
+        }
+
+        for (byte, anchors) in &self.byte_anchors {
+            for found in memchr::memchr_iter(*byte, data) {
+                for anchor in anchors {
+                    verify(anchor, found);
+                }
+            }
+        }
+
+        for &index in &self.wildcard_only {
+            let length = self.signatures[index].len();
+            if length <= data.len() {
+                results.extend((0..=data.len() - length).map(|start| (index, start)));
+            }
+        }
+
+        results.sort_unstable();
+        results.dedup();
+        results
+    }
+
+    /// シグネチャごとの一致位置
+    pub fn scan_grouped(&self, data: &[u8]) -> Vec<Vec<usize>> {
+        let mut grouped = vec![Vec::new(); self.signatures.len()];
+        for (index, position) in self.scan(data) {
+            grouped[index].push(position);
+        }
+        grouped
+    }
+}
+
+/// 最長の連続した固定バイト列（開始オフセット, 長さ）
+fn longest_fixed_run(signature: &Signature) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = 0;
+    for (i, &fixed) in signature.mask.iter().chain(std::iter::once(&false)).enumerate() {
+        if !fixed {
+            let length = i - start;
+            if length > 0 && best.is_none_or(|(_, best_length)| length > best_length) {
+                best = Some((start, length));
+            }
+            start = i + 1;
+        }
+    }
+    best
+}
+
+/// ファイル上に実体のあるセクション
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageRegion {
+    pub name: String,
+    /// 仮想アドレス
+    pub address: u64,
+    pub file_offset: usize,
+    pub size: usize,
+    pub executable: bool,
+}
+
+/// シグネチャの一致位置
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureMatch {
+    /// 仮想アドレス
+    pub address: u64,
+    pub file_offset: usize,
+    pub section: String,
+}
+
+/// バイナリのセクション一覧（PE/ELF、それ以外はファイル全体を1つの領域とみなす）
+pub fn image_regions(binary_data: &[u8]) -> Result<Vec<ImageRegion>> {
+    let clamp = |offset: u64, size: u64| -> Option<(usize, usize)> {
+        let offset = usize::try_from(offset).ok()?;
+        if offset >= binary_data.len() {
+            return None;
+        }
+        let size = std::cmp::min(usize::try_from(size).ok()?, binary_data.len() - offset);
+        (size > 0).then_some((offset, size))
+    };
+
+    let regions = match Object::parse(binary_data)? {
+        Object::PE(pe) => {
+            let image_base = pe.image_base as u64;
+            pe.sections
+                .iter()
+                .filter_map(|s| {
+                    let (file_offset, size) = clamp(s.pointer_to_raw_data as u64, s.size_of_raw_data as u64)?;
+                    Some(ImageRegion {
+                        name: s.name().unwrap_or("").to_string(),
+                        address: image_base + s.virtual_address as u64,
+                        file_offset,
+                        size,
+                        executable: s.characteristics & goblin::pe::section_table::IMAGE_SCN_MEM_EXECUTE != 0,
+                    })
+                })
+                .collect()
+        }
+        Object::Elf(elf) => elf
+            .section_headers
+            .iter()
+            .filter(|sh| {
+                sh.sh_type != goblin::elf::section_header::SHT_NOBITS
+                    && sh.sh_flags & goblin::elf::section_header::SHF_ALLOC as u64 != 0
+            })
+            .filter_map(|sh| {
+                let (file_offset, size) = clamp(sh.sh_offset, sh.sh_size)?;
+                Some(ImageRegion {
+                    name: elf.shdr_strtab.get_at(sh.sh_name).unwrap_or("").to_string(),
+                    address: sh.sh_addr,
+                    file_offset,
+                    size,
+                    executable: sh.sh_flags & goblin::elf::section_header::SHF_EXECINSTR as u64 != 0,
+                })
+            })
+            .collect(),
+        _ => vec![ImageRegion {
+            name: String::new(),
+            address: 0,
+            file_offset: 0,
+            size: binary_data.len(),
+            executable: true,
+        }],
+    };
+
+    Ok(regions)
+}
+
+/// 対象のセクション（名前の指定がなければ全セクション）
+fn select_regions(binary_data: &[u8], section: Option<&str>) -> Result<Vec<ImageRegion>> {
+    let regions: Vec<ImageRegion> = image_regions(binary_data)?
+        .into_iter()
+        .filter(|region| section.is_none_or(|name| region.name == name))
+        .collect();
+    if regions.is_empty() {
+        bail!("No section named {}", section.unwrap_or(""));
+    }
+    Ok(regions)
+}
+
+/// 静的なバイナリイメージをシグネチャでスキャン（一致位置は仮想アドレスで返す）
+pub fn scan_image(binary_data: &[u8], signature: &Signature, section: Option<&str>) -> Result<Vec<SignatureMatch>> {
+    let mut matches = scan_image_multi(binary_data, std::slice::from_ref(signature), section)?;
+    Ok(matches.pop().unwrap_or_default())
+}
+
+/// 静的なバイナリイメージを複数のシグネチャで一度にスキャン（シグネチャごとの一致位置）
+pub fn scan_image_multi(binary_data: &[u8], signatures: &[Signature], section: Option<&str>) -> Result<Vec<Vec<SignatureMatch>>> {
+    let scanner = MultiPatternScanner::new(signatures);
+    let mut matches = vec![Vec::new(); signatures.len()];
+    for region in select_regions(binary_data, section)? {
+        let data = &binary_data[region.file_offset..region.file_offset + region.size];
+        for (index, position) in scanner.scan(data) {
+            matches[index].push(SignatureMatch {
+                address: region.address + position as u64,
+                file_offset: region.file_offset + position,
+                section: region.name.clone(),
+            });
+        }
+    }
+    Ok(matches)
+}
+
+/// 生成したシグネチャ
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedSignature {
+    /// 対象の仮想アドレス
+    pub address: u64,
+    /// IDA形式のパターン
+    pub signature: String,
+    pub length: usize,
+    pub wildcards: usize,
+    /// シグネチャが覆う命令数
+    pub instruction_count: usize,
+}
+
+/// 指定アドレスの関数・命令を一意に特定する最短のシグネチャを生成
+///
+/// アドレスから命令単位でマスク付きのバイト列を作り、対象のセクション内で
+/// 一致する位置が対象アドレスだけになるまで1バイトずつ伸ばす
+pub fn make_signature(binary_data: &[u8], address: u64, section: Option<&str>, max_length: usize) -> Result<GeneratedSignature> {
+    let regions = select_regions(binary_data, section)?;
+    let all_regions = image_regions(binary_data)?;
+    let target = all_regions
+        .iter()
+        .find(|region| address >= region.address && address < region.address + region.size as u64)
+        .ok_or_else(|| anyhow!("Address 0x{:x} is not in any file-backed section", address))?;
+    let target_offset = target.file_offset + (address - target.address) as usize;
+
+    // イメージの範囲（この範囲の即値・変位はアドレスとみなす）
+    let image_start = all_regions.iter().map(|r| r.address).min().unwrap_or(0);
+    let image_end = all_regions.iter().map(|r| r.address + r.size as u64).max().unwrap_or(0);
+
+    let code_end = std::cmp::min(target.file_offset + target.size, target_offset + max_length + 15);
+    let (signature, boundaries) = masked_instructions(
+        &binary_data[target_offset..code_end],
+        address,
+        max_length,
+        image_start..image_end,
+    )?;
+    let first_end = *boundaries.first().ok_or_else(|| anyhow!("No instructions at 0x{:x}", address))?;
+
+    // 最初の命令で候補を求め、1バイトずつ伸ばして絞り込む
+    let first = Signature {
+        bytes: signature.bytes[..first_end].to_vec(),
+        mask: signature.mask[..first_end].to_vec(),
+    };
+    let mut candidates: Vec<usize> = Vec::new();
+    for region in &regions {
+        let data = &binary_data[region.file_offset..region.file_offset + region.size];
+        candidates.extend(first.find_all(data).into_iter().map(|position| region.file_offset + position));
+    }
+
+    let mut length = first_end;
+    loop {
+        let index = length - 1;
+        candidates.retain(|&offset| {
+            offset == target_offset
+                || binary_data
+                    .get(offset + index)
+                    .is_some_and(|&byte| !signature.mask[index] || byte == signature.bytes[index])
+        });
+        let others = candidates.iter().filter(|&&offset| offset != target_offset).count();
+        if others == 0 {
+            break;
+        }
+        if length == signature.len() {
+            bail!(
+                "No unique signature within {} bytes at 0x{:x} ({} other matches)",
+                signature.len(),
+                address,
+                others
+            );
+        }
+        length += 1;
+    }
+
+    // 末尾のワイルドカードは一致に寄与しない
+    while length > 1 && !signature.mask[length - 1] {
+        length -= 1;
+    }
+    let result = Signature {
+        bytes: signature.bytes[..length].to_vec(),
+        mask: signature.mask[..length].to_vec(),
+    };
+
+    Ok(GeneratedSignature {
+        address,
+        signature: result.to_string(),
+        length,
+        wildcards: result.wildcard_count(),
+        instruction_count: boundaries.iter().position(|&end| end >= length).map_or(boundaries.len(), |i| i + 1),
+    })
+}
+
+/// 命令列を位置に依存するフィールドをマスクしたバイト列にする
+///
+/// 戻り値の2つ目は各命令の終端（先頭からのバイト数）
+fn masked_instructions(
+    code: &[u8],
+    address: u64,
+    max_length: usize,
+    image: std::ops::Range<u64>,
+) -> Result<(Signature, Vec<usize>)> {
+    let cs = Capstone::new()
+        .x86()
+        .mode(ArchMode::Mode64)
+        .detail(true)
+        .build()
+        .map_err(|e| anyhow!("Failed to create Capstone engine: {}", e))?;
+    let insns = cs
+        .disasm_all(code, address)
+        .map_err(|e| anyhow!("Disassembly failed: {}", e))?;
+
+    let mut signature = Signature { bytes: Vec::new(), mask: Vec::new() };
+    let mut boundaries = Vec::new();
+
+    for insn in insns.iter() {
+        if signature.len() >= max_length {
+            break;
+        }
+        let detail = cs
+            .insn_detail(insn)
+            .map_err(|e| anyhow!("Failed to get instruction detail: {}", e))?;
+        let arch_detail = detail.arch_detail();
+        let x86 = arch_detail.x86().ok_or_else(|| anyhow!("Not an x86 instruction"))?;
+
+        let bytes = insn.bytes();
+        let mut mask = vec![true; bytes.len()];
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let is_branch = mnemonic.starts_with('j') || mnemonic.starts_with("loop") || mnemonic == "call";
+        let next = insn.address() + bytes.len() as u64;
+
+        for operand in x86.operands() {
+            match operand.op_type {
+                X86OperandType::Imm(target) if is_branch => {
+                    // 相対分岐の変位は命令の末尾（rel32またはrel8）
+                    let relative = target.wrapping_sub(next as i64);
+                    let width = if bytes.len() >= 5 && tail_value(bytes, 4) == relative { 4 } else { 1 };
+                    mask_tail(&mut mask, width);
+                }
+                X86OperandType::Imm(value) if image.contains(&(value as u64)) => {
+                    mask_value(bytes, &mut mask, value as u64, &[8, 4]);
+                }
+                X86OperandType::Mem(mem) if mem.base().0 as u32 == X86Reg::X86_REG_RIP => {
+                    mask_value(bytes, &mut mask, mem.disp() as u64, &[4]);
+                }
+                X86OperandType::Mem(mem) if image.contains(&(mem.disp() as u64)) => {
+                    mask_value(bytes, &mut mask, mem.disp() as u64, &[4]);
+                }
+                _ => {}
+            }
+        }
+
+        signature.bytes.extend_from_slice(bytes);
+        signature.mask.extend(mask);
+        boundaries.push(signature.len());
+    }
+
+    let length = std::cmp::min(signature.len(), max_length);
+    signature.bytes.truncate(length);
+    signature.mask.truncate(length);
+    boundaries.retain(|&end| end <= length);
+    if boundaries.last() != Some(&length) && length > 0 {
+        boundaries.push(length);
+    }
+    Ok((signature, boundaries))
+}
+
+/// 命令末尾の符号付き値
+fn tail_value(bytes: &[u8], width: usize) -> i64 {
+    let tail = &bytes[bytes.len() - width..];
+    match width {
+        4 => i32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]) as i64,
+        _ => tail[0] as i8 as i64,
+    }
+}
+
+fn mask_tail(mask: &mut [bool], width: usize) {
+    let len = mask.len();
+    mask[len.saturating_sub(width)..].iter_mut().for_each(|m| *m = false);
+}
+
+/// 命令のバイト列から値のエンコード（指定した幅の下位バイト）を末尾側から探してマスクする
+fn mask_value(bytes: &[u8], mask: &mut [bool], value: u64, widths: &[usize]) {
+    for &width in widths {
+        if width > bytes.len() {
+            continue;
+        }
+        let encoded = &value.to_le_bytes()[..width];
+        if let Some(position) = bytes.windows(width).rposition(|window| window == encoded) {
+            mask[position..position + width].iter_mut().for_each(|m| *m = false);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_find() {
+        let signature = Signature::parse("48 8B ? 24 ?? C3").unwrap();
+        assert_eq!(signature.to_string(), "48 8B ?? 24 ?? C3");
+        assert_eq!(signature.wildcard_count(), 2);
+
+        let data = [0x90, 0x48, 0x8b, 0x5c, 0x24, 0x08, 0xc3, 0x48, 0x8b, 0x4c, 0x24, 0x10, 0xc3];
+        assert_eq!(signature.find_all(&data), vec![1, 7]);
+        assert!(Signature::parse("?? ??").is_err());
+        assert!(Signature::parse("4G").is_err());
+    }
+
+    #[test]
+    fn test_multi_pattern_scan_matches_naive_search() {
+        let signatures = [
+            Signature::parse("48 8B ?? 24").unwrap(),
+            Signature::parse("8B ?? 24").unwrap(),
+            Signature::parse("C3 ?? ?? 90").unwrap(),
+            Signature::parse("?? 24 ?? 90").unwrap(),
+            Signature::parse("48 8B ?? 24").unwrap(),
+        ];
+        let mut data: Vec<u8> = (0..4096u32).map(|i| [0x48, 0x8b, 0x5c, 0x24, 0xc3, 0x90, 0x24, 0x90][(i * 7 % 11 % 8) as usize]).collect();
+        for position in [0, 100, 101, 2000, 4088] {
+            data[position..position + 8].copy_from_slice(&[0x48, 0x8b, 0x5c, 0x24, 0xc3, 0x00, 0x00, 0x90]);
+        }
+
+        let grouped = MultiPatternScanner::new(&signatures).scan_grouped(&data);
+        for (signature, found) in signatures.iter().zip(&grouped) {
+            let naive: Vec<usize> = (0..=data.len() - signature.len()).filter(|&i| signature.matches_at(&data, i)).collect();
+            assert_eq!(found, &naive);
+        }
+        assert!(!grouped[0].is_empty());
+        assert_eq!(grouped[0], grouped[4]);
+    }
+
+    #[test]
+    fn test_make_signature_masks_position_dependent_bytes() -> Result<()> {
+        // 同じ命令で始まる2つの関数（生バイナリ: アドレス = ファイルオフセット）
+        let common = [0x48, 0x83, 0xec, 0x28]; // sub rsp, 0x28
+        let mut data = vec![0xccu8; 0x200];
+        data[0x20..0x24].copy_from_slice(&common);
+        data[0x24..0x2b].copy_from_slice(&[0x48, 0x8b, 0x05, 0x10, 0x20, 0x00, 0x00]); // mov rax, [rip+0x2010]
+        data[0x2b..0x30].copy_from_slice(&[0xe8, 0x00, 0x01, 0x00, 0x00]); // call rel32
+        data[0x30] = 0xc3;
+        data[0x80..0x84].copy_from_slice(&common);
+        data[0x84..0x87].copy_from_slice(&[0x31, 0xc0, 0xc3]); // xor eax, eax; ret
+
+        let generated = make_signature(&data, 0x20, None, DEFAULT_MAX_SIGNATURE_LENGTH)?;
+        assert_eq!(generated.signature, "48 83 EC 28 48");
+        assert_eq!(generated.instruction_count, 2);
+
+        // 十分長いシグネチャではRIP相対の変位と呼び出し先がワイルドカードになる
+        let (full, _) = masked_instructions(&data[0x20..0x31], 0x20, 64, 0..0x200)?;
+        assert_eq!(full.to_string(), "48 83 EC 28 48 8B 05 ?? ?? ?? ?? E8 ?? ?? ?? ?? C3");
+
+        let matches = scan_image(&data, &Signature::parse(&generated.signature)?, None)?;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].address, 0x20);
+        Ok(())
+    }
+}